@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE
+  refresh_tokens (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    family_id VARCHAR(64) NOT NULL,
+    expires_on TIMESTAMPTZ NOT NULL,
+    used_on TIMESTAMPTZ DEFAULT NULL,
+    revoked_on TIMESTAMPTZ DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+
+CREATE INDEX refresh_tokens_family_id_idx ON refresh_tokens (family_id);
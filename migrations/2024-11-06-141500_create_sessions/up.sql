@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE
+  sessions (
+    id SERIAL PRIMARY KEY,
+    jti VARCHAR(64) NOT NULL UNIQUE,
+    user_id INT4 NOT NULL,
+    user_agent VARCHAR(255) DEFAULT NULL,
+    ip_address VARCHAR(45) DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_on TIMESTAMPTZ DEFAULT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
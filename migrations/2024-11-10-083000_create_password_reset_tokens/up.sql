@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE
+  password_reset_tokens (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    expires_on TIMESTAMPTZ NOT NULL,
+    used_on TIMESTAMPTZ DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+ALTER TABLE users
+ADD COLUMN email_verified_at TIMESTAMPTZ DEFAULT NULL;
+
+-- Accounts created before verification existed are treated as verified.
+UPDATE users
+SET
+  email_verified_at = NOW();
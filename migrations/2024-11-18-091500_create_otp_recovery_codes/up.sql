@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE
+  otp_recovery_codes (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    code_hash VARCHAR(80) NOT NULL,
+    used_on TIMESTAMPTZ DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE
+  api_keys (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    prefix VARCHAR(16) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes VARCHAR(255) NOT NULL,
+    expires_on TIMESTAMPTZ DEFAULT NULL,
+    last_used_on TIMESTAMPTZ DEFAULT NULL,
+    revoked_on TIMESTAMPTZ DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
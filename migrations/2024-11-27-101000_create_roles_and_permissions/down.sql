@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users
+DROP CONSTRAINT users_role_fkey;
+
+ALTER TABLE users
+ALTER COLUMN role TYPE VARCHAR(10);
+
+DROP TABLE role_permissions;
+
+DROP TABLE permissions;
+
+DROP TABLE roles;
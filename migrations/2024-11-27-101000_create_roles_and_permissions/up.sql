@@ -0,0 +1,63 @@
+-- Your SQL goes here
+CREATE TABLE
+  roles (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) NOT NULL UNIQUE,
+    description VARCHAR(255) DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW()
+  );
+
+CREATE TABLE
+  permissions (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description VARCHAR(255) DEFAULT NULL
+  );
+
+CREATE TABLE
+  role_permissions (
+    role_id INT4 NOT NULL,
+    permission_id INT4 NOT NULL,
+    PRIMARY KEY (role_id, permission_id),
+    FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE CASCADE,
+    FOREIGN KEY (permission_id) REFERENCES permissions (id) ON DELETE CASCADE
+  );
+
+INSERT INTO
+  roles (name, description)
+VALUES
+  ('admin', 'Full access to the admin dashboard'),
+  ('user', 'Regular account');
+
+INSERT INTO
+  permissions (name, description)
+VALUES
+  ('admin:access', 'Open the admin dashboard'),
+  ('notes:read_all', 'Read the notes of every user'),
+  ('users:read_all', 'Read every user account'),
+  ('roles:manage', 'Create roles and assign them to users');
+
+INSERT INTO
+  role_permissions (role_id, permission_id)
+SELECT
+  roles.id,
+  permissions.id
+FROM
+  roles
+  CROSS JOIN permissions
+WHERE
+  roles.name = 'admin';
+
+ALTER TABLE users
+ALTER COLUMN role TYPE VARCHAR(50);
+
+INSERT INTO
+  roles (name)
+SELECT DISTINCT
+  role
+FROM
+  users
+ON CONFLICT (name) DO NOTHING;
+
+ALTER TABLE users
+ADD CONSTRAINT users_role_fkey FOREIGN KEY (role) REFERENCES roles (name) ON UPDATE CASCADE;
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM permissions
+WHERE
+  name = 'users:manage';
+
+ALTER TABLE users
+DROP COLUMN password_reset_required,
+DROP COLUMN suspended_at;
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+ALTER TABLE users
+ADD COLUMN suspended_at TIMESTAMPTZ DEFAULT NULL,
+ADD COLUMN password_reset_required BOOLEAN NOT NULL DEFAULT FALSE;
+
+INSERT INTO
+  permissions (name, description)
+VALUES
+  (
+    'users:manage',
+    'Suspend, delete and reset the credentials of user accounts'
+  );
+
+INSERT INTO
+  role_permissions (role_id, permission_id)
+SELECT
+  roles.id,
+  permissions.id
+FROM
+  roles
+  CROSS JOIN permissions
+WHERE
+  roles.name = 'admin'
+  AND permissions.name = 'users:manage';
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users
+DROP COLUMN locked_until,
+DROP COLUMN failed_login_count;
+
+DROP TABLE login_attempts;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE
+  login_attempts (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    ip_address VARCHAR(45) DEFAULT NULL,
+    succeeded BOOLEAN NOT NULL,
+    attempted_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+
+CREATE INDEX login_attempts_user_id_attempted_on_idx ON login_attempts (user_id, attempted_on);
+
+ALTER TABLE users
+ADD COLUMN failed_login_count INT4 NOT NULL DEFAULT 0,
+ADD COLUMN locked_until TIMESTAMPTZ DEFAULT NULL;
@@ -0,0 +1,37 @@
+-- Your SQL goes here
+CREATE TABLE
+  audit_events (
+    id SERIAL PRIMARY KEY,
+    actor_id INT4 DEFAULT NULL,
+    action VARCHAR(100) NOT NULL,
+    target_type VARCHAR(50) DEFAULT NULL,
+    target_id VARCHAR(100) DEFAULT NULL,
+    ip_address VARCHAR(45) DEFAULT NULL,
+    user_agent VARCHAR(255) DEFAULT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (actor_id) REFERENCES users (id) ON DELETE SET NULL
+  );
+
+CREATE INDEX audit_events_actor_id_idx ON audit_events (actor_id);
+
+CREATE INDEX audit_events_action_idx ON audit_events (action);
+
+CREATE INDEX audit_events_created_on_idx ON audit_events (created_on);
+
+INSERT INTO
+  permissions (name, description)
+VALUES
+  ('audit:read', 'Read and export the audit log');
+
+INSERT INTO
+  role_permissions (role_id, permission_id)
+SELECT
+  roles.id,
+  permissions.id
+FROM
+  roles
+  CROSS JOIN permissions
+WHERE
+  roles.name = 'admin'
+  AND permissions.name = 'audit:read';
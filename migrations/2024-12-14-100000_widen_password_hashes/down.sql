@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+-- Intentionally a no-op: Argon2 hashes are longer than 80 characters, so
+-- narrowing the columns back would fail or truncate them, and a unique
+-- constraint on password hashes must not come back.
+SELECT 1;
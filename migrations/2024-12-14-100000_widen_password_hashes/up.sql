@@ -0,0 +1,9 @@
+-- Your SQL goes here
+ALTER TABLE users
+DROP CONSTRAINT users_password_key;
+
+ALTER TABLE users
+ALTER COLUMN password TYPE VARCHAR(255);
+
+ALTER TABLE otp_recovery_codes
+ALTER COLUMN code_hash TYPE VARCHAR(255);
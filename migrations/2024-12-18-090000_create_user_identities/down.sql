@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE oidc_login_states;
+
+DROP TABLE user_identities;
+
+DELETE FROM users
+WHERE
+  password IS NULL;
+
+ALTER TABLE users
+ALTER COLUMN password SET NOT NULL;
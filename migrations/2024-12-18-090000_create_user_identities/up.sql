@@ -0,0 +1,30 @@
+-- Your SQL goes here
+ALTER TABLE users
+ALTER COLUMN password DROP NOT NULL;
+
+CREATE TABLE
+  user_identities (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    provider VARCHAR(50) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    email VARCHAR(100) DEFAULT NULL,
+    last_login_on TIMESTAMPTZ DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, subject),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+
+CREATE INDEX user_identities_user_id_idx ON user_identities (user_id);
+
+CREATE TABLE
+  oidc_login_states (
+    id SERIAL PRIMARY KEY,
+    state_hash VARCHAR(64) NOT NULL UNIQUE,
+    nonce VARCHAR(64) NOT NULL,
+    code_verifier VARCHAR(128) NOT NULL,
+    user_id INT4 DEFAULT NULL,
+    expires_on TIMESTAMPTZ NOT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TABLE
+  tags (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, name),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+
+CREATE TABLE
+  note_tags (
+    note_id INT4 NOT NULL,
+    tag_id INT4 NOT NULL,
+    PRIMARY KEY (note_id, tag_id),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+  );
+
+CREATE INDEX note_tags_tag_id_idx ON note_tags (tag_id);
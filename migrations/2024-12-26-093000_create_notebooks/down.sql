@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE notes
+DROP COLUMN notebook_id;
+
+DROP TABLE notebooks;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TABLE
+  notebooks (
+    id SERIAL PRIMARY KEY,
+    user_id INT4 NOT NULL,
+    parent_id INT4 DEFAULT NULL,
+    name VARCHAR(100) NOT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (parent_id) REFERENCES notebooks (id) ON DELETE CASCADE
+  );
+
+CREATE INDEX notebooks_user_id_idx ON notebooks (user_id);
+
+ALTER TABLE notes
+ADD COLUMN notebook_id INT4 DEFAULT NULL REFERENCES notebooks (id) ON DELETE SET NULL;
+
+CREATE INDEX notes_notebook_id_idx ON notes (notebook_id);
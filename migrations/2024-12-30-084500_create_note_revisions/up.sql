@@ -0,0 +1,27 @@
+-- Your SQL goes here
+CREATE TABLE
+  note_revisions (
+    id SERIAL PRIMARY KEY,
+    note_id INT4 NOT NULL,
+    revision INT4 NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    content TEXT NOT NULL,
+    edited_by INT4 DEFAULT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (note_id, revision),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+    FOREIGN KEY (edited_by) REFERENCES users (id) ON DELETE SET NULL
+  );
+
+-- Existing notes start their history at their current text.
+INSERT INTO
+  note_revisions (note_id, revision, title, content, edited_by, created_on)
+SELECT
+  id,
+  1,
+  title,
+  content,
+  created_by,
+  COALESCE(updated_on, created_on, NOW())
+FROM
+  notes;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE notes
+ADD COLUMN deleted_at TIMESTAMPTZ DEFAULT NULL;
+
+CREATE INDEX notes_deleted_at_idx ON notes (deleted_at)
+WHERE
+  deleted_at IS NOT NULL;
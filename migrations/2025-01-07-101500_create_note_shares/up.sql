@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE
+  note_shares (
+    note_id INT4 NOT NULL,
+    user_id INT4 NOT NULL,
+    permission VARCHAR(10) NOT NULL CHECK (permission IN ('read', 'edit')),
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (note_id, user_id),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+
+CREATE INDEX note_shares_user_id_idx ON note_shares (user_id);
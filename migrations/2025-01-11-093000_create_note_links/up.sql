@@ -0,0 +1,17 @@
+-- Your SQL goes here
+CREATE TABLE
+  note_links (
+    id SERIAL PRIMARY KEY,
+    note_id INT4 NOT NULL,
+    slug VARCHAR(64) NOT NULL UNIQUE,
+    password_hash VARCHAR(255) DEFAULT NULL,
+    expires_at TIMESTAMPTZ DEFAULT NULL,
+    revoked_at TIMESTAMPTZ DEFAULT NULL,
+    view_count INT8 NOT NULL DEFAULT 0,
+    created_by INT4 NOT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (note_id) REFERENCES notes (id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE CASCADE
+  );
+
+CREATE INDEX note_links_note_id_idx ON note_links (note_id);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- search_vector is not listed in src/schema.rs so that notes::all_columns keeps
+-- matching the Note model; it is only read through raw SQL fragments.
+ALTER TABLE notes
+ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('english', title), 'A') || setweight(to_tsvector('english', content), 'B')
+) STORED;
+
+CREATE INDEX notes_search_vector_idx ON notes USING GIN (search_vector);
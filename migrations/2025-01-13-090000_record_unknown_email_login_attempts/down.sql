@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX login_attempts_email_attempted_on_idx;
+
+DELETE FROM login_attempts
+WHERE
+  user_id IS NULL;
+
+ALTER TABLE login_attempts
+DROP COLUMN email,
+ALTER COLUMN user_id SET NOT NULL;
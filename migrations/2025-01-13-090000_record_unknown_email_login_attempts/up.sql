@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- Failed logins for emails without an account are recorded by email, so they
+-- can be throttled exactly like logins for existing accounts.
+ALTER TABLE login_attempts
+ALTER COLUMN user_id DROP NOT NULL,
+ADD COLUMN email VARCHAR(255) DEFAULT NULL;
+
+CREATE INDEX login_attempts_email_attempted_on_idx ON login_attempts (email, attempted_on)
+WHERE
+  email IS NOT NULL;
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX login_attempts_attempted_on_idx;
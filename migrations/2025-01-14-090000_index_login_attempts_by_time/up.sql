@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- Lets old login attempts be pruned without scanning the whole table.
+CREATE INDEX login_attempts_attempted_on_idx ON login_attempts (attempted_on);
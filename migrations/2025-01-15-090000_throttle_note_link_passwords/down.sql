@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE note_links
+DROP COLUMN last_failed_password_at,
+DROP COLUMN failed_password_count;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- Consecutive wrong passwords for a link, so guessing can be slowed down.
+ALTER TABLE note_links
+ADD COLUMN failed_password_count INT4 NOT NULL DEFAULT 0,
+ADD COLUMN last_failed_password_at TIMESTAMPTZ DEFAULT NULL;
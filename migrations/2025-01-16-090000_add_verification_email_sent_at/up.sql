@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- When a verification email was last requested without signing in, so the
+-- resend endpoint cannot be used to flood an inbox.
+ALTER TABLE users
+ADD COLUMN verification_email_sent_at TIMESTAMPTZ DEFAULT NULL;
@@ -0,0 +1,67 @@
+use super::insertables::NewAuditEvent;
+use super::messages::*;
+use crate::models::AuditEvent;
+use crate::schema::audit_events;
+use crate::utils::db::{DbActor, DbResult};
+use actix::Handler;
+use diesel::{pg::Pg, prelude::*};
+
+impl Handler<CreateAuditEvent> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: CreateAuditEvent, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::insert_into(audit_events::table)
+            .values(NewAuditEvent {
+                actor_id: msg.actor_id,
+                action: msg.action,
+                target_type: msg.target_type,
+                target_id: msg.target_id,
+                ip_address: msg.ip_address,
+                user_agent: msg.user_agent,
+                details: msg.details,
+            })
+            .execute(&mut connection)?)
+    }
+}
+
+impl Handler<FetchAuditEvents> for DbActor {
+    type Result = DbResult<(i64, Vec<AuditEvent>)>;
+
+    fn handle(&mut self, msg: FetchAuditEvents, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let filtered = || {
+            let mut query = audit_events::table.into_boxed::<Pg>();
+
+            if let Some(actor_id) = msg.actor_id {
+                query = query.filter(audit_events::actor_id.eq(actor_id));
+            }
+
+            if let Some(action) = &msg.action {
+                query = query.filter(audit_events::action.eq(action.clone()));
+            }
+
+            if let Some(from) = msg.from {
+                query = query.filter(audit_events::created_on.ge(from));
+            }
+
+            if let Some(to) = msg.to {
+                query = query.filter(audit_events::created_on.lt(to));
+            }
+
+            query
+        };
+
+        let total_events: i64 = filtered().count().get_result::<i64>(&mut connection)?;
+
+        let events: Vec<AuditEvent> = filtered()
+            .order(audit_events::created_on.desc())
+            .limit(msg.limit)
+            .offset(msg.offset)
+            .get_results::<AuditEvent>(&mut connection)?;
+
+        Ok((total_events, events))
+    }
+}
@@ -0,0 +1,209 @@
+use super::messages::*;
+use crate::{
+    handlers::auth_handlers::session_handlers::{client_ip, client_user_agent},
+    middlewares::auth_middlewares::require_permission,
+    models::AuditEvent,
+    utils::{
+        db::{AppState, DbActor},
+        errors::ApiError,
+        jwt::Claims,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web::{Data, Query},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of rows written to a single CSV export.
+const CSV_EXPORT_LIMIT: i64 = 10_000;
+
+/// Id of the authenticated user making the request, if any.
+pub fn request_actor_id(req: &HttpRequest) -> Option<i32> {
+    req.extensions().get::<Claims>().map(|claims| claims.id)
+}
+
+/// Records who did what to which resource. Failing to write the audit event is
+/// logged but never fails the request that triggered it.
+pub async fn record_audit_event(
+    db: &Addr<DbActor>,
+    req: &HttpRequest,
+    actor_id: Option<i32>,
+    action: &str,
+    target: Option<(&str, String)>,
+    details: serde_json::Value,
+) {
+    let (target_type, target_id) = match target {
+        Some((target_type, target_id)) => (Some(target_type.to_string()), Some(target_id)),
+        None => (None, None),
+    };
+
+    let result = db
+        .send(CreateAuditEvent {
+            actor_id,
+            action: action.to_string(),
+            target_type,
+            target_id,
+            ip_address: client_ip(req),
+            user_agent: client_user_agent(req),
+            details,
+        })
+        .await;
+
+    if !matches!(result, Ok(Ok(_))) {
+        eprintln!("Audit: unable to record {} event", action);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    user_id: Option<i32>,
+    action: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    format: Option<String>,
+    limit: Option<i64>,
+    page: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct AuditEventsResponse {
+    total_events: i64,
+    number_of_page: i64,
+    page: i64,
+    events: Vec<AuditEvent>,
+}
+
+fn csv_field(value: &str) -> String {
+    let value: String = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn events_to_csv(events: &[AuditEvent]) -> String {
+    let mut csv: String = String::from(
+        "id,created_on,actor_id,action,target_type,target_id,ip_address,user_agent,details\n",
+    );
+
+    for event in events {
+        let fields: [String; 9] = [
+            event.id.to_string(),
+            event.created_on.to_rfc3339(),
+            event.actor_id.map(|id| id.to_string()).unwrap_or_default(),
+            event.action.clone(),
+            event.target_type.clone().unwrap_or_default(),
+            event.target_id.clone().unwrap_or_default(),
+            event.ip_address.clone().unwrap_or_default(),
+            event.user_agent.clone().unwrap_or_default(),
+            event.details.to_string(),
+        ];
+
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/audit",
+    params(
+        ("user_id" = Option<i32>, Query, description = "Only events performed by this user."),
+        ("action" = Option<String>, Query, description = "Only events with this action (example: user.login)."),
+        ("from" = Option<String>, Query, description = "Only events at or after this RFC 3339 timestamp."),
+        ("to" = Option<String>, Query, description = "Only events before this RFC 3339 timestamp."),
+        ("format" = Option<String>, Query, description = "json (default) or csv. CSV exports ignore pagination and contain up to 10000 events."),
+        ("page" = Option<i64>, Query, description = "Page number for pagination (default: 1)."),
+        ("limit" = Option<i64>, Query, description = "Limit of events per page (default: 50)."),
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved the matching audit events, newest first."),
+        (status = 400, description = "Invalid format, page or limit."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the audit:read permission."),
+        (status = 500, description = "Internal Server Error: Unable to retrieve audit events."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/audit", wrap = "require_permission(\"audit:read\")")]
+pub async fn fetch_audit_events(
+    state: Data<AppState>,
+    query: Query<AuditQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let as_csv: bool = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(_) => {
+            return Err(ApiError::BadRequest(String::from(
+                "format must be json or csv",
+            )))
+        }
+    };
+
+    let limit: i64 = query.limit.unwrap_or(50);
+    let page: i64 = query.page.unwrap_or(1);
+
+    if !(1..=500).contains(&limit) || page < 1 {
+        return Err(ApiError::BadRequest(String::from(
+            "limit must be between 1 and 500 and page must be positive",
+        )));
+    }
+
+    let (limit, offset): (i64, i64) = if as_csv {
+        (CSV_EXPORT_LIMIT, 0)
+    } else {
+        let offset: i64 = (page - 1)
+            .checked_mul(limit)
+            .ok_or_else(|| ApiError::BadRequest(String::from("page is too large")))?;
+
+        (limit, offset)
+    };
+
+    let (total_events, events) = db
+        .send(FetchAuditEvents {
+            actor_id: query.user_id,
+            action: query.action.clone(),
+            from: query.from,
+            to: query.to,
+            limit,
+            offset,
+        })
+        .await??;
+
+    if as_csv {
+        return Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![DispositionParam::Filename(format!(
+                    "audit-{}.csv",
+                    Utc::now().format("%Y%m%d%H%M%S")
+                ))],
+            })
+            .body(events_to_csv(&events)));
+    }
+
+    Ok(HttpResponse::Ok().json(AuditEventsResponse {
+        total_events,
+        number_of_page: (total_events as f64 / limit as f64).ceil() as i64,
+        page,
+        events,
+    }))
+}
@@ -0,0 +1,15 @@
+use crate::schema::audit_events;
+use diesel::Insertable;
+use serde::Serialize;
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=audit_events)]
+pub struct NewAuditEvent {
+    pub actor_id: Option<i32>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+}
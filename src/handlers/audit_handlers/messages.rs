@@ -0,0 +1,27 @@
+use crate::models::AuditEvent;
+use crate::utils::db::DbResult;
+use actix::Message;
+use chrono::{DateTime, Utc};
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct CreateAuditEvent {
+    pub actor_id: Option<i32>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<(i64, Vec<AuditEvent>)>")]
+pub struct FetchAuditEvents {
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: i64,
+    pub offset: i64,
+}
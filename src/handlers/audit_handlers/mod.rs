@@ -0,0 +1,4 @@
+pub mod actors;
+pub mod audit_handlers;
+pub mod insertables;
+pub mod messages;
@@ -1,116 +1,1065 @@
-use super::insertables::{NewUser, OTPInfoInsertable};
+use super::insertables::{
+    NewApiKey, NewLoginAttempt, NewOTPRecoveryCode, NewOidcLoginState, NewPasswordResetToken,
+    NewRefreshToken, NewSession, NewUser, NewUserIdentity, OTPInfoInsertable,
+};
+use super::lockout_handlers::{
+    BACKOFF_THRESHOLD, BACKOFF_WINDOW_MINUTES, LOCKOUT_MINUTES, LOCKOUT_THRESHOLD,
+    LOGIN_ATTEMPT_RETENTION_DAYS, MAX_BACKOFF_SECONDS,
+};
 use super::messages::*;
-use crate::models::User;
+use crate::models::{
+    ApiKey, LoginAttempt, OTPRecoveryCode, OidcLoginState, RefreshToken, Session, User,
+    UserIdentity,
+};
 use crate::schema::users::dsl::*;
-use crate::utils::db::DbActor;
+use crate::schema::{
+    api_keys, login_attempts, oidc_login_states, otp_recovery_codes, password_reset_tokens,
+    refresh_tokens, sessions, user_identities,
+};
+use crate::utils::db::{DbActor, DbResult};
 use actix::Handler;
-use diesel::prelude::*;
+use chrono::{DateTime, Duration, Utc};
+use diesel::{dsl::count_star, prelude::*};
+use rand::Rng;
+
+fn revoke_sessions_by_jti(connection: &mut PgConnection, jtis: Vec<String>) -> QueryResult<usize> {
+    let now: DateTime<Utc> = Utc::now();
+
+    diesel::update(
+        refresh_tokens::table
+            .filter(refresh_tokens::family_id.eq_any(&jtis))
+            .filter(refresh_tokens::revoked_on.is_null()),
+    )
+    .set(refresh_tokens::revoked_on.eq(now))
+    .execute(connection)?;
+
+    diesel::update(
+        sessions::table
+            .filter(sessions::jti.eq_any(&jtis))
+            .filter(sessions::revoked_on.is_null()),
+    )
+    .set(sessions::revoked_on.eq(now))
+    .execute(connection)
+}
 
 impl Handler<FetchUser> for DbActor {
-    type Result = QueryResult<Vec<User>>;
+    type Result = DbResult<Vec<User>>;
 
     fn handle(&mut self, _msg: FetchUser, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Fetch User: Unable to establish connection");
+        let mut connection = self.0.get()?;
+
+        Ok(users.get_results::<User>(&mut connection)?)
+    }
+}
+
+impl Handler<FetchUserById> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: FetchUserById, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
 
-        users.get_results::<User>(&mut connection)
+        Ok(users.find(msg.user_id).first::<User>(&mut connection)?)
+    }
+}
+
+fn new_user_role(connection: &mut PgConnection) -> QueryResult<String> {
+    let user_count: i64 = users.count().get_result::<i64>(connection)?;
+
+    if user_count == 0 {
+        Ok(String::from("admin"))
+    } else {
+        Ok(String::from("user"))
     }
 }
 
 impl Handler<CreateUser> for DbActor {
-    type Result = QueryResult<User>;
+    type Result = DbResult<User>;
 
     fn handle(&mut self, msg: CreateUser, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Create User: Unable to establish connection");
-
-        let user_count: i64 = users
-            .count()
-            .get_result::<i64>(&mut connection)
-            .expect("Unable to get user count");
-
-        let user_role: String = if user_count == 0 {
-            String::from("admin")
-        } else {
-            String::from("user")
-        };
+        let mut connection = self.0.get()?;
 
         let new_user: NewUser = NewUser {
             username: msg.username,
             email: msg.email,
-            password: msg.password,
-            role: user_role,
+            password: Some(msg.password),
+            role: new_user_role(&mut connection)?,
+            email_verified_at: None,
         };
 
-        diesel::insert_into(users)
+        Ok(diesel::insert_into(users)
             .values(new_user)
-            .get_result::<User>(&mut connection)
+            .get_result::<User>(&mut connection)?)
     }
 }
 
 impl Handler<LoginAndGetUser> for DbActor {
-    type Result = QueryResult<User>;
+    type Result = DbResult<User>;
 
     fn handle(&mut self, msg: LoginAndGetUser, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Login And Get User: Unable to establish connection");
+        let mut connection = self.0.get()?;
 
-        users
+        Ok(users
+            .filter(email.eq(&msg.email))
+            .first::<User>(&mut connection)?)
+    }
+}
+
+impl Handler<FindUserByEmail> for DbActor {
+    type Result = DbResult<Option<User>>;
+
+    fn handle(&mut self, msg: FindUserByEmail, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(users
             .filter(email.eq(&msg.email))
             .first::<User>(&mut connection)
+            .optional()?)
+    }
+}
+
+impl Handler<StampVerificationEmailSent> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(
+        &mut self,
+        msg: StampVerificationEmailSent,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(
+            users
+                .find(msg.user_id)
+                .filter(email_verified_at.is_null())
+                .filter(
+                    verification_email_sent_at
+                        .is_null()
+                        .or(verification_email_sent_at.lt(msg.not_sent_since)),
+                ),
+        )
+        .set(verification_email_sent_at.eq(Utc::now()))
+        .execute(&mut connection)?)
     }
 }
 
 impl Handler<UpdateUserPassword> for DbActor {
-    type Result = Result<usize, diesel::result::Error>;
+    type Result = DbResult<usize>;
 
     fn handle(&mut self, msg: UpdateUserPassword, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Update User Password: Unable to establish connection");
+        let mut connection = self.0.get()?;
 
-        diesel::update(users.filter(id.eq(msg.user_id)))
+        Ok(diesel::update(users.filter(id.eq(msg.user_id)))
             .set(password.eq(msg.new_password))
-            .execute(&mut connection)
+            .execute(&mut connection)?)
     }
 }
 
 impl Handler<DeleteUser> for DbActor {
-    type Result = Result<usize, diesel::result::Error>;
+    type Result = DbResult<usize>;
 
     fn handle(&mut self, msg: DeleteUser, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Delete User: Unable to establish connection");
+        let mut connection = self.0.get()?;
 
-        diesel::delete(users.filter(id.eq(msg.user_id))).execute(&mut connection)
+        Ok(diesel::delete(users.filter(id.eq(msg.user_id))).execute(&mut connection)?)
     }
 }
 
 impl Handler<OTPMessage> for DbActor {
-    type Result = QueryResult<User>;
+    type Result = DbResult<User>;
 
     fn handle(&mut self, msg: OTPMessage, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("OTP Message: Unable to establish connection");
+        let mut connection = self.0.get()?;
 
-        diesel::update(users.filter(email.eq(&msg.email)))
+        Ok(diesel::update(users.filter(email.eq(&msg.email)))
             .set(OTPInfoInsertable {
                 otp_verified: msg.otp_verified,
                 otp_enabled: msg.otp_enabled,
                 otp_base32: msg.otp_base32,
                 otp_auth_url: msg.otp_auth_url,
             })
-            .get_result::<User>(&mut connection)
+            .get_result::<User>(&mut connection)?)
+    }
+}
+
+impl Handler<SetUserSuspended> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: SetUserSuspended, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let suspended_on: Option<DateTime<Utc>> = msg.suspended.then(Utc::now);
+
+            let user: User = diesel::update(users.find(msg.user_id))
+                .set(suspended_at.eq(suspended_on))
+                .get_result::<User>(connection)?;
+
+            if msg.suspended {
+                let jtis: Vec<String> = sessions::table
+                    .filter(sessions::user_id.eq(user.id))
+                    .filter(sessions::revoked_on.is_null())
+                    .select(sessions::jti)
+                    .get_results::<String>(connection)?;
+
+                revoke_sessions_by_jti(connection, jtis)?;
+            }
+
+            Ok(user)
+        })
+    }
+}
+
+impl Handler<RequirePasswordReset> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: RequirePasswordReset, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let user: User = diesel::update(users.find(msg.user_id))
+                .set(password_reset_required.eq(true))
+                .get_result::<User>(connection)?;
+
+            let jtis: Vec<String> = sessions::table
+                .filter(sessions::user_id.eq(user.id))
+                .filter(sessions::revoked_on.is_null())
+                .select(sessions::jti)
+                .get_results::<String>(connection)?;
+
+            revoke_sessions_by_jti(connection, jtis)?;
+
+            Ok(user)
+        })
+    }
+}
+
+impl Handler<ResetUserOTP> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: ResetUserOTP, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection.transaction(|connection| {
+            diesel::delete(
+                otp_recovery_codes::table.filter(otp_recovery_codes::user_id.eq(msg.user_id)),
+            )
+            .execute(connection)?;
+
+            diesel::update(users.find(msg.user_id))
+                .set(OTPInfoInsertable {
+                    otp_verified: false,
+                    otp_enabled: false,
+                    otp_base32: Some(String::new()),
+                    otp_auth_url: Some(String::new()),
+                })
+                .get_result::<User>(connection)
+        })?)
+    }
+}
+
+/// Deletes login attempts that no longer count towards any throttle, and
+/// account attempts older than `LOGIN_ATTEMPT_RETENTION_DAYS`.
+fn prune_login_attempts(connection: &mut PgConnection) -> QueryResult<usize> {
+    let now: DateTime<Utc> = Utc::now();
+    let throttle_window: Duration = Duration::minutes(LOCKOUT_MINUTES.max(BACKOFF_WINDOW_MINUTES));
+
+    diesel::delete(
+        login_attempts::table
+            .filter(login_attempts::user_id.is_null())
+            .filter(login_attempts::attempted_on.lt(now - throttle_window)),
+    )
+    .execute(connection)?;
+
+    diesel::delete(login_attempts::table.filter(
+        login_attempts::attempted_on.lt(now - Duration::days(LOGIN_ATTEMPT_RETENTION_DAYS)),
+    ))
+    .execute(connection)
+}
+
+/// When the IP behind `recent_attempts`, newest first, may try again, if its
+/// consecutive failures put it into backoff.
+fn backoff_until(
+    recent_attempts: &[(bool, DateTime<Utc>)],
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let consecutive_failures: usize = recent_attempts
+        .iter()
+        .take_while(|(succeeded, _)| !succeeded)
+        .count();
+
+    if consecutive_failures < BACKOFF_THRESHOLD {
+        return None;
+    }
+
+    let exponent: u32 = (consecutive_failures - BACKOFF_THRESHOLD).min(16) as u32;
+    let delay: Duration = Duration::seconds((1_i64 << exponent).min(MAX_BACKOFF_SECONDS));
+    let retry_on: DateTime<Utc> = recent_attempts[0].1 + delay;
+
+    (retry_on > now).then_some(retry_on)
+}
+
+impl Handler<CheckLoginThrottle> for DbActor {
+    type Result = DbResult<Option<DateTime<Utc>>>;
+
+    fn handle(&mut self, msg: CheckLoginThrottle, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let now: DateTime<Utc> = Utc::now();
+
+        let user_locked_until: Option<DateTime<Utc>> = users
+            .find(msg.user_id)
+            .select(locked_until)
+            .first::<Option<DateTime<Utc>>>(&mut connection)?;
+
+        if let Some(user_locked_until) = user_locked_until.filter(|until| *until > now) {
+            return Ok(Some(user_locked_until));
+        }
+
+        let recent_attempts: Vec<(bool, DateTime<Utc>)> = login_attempts::table
+            .filter(login_attempts::user_id.eq(msg.user_id))
+            .filter(login_attempts::ip_address.is_not_distinct_from(&msg.ip_address))
+            .filter(
+                login_attempts::attempted_on.gt(now - Duration::minutes(BACKOFF_WINDOW_MINUTES)),
+            )
+            .order(login_attempts::attempted_on.desc())
+            .select((login_attempts::succeeded, login_attempts::attempted_on))
+            .limit(50)
+            .get_results::<(bool, DateTime<Utc>)>(&mut connection)?;
+
+        Ok(backoff_until(&recent_attempts, now))
+    }
+}
+
+impl Handler<CheckUnknownLoginThrottle> for DbActor {
+    type Result = DbResult<Option<DateTime<Utc>>>;
+
+    fn handle(&mut self, msg: CheckUnknownLoginThrottle, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let now: DateTime<Utc> = Utc::now();
+
+        // Attempts are not recorded while locked, so the lockout ends
+        // LOCKOUT_MINUTES after the failure that reached the threshold.
+        let (failures, last_failure): (i64, Option<DateTime<Utc>>) = login_attempts::table
+            .filter(login_attempts::email.eq(&msg.email))
+            .filter(login_attempts::attempted_on.gt(now - Duration::minutes(LOCKOUT_MINUTES)))
+            .select((count_star(), diesel::dsl::max(login_attempts::attempted_on)))
+            .first::<(i64, Option<DateTime<Utc>>)>(&mut connection)?;
+
+        if let Some(last_failure) = last_failure.filter(|_| failures >= LOCKOUT_THRESHOLD as i64) {
+            return Ok(Some(last_failure + Duration::minutes(LOCKOUT_MINUTES)));
+        }
+
+        let recent_attempts: Vec<(bool, DateTime<Utc>)> = login_attempts::table
+            .filter(login_attempts::email.eq(&msg.email))
+            .filter(login_attempts::ip_address.is_not_distinct_from(&msg.ip_address))
+            .filter(
+                login_attempts::attempted_on.gt(now - Duration::minutes(BACKOFF_WINDOW_MINUTES)),
+            )
+            .order(login_attempts::attempted_on.desc())
+            .select((login_attempts::succeeded, login_attempts::attempted_on))
+            .limit(50)
+            .get_results::<(bool, DateTime<Utc>)>(&mut connection)?;
+
+        Ok(backoff_until(&recent_attempts, now))
+    }
+}
+
+impl Handler<RecordUnknownLoginAttempt> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: RecordUnknownLoginAttempt, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        prune_login_attempts(&mut connection)?;
+
+        Ok(diesel::insert_into(login_attempts::table)
+            .values(NewLoginAttempt {
+                user_id: None,
+                ip_address: msg.ip_address,
+                succeeded: false,
+                email: Some(msg.email),
+            })
+            .execute(&mut connection)?)
+    }
+}
+
+impl Handler<RecordLoginAttempt> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: RecordLoginAttempt, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection.transaction(|connection| {
+            prune_login_attempts(connection)?;
+
+            diesel::insert_into(login_attempts::table)
+                .values(NewLoginAttempt {
+                    user_id: Some(msg.user_id),
+                    ip_address: msg.ip_address,
+                    succeeded: msg.succeeded,
+                    email: None,
+                })
+                .execute(connection)?;
+
+            if msg.succeeded && msg.second_factor_pending {
+                return users.find(msg.user_id).first::<User>(connection);
+            }
+
+            if msg.succeeded {
+                return diesel::update(users.find(msg.user_id))
+                    .set((
+                        failed_login_count.eq(0),
+                        locked_until.eq(None::<DateTime<Utc>>),
+                    ))
+                    .get_result::<User>(connection);
+            }
+
+            let user: User = diesel::update(users.find(msg.user_id))
+                .set(failed_login_count.eq(failed_login_count + 1))
+                .get_result::<User>(connection)?;
+
+            if user.failed_login_count < LOCKOUT_THRESHOLD {
+                return Ok(user);
+            }
+
+            diesel::update(users.find(msg.user_id))
+                .set((
+                    failed_login_count.eq(0),
+                    locked_until.eq(Utc::now() + Duration::minutes(LOCKOUT_MINUTES)),
+                ))
+                .get_result::<User>(connection)
+        })?)
+    }
+}
+
+impl Handler<FetchLoginAttempts> for DbActor {
+    type Result = DbResult<Vec<LoginAttempt>>;
+
+    fn handle(&mut self, msg: FetchLoginAttempts, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(login_attempts::table
+            .filter(login_attempts::user_id.eq(msg.user_id))
+            .order(login_attempts::attempted_on.desc())
+            .limit(50)
+            .get_results::<LoginAttempt>(&mut connection)?)
+    }
+}
+
+impl Handler<UnlockUser> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: UnlockUser, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(users.find(msg.user_id))
+            .set((
+                failed_login_count.eq(0),
+                locked_until.eq(None::<DateTime<Utc>>),
+            ))
+            .get_result::<User>(&mut connection)?)
+    }
+}
+
+impl Handler<CreateRefreshToken> for DbActor {
+    type Result = DbResult<RefreshToken>;
+
+    fn handle(&mut self, msg: CreateRefreshToken, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::insert_into(refresh_tokens::table)
+            .values(NewRefreshToken {
+                user_id: msg.user_id,
+                token_hash: msg.token_hash,
+                family_id: msg.family_id,
+                expires_on: msg.expires_on,
+            })
+            .get_result::<RefreshToken>(&mut connection)?)
+    }
+}
+
+impl Handler<RotateRefreshToken> for DbActor {
+    type Result = DbResult<RefreshOutcome>;
+
+    fn handle(&mut self, msg: RotateRefreshToken, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let now: DateTime<Utc> = Utc::now();
+
+            let existing_token: Option<RefreshToken> = refresh_tokens::table
+                .filter(refresh_tokens::token_hash.eq(&msg.token_hash))
+                .for_update()
+                .first::<RefreshToken>(connection)
+                .optional()?;
+
+            let Some(existing_token) = existing_token else {
+                return Ok(RefreshOutcome::Invalid);
+            };
+
+            if existing_token.used_on.is_some() {
+                revoke_sessions_by_jti(connection, vec![existing_token.family_id])?;
+
+                return Ok(RefreshOutcome::Reused);
+            }
+
+            if existing_token.revoked_on.is_some() {
+                return Ok(RefreshOutcome::Invalid);
+            }
+
+            if existing_token.expires_on < now {
+                return Ok(RefreshOutcome::Expired);
+            }
+
+            diesel::update(refresh_tokens::table.find(existing_token.id))
+                .set(refresh_tokens::used_on.eq(now))
+                .execute(connection)?;
+
+            diesel::insert_into(refresh_tokens::table)
+                .values(NewRefreshToken {
+                    user_id: existing_token.user_id,
+                    token_hash: msg.new_token_hash,
+                    family_id: existing_token.family_id.clone(),
+                    expires_on: msg.expires_on,
+                })
+                .execute(connection)?;
+
+            let user: User = users
+                .find(existing_token.user_id)
+                .first::<User>(connection)?;
+
+            Ok(RefreshOutcome::Rotated(
+                Box::new(user),
+                existing_token.family_id,
+            ))
+        })
+    }
+}
+
+impl Handler<CreateSession> for DbActor {
+    type Result = DbResult<Session>;
+
+    fn handle(&mut self, msg: CreateSession, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::insert_into(sessions::table)
+            .values(NewSession {
+                jti: msg.jti,
+                user_id: msg.user_id,
+                user_agent: msg.user_agent,
+                ip_address: msg.ip_address,
+            })
+            .get_result::<Session>(&mut connection)?)
+    }
+}
+
+impl Handler<TouchSession> for DbActor {
+    type Result = DbResult<Option<(Session, User)>>;
+
+    fn handle(&mut self, msg: TouchSession, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let session: Option<Session> = diesel::update(
+            sessions::table
+                .filter(sessions::jti.eq(&msg.jti))
+                .filter(sessions::revoked_on.is_null()),
+        )
+        .set(sessions::last_seen_on.eq(Utc::now()))
+        .get_result::<Session>(&mut connection)
+        .optional()?;
+
+        match session {
+            Some(session) => {
+                let user: User = users.find(session.user_id).first::<User>(&mut connection)?;
+                Ok(Some((session, user)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Handler<FetchUserSessions> for DbActor {
+    type Result = DbResult<Vec<Session>>;
+
+    fn handle(&mut self, msg: FetchUserSessions, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(sessions::table
+            .filter(sessions::user_id.eq(msg.user_id))
+            .filter(sessions::revoked_on.is_null())
+            .order(sessions::last_seen_on.desc())
+            .get_results::<Session>(&mut connection)?)
+    }
+}
+
+impl Handler<RevokeSession> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: RevokeSession, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection.transaction(|connection| {
+            let jtis: Vec<String> = sessions::table
+                .filter(sessions::id.eq(msg.session_id))
+                .filter(sessions::user_id.eq(msg.user_id))
+                .select(sessions::jti)
+                .get_results::<String>(connection)?;
+
+            revoke_sessions_by_jti(connection, jtis)
+        })?)
+    }
+}
+
+impl Handler<RevokeSessionByJti> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: RevokeSessionByJti, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection
+            .transaction(|connection| revoke_sessions_by_jti(connection, vec![msg.jti]))?)
+    }
+}
+
+impl Handler<RevokeUserSessions> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: RevokeUserSessions, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection.transaction(|connection| {
+            let jtis: Vec<String> = sessions::table
+                .filter(sessions::user_id.eq(msg.user_id))
+                .filter(sessions::revoked_on.is_null())
+                .select(sessions::jti)
+                .get_results::<String>(connection)?;
+
+            revoke_sessions_by_jti(connection, jtis)
+        })?)
+    }
+}
+
+impl Handler<CreatePasswordResetToken> for DbActor {
+    type Result = DbResult<Option<User>>;
+
+    fn handle(&mut self, msg: CreatePasswordResetToken, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let user: Option<User> = users
+                .filter(email.eq(&msg.email))
+                .first::<User>(connection)
+                .optional()?;
+
+            let Some(user) = user else {
+                return Ok(None);
+            };
+
+            diesel::update(
+                password_reset_tokens::table
+                    .filter(password_reset_tokens::user_id.eq(user.id))
+                    .filter(password_reset_tokens::used_on.is_null()),
+            )
+            .set(password_reset_tokens::used_on.eq(Utc::now()))
+            .execute(connection)?;
+
+            diesel::insert_into(password_reset_tokens::table)
+                .values(NewPasswordResetToken {
+                    user_id: user.id,
+                    token_hash: msg.token_hash,
+                    expires_on: msg.expires_on,
+                })
+                .execute(connection)?;
+
+            Ok(Some(user))
+        })
+    }
+}
+
+impl Handler<FetchPasswordResetUser> for DbActor {
+    type Result = DbResult<Option<User>>;
+
+    fn handle(&mut self, msg: FetchPasswordResetUser, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(password_reset_tokens::table
+            .inner_join(users)
+            .filter(password_reset_tokens::token_hash.eq(&msg.token_hash))
+            .filter(password_reset_tokens::used_on.is_null())
+            .filter(password_reset_tokens::expires_on.gt(Utc::now()))
+            .select(users::all_columns())
+            .first::<User>(&mut connection)
+            .optional()?)
+    }
+}
+
+impl Handler<ResetPassword> for DbActor {
+    type Result = DbResult<Option<i32>>;
+
+    fn handle(&mut self, msg: ResetPassword, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let now: DateTime<Utc> = Utc::now();
+
+            let reset_user_id: Option<i32> = diesel::update(
+                password_reset_tokens::table
+                    .filter(password_reset_tokens::token_hash.eq(&msg.token_hash))
+                    .filter(password_reset_tokens::used_on.is_null())
+                    .filter(password_reset_tokens::expires_on.gt(now)),
+            )
+            .set(password_reset_tokens::used_on.eq(now))
+            .returning(password_reset_tokens::user_id)
+            .get_result::<i32>(connection)
+            .optional()?;
+
+            let Some(reset_user_id) = reset_user_id else {
+                return Ok(None);
+            };
+
+            diesel::update(users.find(reset_user_id))
+                .set((
+                    password.eq(msg.new_password),
+                    password_reset_required.eq(false),
+                ))
+                .execute(connection)?;
+
+            let jtis: Vec<String> = sessions::table
+                .filter(sessions::user_id.eq(reset_user_id))
+                .filter(sessions::revoked_on.is_null())
+                .select(sessions::jti)
+                .get_results::<String>(connection)?;
+
+            revoke_sessions_by_jti(connection, jtis)?;
+
+            Ok(Some(reset_user_id))
+        })
+    }
+}
+
+impl Handler<VerifyUserEmail> for DbActor {
+    type Result = DbResult<Option<User>>;
+
+    fn handle(&mut self, msg: VerifyUserEmail, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let user: Option<User> = users
+            .filter(id.eq(msg.user_id))
+            .filter(email.eq(&msg.email))
+            .first::<User>(&mut connection)
+            .optional()?;
+
+        match user {
+            Some(user) if user.email_verified_at.is_none() => {
+                Ok(diesel::update(users.find(user.id))
+                    .set(email_verified_at.eq(Utc::now()))
+                    .get_result::<User>(&mut connection)
+                    .map(Some)?)
+            }
+            user => Ok(user),
+        }
+    }
+}
+
+impl Handler<ReplaceRecoveryCodes> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: ReplaceRecoveryCodes, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection.transaction(|connection| {
+            diesel::delete(
+                otp_recovery_codes::table.filter(otp_recovery_codes::user_id.eq(msg.user_id)),
+            )
+            .execute(connection)?;
+
+            let new_codes: Vec<NewOTPRecoveryCode> = msg
+                .code_hashes
+                .into_iter()
+                .map(|code_hash| NewOTPRecoveryCode {
+                    user_id: msg.user_id,
+                    code_hash,
+                })
+                .collect();
+
+            if new_codes.is_empty() {
+                return Ok(0);
+            }
+
+            diesel::insert_into(otp_recovery_codes::table)
+                .values(new_codes)
+                .execute(connection)
+        })?)
+    }
+}
+
+impl Handler<FetchRecoveryCodes> for DbActor {
+    type Result = DbResult<Vec<OTPRecoveryCode>>;
+
+    fn handle(&mut self, msg: FetchRecoveryCodes, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(otp_recovery_codes::table
+            .filter(otp_recovery_codes::user_id.eq(msg.user_id))
+            .filter(otp_recovery_codes::used_on.is_null())
+            .get_results::<OTPRecoveryCode>(&mut connection)?)
+    }
+}
+
+impl Handler<ConsumeRecoveryCode> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: ConsumeRecoveryCode, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(
+            otp_recovery_codes::table
+                .filter(otp_recovery_codes::id.eq(msg.code_id))
+                .filter(otp_recovery_codes::used_on.is_null()),
+        )
+        .set(otp_recovery_codes::used_on.eq(Utc::now()))
+        .execute(&mut connection)?)
+    }
+}
+
+impl Handler<CreateApiKey> for DbActor {
+    type Result = DbResult<ApiKey>;
+
+    fn handle(&mut self, msg: CreateApiKey, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::insert_into(api_keys::table)
+            .values(NewApiKey {
+                user_id: msg.user_id,
+                name: msg.name,
+                prefix: msg.prefix,
+                key_hash: msg.key_hash,
+                scopes: msg.scopes,
+                expires_on: msg.expires_on,
+            })
+            .get_result::<ApiKey>(&mut connection)?)
+    }
+}
+
+impl Handler<FetchUserApiKeys> for DbActor {
+    type Result = DbResult<Vec<ApiKey>>;
+
+    fn handle(&mut self, msg: FetchUserApiKeys, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(api_keys::table
+            .filter(api_keys::user_id.eq(msg.user_id))
+            .filter(api_keys::revoked_on.is_null())
+            .order(api_keys::created_on.desc())
+            .get_results::<ApiKey>(&mut connection)?)
+    }
+}
+
+impl Handler<RevokeApiKey> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: RevokeApiKey, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(
+            api_keys::table
+                .filter(api_keys::id.eq(msg.api_key_id))
+                .filter(api_keys::user_id.eq(msg.user_id))
+                .filter(api_keys::revoked_on.is_null()),
+        )
+        .set(api_keys::revoked_on.eq(Utc::now()))
+        .execute(&mut connection)?)
+    }
+}
+
+impl Handler<AuthenticateApiKey> for DbActor {
+    type Result = DbResult<Option<(ApiKey, User)>>;
+
+    fn handle(&mut self, msg: AuthenticateApiKey, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let now: DateTime<Utc> = Utc::now();
+
+        let api_key: Option<ApiKey> = diesel::update(
+            api_keys::table
+                .filter(api_keys::key_hash.eq(&msg.key_hash))
+                .filter(api_keys::revoked_on.is_null())
+                .filter(
+                    api_keys::expires_on
+                        .is_null()
+                        .or(api_keys::expires_on.gt(now)),
+                ),
+        )
+        .set(api_keys::last_used_on.eq(now))
+        .get_result::<ApiKey>(&mut connection)
+        .optional()?;
+
+        match api_key {
+            Some(api_key) => {
+                let user: User = users.find(api_key.user_id).first::<User>(&mut connection)?;
+                Ok(Some((api_key, user)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Handler<CreateOidcLoginState> for DbActor {
+    type Result = DbResult<OidcLoginState>;
+
+    fn handle(&mut self, msg: CreateOidcLoginState, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        diesel::delete(
+            oidc_login_states::table.filter(oidc_login_states::expires_on.le(Utc::now())),
+        )
+        .execute(&mut connection)?;
+
+        let new_state: NewOidcLoginState = NewOidcLoginState {
+            state_hash: msg.state_hash,
+            nonce: msg.nonce,
+            code_verifier: msg.code_verifier,
+            user_id: msg.user_id,
+            expires_on: msg.expires_on,
+        };
+
+        Ok(diesel::insert_into(oidc_login_states::table)
+            .values(new_state)
+            .get_result::<OidcLoginState>(&mut connection)?)
+    }
+}
+
+impl Handler<ConsumeOidcLoginState> for DbActor {
+    type Result = DbResult<Option<OidcLoginState>>;
+
+    fn handle(&mut self, msg: ConsumeOidcLoginState, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::delete(
+            oidc_login_states::table.filter(oidc_login_states::state_hash.eq(&msg.state_hash)),
+        )
+        .get_result::<OidcLoginState>(&mut connection)
+        .optional()
+        .map(|state| state.filter(|state| state.expires_on > Utc::now()))?)
+    }
+}
+
+impl Handler<LoginWithIdentity> for DbActor {
+    type Result = DbResult<Option<User>>;
+
+    fn handle(&mut self, msg: LoginWithIdentity, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let identity: Option<UserIdentity> = diesel::update(
+            user_identities::table
+                .filter(user_identities::provider.eq(&msg.provider))
+                .filter(user_identities::subject.eq(&msg.subject)),
+        )
+        .set(user_identities::last_login_on.eq(Utc::now()))
+        .get_result::<UserIdentity>(&mut connection)
+        .optional()?;
+
+        match identity {
+            Some(identity) => Ok(users
+                .find(identity.user_id)
+                .first::<User>(&mut connection)
+                .map(Some)?),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Handler<CreateUserWithIdentity> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: CreateUserWithIdentity, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let mut candidate: String = msg.username.clone();
+
+            while users
+                .filter(username.eq(&candidate))
+                .count()
+                .get_result::<i64>(connection)?
+                > 0
+            {
+                let base: String = msg.username.chars().take(25).collect();
+                candidate = format!("{}_{:04}", base, rand::thread_rng().gen_range(0..10_000));
+            }
+
+            let now = Utc::now().naive_utc();
+
+            let new_user: NewUser = NewUser {
+                username: candidate,
+                email: msg.email.clone(),
+                password: None,
+                role: new_user_role(connection)?,
+                email_verified_at: msg.email_verified.then_some(now),
+            };
+
+            let user: User = diesel::insert_into(users)
+                .values(new_user)
+                .get_result::<User>(connection)?;
+
+            diesel::insert_into(user_identities::table)
+                .values(NewUserIdentity {
+                    user_id: user.id,
+                    provider: msg.provider,
+                    subject: msg.subject,
+                    email: Some(msg.email),
+                    last_login_on: Some(now),
+                })
+                .execute(connection)?;
+
+            Ok(user)
+        })
+    }
+}
+
+impl Handler<LinkUserIdentity> for DbActor {
+    type Result = DbResult<UserIdentity>;
+
+    fn handle(&mut self, msg: LinkUserIdentity, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::insert_into(user_identities::table)
+            .values(NewUserIdentity {
+                user_id: msg.user_id,
+                provider: msg.provider,
+                subject: msg.subject,
+                email: msg.email,
+                last_login_on: None,
+            })
+            .get_result::<UserIdentity>(&mut connection)?)
+    }
+}
+
+impl Handler<FetchUserIdentities> for DbActor {
+    type Result = DbResult<Vec<UserIdentity>>;
+
+    fn handle(&mut self, msg: FetchUserIdentities, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(user_identities::table
+            .filter(user_identities::user_id.eq(msg.user_id))
+            .order(user_identities::created_on.asc())
+            .get_results::<UserIdentity>(&mut connection)?)
+    }
+}
+
+impl Handler<UnlinkUserIdentity> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: UnlinkUserIdentity, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::delete(
+            user_identities::table
+                .filter(user_identities::id.eq(msg.identity_id))
+                .filter(user_identities::user_id.eq(msg.user_id)),
+        )
+        .execute(&mut connection)?)
     }
 }
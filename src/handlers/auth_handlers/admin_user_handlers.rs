@@ -0,0 +1,269 @@
+use super::{messages::*, password_handlers::send_password_reset_email, utils::UserResponse};
+use crate::{
+    handlers::audit_handlers::audit_handlers::{record_audit_event, request_actor_id},
+    middlewares::auth_middlewares::require_permission,
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::Claims,
+        tokens::{generate_opaque_token, hash_token},
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, post,
+    web::{Data, Path},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{Duration, Utc};
+
+fn reject_own_account(req: &HttpRequest, user_id: i32) -> Result<(), ApiError> {
+    let claims: Option<Claims> = req.extensions().get::<Claims>().cloned();
+
+    match claims {
+        Some(claims) if claims.id == user_id => Err(ApiError::BadRequest(String::from(
+            "this action cannot be applied to your own account",
+        ))),
+        Some(_) => Ok(()),
+        None => Err(ApiError::Unauthorized(String::from("unauthorized access"))),
+    }
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}/suspend",
+    responses(
+        (status = 200, description = "User suspended. Their sessions are revoked and they can no longer log in or use API keys."),
+        (status = 400, description = "Admins cannot suspend their own account."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the users:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to suspend the user."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post(
+    "/users/{user_id}/suspend",
+    wrap = "require_permission(\"users:manage\")"
+)]
+pub async fn suspend_user(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+
+    reject_own_account(&req, user_id)?;
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = db
+        .send(SetUserSuspended {
+            user_id,
+            suspended: true,
+        })
+        .await?
+        .not_found(|| format!("user {} not found", user_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "user.suspended",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}/reactivate",
+    responses(
+        (status = 200, description = "User reactivated and able to log in again."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the users:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to reactivate the user."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post(
+    "/users/{user_id}/reactivate",
+    wrap = "require_permission(\"users:manage\")"
+)]
+pub async fn reactivate_user(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = db
+        .send(SetUserSuspended {
+            user_id,
+            suspended: false,
+        })
+        .await?
+        .not_found(|| format!("user {} not found", user_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "user.reactivated",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}/password-reset",
+    responses(
+        (status = 200, description = "The user is logged out everywhere, cannot log in until they reset their password, and has been emailed a reset token."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the users:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to force a password reset."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post(
+    "/users/{user_id}/password-reset",
+    wrap = "require_permission(\"users:manage\")"
+)]
+pub async fn force_password_reset(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = db
+        .send(RequirePasswordReset { user_id })
+        .await?
+        .not_found(|| format!("user {} not found", user_id))?;
+
+    let reset_token: String = generate_opaque_token();
+
+    db.send(CreatePasswordResetToken {
+        email: user.email.clone(),
+        token_hash: hash_token(&reset_token),
+        expires_on: (Utc::now() + Duration::minutes(30)).naive_utc(),
+    })
+    .await??
+    .ok_or_else(|| ApiError::Internal(String::from("failed to create password reset token")))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "user.password_reset_forced",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    send_password_reset_email(state.as_ref(), &user, &reset_token)
+        .await
+        .map_err(|_| ApiError::Internal(String::from("failed to send password reset email")))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("user {} must reset their password", user_id)
+    })))
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}/otp",
+    responses(
+        (status = 200, description = "Two-factor authentication and recovery codes removed from the user."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the users:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to reset two-factor authentication."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/users/{user_id}/otp", wrap = "require_permission(\"users:manage\")")]
+pub async fn reset_user_otp(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = db
+        .send(ResetUserOTP { user_id })
+        .await?
+        .not_found(|| format!("user {} not found", user_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "user.otp_reset",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}",
+    responses(
+        (status = 200, description = "User and all of their data deleted."),
+        (status = 400, description = "Admins cannot delete their own account from the dashboard."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the users:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to delete the user."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/users/{user_id}", wrap = "require_permission(\"users:manage\")")]
+pub async fn admin_delete_user(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+
+    reject_own_account(&req, user_id)?;
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    if db.send(DeleteUser { user_id }).await?? == 0 {
+        return Err(ApiError::NotFound(format!("user {} not found", user_id)));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "user.deleted",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok()
+        .json(serde_json::json!({ "message": format!("deleted user {}", user_id) })))
+}
@@ -0,0 +1,273 @@
+use super::messages::*;
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::ApiKey,
+    utils::{
+        db::{AppState, DbActor},
+        errors::ApiError,
+        jwt::Claims,
+        tokens::{generate_opaque_token, hash_token},
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const API_KEY_PREFIX: &str = "rna_";
+
+pub const API_KEY_SCOPES: [&str; 7] = [
+    "notes:read",
+    "notes:write",
+    "account:read",
+    "account:write",
+    "transactions:read",
+    "admin:read",
+    "admin:write",
+];
+
+#[derive(Serialize)]
+struct ApiKeyResponse {
+    id: i32,
+    name: String,
+    prefix: String,
+    scopes: Vec<String>,
+    expires_on: Option<DateTime<Utc>>,
+    last_used_on: Option<DateTime<Utc>>,
+    created_on: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        ApiKeyResponse {
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            scopes: api_key
+                .scopes
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            expires_on: api_key.expires_on,
+            last_used_on: api_key.last_used_on,
+            created_on: api_key.created_on,
+        }
+    }
+}
+
+pub fn reject_api_key_claims(claims: &Claims) -> Result<(), ApiError> {
+    match claims.scopes {
+        Some(_) => Err(ApiError::Forbidden(String::from(
+            "api keys cannot be managed with an api key",
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    #[schema(example = "backup script", required = true)]
+    pub name: String,
+    #[schema(example = json!(["notes:read"]), required = true)]
+    pub scopes: Vec<String>,
+    #[schema(example = 90)]
+    pub expires_in_days: Option<i64>,
+}
+
+#[utoipa::path(
+    path = "/auth/api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "API key created. The key is only returned once; send it as a Bearer token or in the X-API-Key header."),
+        (status = 400, description = "Invalid name, scope or expiry."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 500, description = "Internal Server Error: Unable to create the API key."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/api-keys")]
+pub async fn create_api_key(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<CreateApiKeyRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    reject_api_key_claims(&claims)?;
+
+    let name: String = body.name.trim().to_string();
+
+    if name.is_empty() || name.len() > 100 {
+        return Err(ApiError::BadRequest(String::from(
+            "name must be between 1 and 100 characters",
+        )));
+    }
+
+    if body.scopes.is_empty() {
+        return Err(ApiError::BadRequest(String::from(
+            "at least one scope is required",
+        )));
+    }
+
+    if let Some(scope) = body
+        .scopes
+        .iter()
+        .find(|scope| !API_KEY_SCOPES.contains(&scope.as_str()))
+    {
+        return Err(ApiError::Validation(
+            format!("unknown scope {}", scope),
+            serde_json::json!({ "allowed_scopes": API_KEY_SCOPES }),
+        ));
+    }
+
+    let expires_on: Option<NaiveDateTime> = match body.expires_in_days {
+        Some(days) if days <= 0 => {
+            return Err(ApiError::BadRequest(String::from(
+                "expires_in_days must be positive",
+            )));
+        }
+        Some(days) => Some((Utc::now() + Duration::days(days)).naive_utc()),
+        None => None,
+    };
+
+    let key: String = format!("{}{}", API_KEY_PREFIX, generate_opaque_token());
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let mut scopes: Vec<String> = body.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let api_key: ApiKey = db
+        .send(CreateApiKey {
+            user_id: claims.id,
+            name,
+            prefix: key.chars().take(12).collect(),
+            key_hash: hash_token(&key),
+            scopes: scopes.join(" "),
+            expires_on,
+        })
+        .await??;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "api_key.created",
+        Some(("api_key", api_key.id.to_string())),
+        serde_json::json!({ "name": api_key.name, "scopes": api_key.scopes }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "key": key,
+        "api_key": ApiKeyResponse::from(api_key),
+    })))
+}
+
+#[utoipa::path(
+    path = "/auth/api-keys",
+    responses(
+        (status = 200, description = "Successfully retrieved the active API keys of the authenticated user."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 500, description = "Internal Server Error: Unable to retrieve API keys."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/api-keys")]
+pub async fn fetch_api_keys(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    reject_api_key_claims(&claims)?;
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let api_keys: Vec<ApiKeyResponse> = db
+        .send(FetchUserApiKeys { user_id: claims.id })
+        .await??
+        .into_iter()
+        .map(ApiKeyResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(api_keys))
+}
+
+#[utoipa::path(
+    path = "/auth/api-keys/{api_key_id}",
+    responses(
+        (status = 200, description = "API key revoked."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 404, description = "API key not found."),
+        (status = 500, description = "Internal Server Error: Unable to revoke the API key."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/api-keys/{api_key_id}")]
+pub async fn revoke_api_key(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let api_key_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    reject_api_key_claims(&claims)?;
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let rows_affected: usize = db
+        .send(RevokeApiKey {
+            user_id: claims.id,
+            api_key_id,
+        })
+        .await??;
+
+    if rows_affected == 0 {
+        return Err(ApiError::NotFound(format!(
+            "api key {} not found",
+            api_key_id
+        )));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "api_key.revoked",
+        Some(("api_key", api_key_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok()
+        .json(serde_json::json!({ "message": format!("revoked api key {}", api_key_id) })))
+}
@@ -1,21 +1,133 @@
-use super::messages::*;
-use crate::utils::{
-    db::{AppState, DbActor},
-    jwt::{encode_jwt, Claims},
+use super::{
+    messages::*,
+    password_handlers::reject_weak_password,
+    session_handlers::{client_ip, start_session},
+    verification_handlers::send_verification_email,
+};
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    middlewares::auth_middlewares::{check_csrf, token_from_request, TokenTransport, CSRF_COOKIE},
+    models::User,
+    utils::{
+        config::Config,
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::{Claims, ACCESS_TOKEN_MINUTES},
+        passwords::{hash_password, needs_rehash, verify_password},
+        tokens::generate_opaque_token,
+    },
 };
 use actix::Addr;
 use actix_web::{
     cookie::{
         time::{Duration, OffsetDateTime},
-        Cookie,
+        Cookie, SameSite,
     },
-    delete, get, post,
+    delete, post,
     web::{Data, Json},
-    HttpMessage, HttpRequest, HttpResponse, Responder,
+    HttpMessage, HttpRequest, HttpResponse,
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use std::sync::OnceLock;
 use utoipa::ToSchema;
 
+/// The one message for an unknown email, a wrong password and an account
+/// without a password, so failed logins don't tell which emails have accounts.
+const INVALID_CREDENTIALS: &str = "invalid email or password";
+
+fn too_many_attempts(retry_on: DateTime<Utc>) -> (String, i64) {
+    (
+        String::from("too many failed login attempts, try again later"),
+        (retry_on - Utc::now()).num_seconds().max(1),
+    )
+}
+
+/// Verifies the password against a throwaway hash, so a login for an unknown
+/// email takes as long as one for an existing account.
+fn verify_dummy_password(config: &Config, password: &str) {
+    static DUMMY_HASH: OnceLock<Option<String>> = OnceLock::new();
+
+    if let Some(dummy_hash) =
+        DUMMY_HASH.get_or_init(|| hash_password(&config.passwords, "dummy password").ok())
+    {
+        verify_password(password, dummy_hash);
+    }
+}
+
+/// Fails a login for an email without an account the same way as a wrong
+/// password, including the backoff and lockout.
+async fn reject_unknown_email(
+    state: &AppState,
+    req: &HttpRequest,
+    body: &LoginUserRequest,
+) -> Result<HttpResponse, ApiError> {
+    let ip_address: Option<String> = client_ip(req);
+
+    if let Some(retry_on) = state
+        .db
+        .send(CheckUnknownLoginThrottle {
+            email: body.email.clone(),
+            ip_address: ip_address.clone(),
+        })
+        .await??
+    {
+        let (detail, retry_after) = too_many_attempts(retry_on);
+
+        return Err(ApiError::RateLimited(detail, retry_after));
+    }
+
+    verify_dummy_password(&state.config, &body.password);
+
+    state
+        .db
+        .send(RecordUnknownLoginAttempt {
+            email: body.email.clone(),
+            ip_address,
+        })
+        .await??;
+
+    Err(ApiError::Unauthorized(String::from(INVALID_CREDENTIALS)))
+}
+
+fn cookie_same_site(config: &Config) -> SameSite {
+    match config.cookies.same_site.as_str() {
+        "strict" => SameSite::Strict,
+        "none" => SameSite::None,
+        _ => SameSite::Lax,
+    }
+}
+
+pub fn build_token_cookie(
+    config: &Config,
+    token: String,
+    expires: OffsetDateTime,
+) -> Cookie<'static> {
+    Cookie::build("token", token)
+        .path("/")
+        .http_only(config.cookies.http_only)
+        .secure(config.cookie_secure())
+        .same_site(cookie_same_site(config))
+        .expires(expires)
+        .finish()
+}
+
+/// CSRF token for cookie authenticated clients. It is readable by scripts so
+/// they can send it back in the `X-CSRF-Token` header.
+pub fn build_csrf_cookie(
+    config: &Config,
+    csrf_token: String,
+    expires: OffsetDateTime,
+) -> Cookie<'static> {
+    Cookie::build(CSRF_COOKIE, csrf_token)
+        .path("/")
+        .http_only(false)
+        .secure(config.cookie_secure())
+        .same_site(cookie_same_site(config))
+        .expires(expires)
+        .finish()
+}
+
 #[derive(Deserialize, ToSchema)]
 pub struct RegisterUserRequest {
     #[schema(example = "random", required = true)]
@@ -30,39 +142,143 @@ pub struct RegisterUserRequest {
     path = "/user/register",
     request_body = RegisterUserRequest,
     responses(
-        (status = 200, description = "Successfully registered a new user."),
+        (status = 200, description = "Successfully registered a new user. A verification link is emailed to the new address."),
+        (status = 400, description = "The password does not meet the password policy. Policy violations are listed in `errors`."),
+        (status = 409, description = "The username or email address is already registered."),
         (status = 500, description = "Failed to register the user due to an internal error."),
     )
 )]
 #[post("/register")]
 pub async fn register_user(
     state: Data<AppState>,
+    req: HttpRequest,
     body: Json<RegisterUserRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    let hashed_password: String = match bcrypt::hash(&body.password, bcrypt::DEFAULT_COST) {
-        Ok(hash) => hash,
-        Err(_) => {
-            return HttpResponse::InternalServerError()
-                .json(serde_json::json!({ "message": "password hashing failed" }))
-        }
-    };
+    reject_weak_password(
+        &state.config.passwords,
+        &body.password,
+        &body.username,
+        &body.email,
+    )?;
+
+    let hashed_password: String = hash_password(&state.config.passwords, &body.password)
+        .map_err(|_| ApiError::Internal(String::from("password hashing failed")))?;
 
-    match db
+    let user: User = db
         .send(CreateUser {
             username: body.username.clone(),
             email: body.email.clone(),
             password: hashed_password,
         })
-        .await
-    {
-        Ok(Ok(user)) => HttpResponse::Ok().json(user),
-        Ok(Err(_)) => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "failed to create user" })),
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "unable to create user" })),
+        .await?
+        .conflict(|| String::from("username or email is already registered"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(user.id),
+        "user.registered",
+        Some(("user", user.id.to_string())),
+        serde_json::json!({ "email": user.email, "role": user.role }),
+    )
+    .await;
+
+    if let Err(err) = send_verification_email(state.as_ref(), &user).await {
+        eprintln!("Register User: unable to send verification email: {}", err);
     }
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Finishes a login once the user has proven who they are, whether with a
+/// password or through an identity provider: applies the account checks and
+/// either issues a session or an MFA challenge.
+pub async fn complete_login(
+    state: &AppState,
+    req: &HttpRequest,
+    user: &User,
+    method: &str,
+) -> Result<HttpResponse, ApiError> {
+    let db: &Addr<DbActor> = &state.db;
+
+    if user.suspended_at.is_some() {
+        return Err(ApiError::Forbidden(String::from("account is suspended")));
+    }
+
+    if state.config.auth.email_verification == "login" && user.email_verified_at.is_none() {
+        return Err(ApiError::Forbidden(String::from(
+            "email address has not been verified",
+        )));
+    }
+
+    if user.otp_enabled.unwrap_or(false) && user.otp_verified.unwrap_or(false) {
+        record_audit_event(
+            db,
+            req,
+            Some(user.id),
+            "user.login_mfa_challenged",
+            Some(("user", user.id.to_string())),
+            serde_json::json!({ "method": method }),
+        )
+        .await;
+
+        let token: String = state
+            .jwt_keys
+            .encode_mfa_challenge_jwt(user.email.clone(), user.id, user.role.clone())
+            .map_err(|_| ApiError::Internal(String::from("failed to generate token")))?;
+
+        let five_minutes: OffsetDateTime = OffsetDateTime::now_utc() + Duration::minutes(5);
+
+        return Ok(HttpResponse::Ok()
+            .cookie(build_token_cookie(
+                &state.config,
+                token.clone(),
+                five_minutes,
+            ))
+            .cookie(build_csrf_cookie(
+                &state.config,
+                generate_opaque_token(),
+                five_minutes,
+            ))
+            .json(serde_json::json!({
+                "mfa_pending": true,
+                "token": token,
+                "message": "submit an otp token to /auth/otp/validate",
+            })));
+    }
+
+    let (token, refresh_token) = start_session(state, user, req).await?;
+
+    record_audit_event(
+        db,
+        req,
+        Some(user.id),
+        "user.login",
+        Some(("user", user.id.to_string())),
+        serde_json::json!({ "mfa": false, "method": method }),
+    )
+    .await;
+
+    let expires: OffsetDateTime =
+        OffsetDateTime::now_utc() + Duration::minutes(ACCESS_TOKEN_MINUTES);
+
+    Ok(HttpResponse::Ok()
+        .cookie(build_token_cookie(&state.config, token.clone(), expires))
+        .cookie(build_csrf_cookie(
+            &state.config,
+            generate_opaque_token(),
+            expires,
+        ))
+        .json(serde_json::json!({
+            "token": token,
+            "refresh_token": refresh_token,
+            "user": {
+                "email": user.email,
+                "username": user.username,
+            }
+        })))
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -77,8 +293,10 @@ pub struct LoginUserRequest {
     path = "/user/login",
     request_body = LoginUserRequest,
     responses(
-        (status = 200, description = "Successfully logged in. Returns a bearer token along with user information"),
+        (status = 200, description = "Successfully logged in. Returns a short-lived bearer token and a refresh token along with user information, or a short-lived challenge token when two-factor authentication is enabled"),
         (status = 401, description = "Unauthorized: Invalid email or password."),
+        (status = 403, description = "Forbidden: The account is suspended, must reset its password, or its email address has not been verified."),
+        (status = 429, description = "Too many failed login attempts for this email, whether or not it has an account. It is temporarily locked or this IP must wait; see the Retry-After header."),
         (status = 500, description = "Internal Server Error: Unable to process the login request."),
     ),
     security(
@@ -86,52 +304,122 @@ pub struct LoginUserRequest {
     )
 )]
 #[post("/login")]
-pub async fn login_user(state: Data<AppState>, body: Json<LoginUserRequest>) -> impl Responder {
+pub async fn login_user(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<LoginUserRequest>,
+) -> Result<HttpResponse, ApiError> {
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    match db
+    let user: User = match db
         .send(LoginAndGetUser {
             email: body.email.clone(),
             password: body.password.clone(),
         })
-        .await
+        .await?
     {
-        Ok(Ok(user)) => {
-            let is_valid = bcrypt::verify(&body.password, &user.password);
-            if is_valid.unwrap_or(false) {
-                let token_result = encode_jwt(user.email.clone(), user.id, user.role);
-                match token_result {
-                    Ok(token) => {
-                        let oneday: OffsetDateTime = OffsetDateTime::now_utc() + Duration::days(1);
-
-                        let cookie = Cookie::build("token", token.clone())
-                            .path("/")
-                            .http_only(true)
-                            .secure(false)
-                            .expires(oneday)
-                            .finish();
-
-                        HttpResponse::Ok().cookie(cookie).json(serde_json::json!({
-                            "token": token,
-                            "user": {
-                                "email": user.email,
-                                "username": user.username,
-                            }
-                        }))
-                    }
-                    Err(_) => HttpResponse::InternalServerError()
-                        .json(serde_json::json!({ "message": "failed to generate token" })),
+        Ok(user) => user,
+        Err(ApiError::NotFound(_)) => {
+            return reject_unknown_email(state.as_ref(), &req, &body).await
+        }
+        Err(err) => return Err(err),
+    };
+
+    let ip_address: Option<String> = client_ip(&req);
+
+    if let Some(retry_on) = db
+        .send(CheckLoginThrottle {
+            user_id: user.id,
+            ip_address: ip_address.clone(),
+        })
+        .await??
+    {
+        let (detail, retry_after) = too_many_attempts(retry_on);
+
+        record_audit_event(
+            &db,
+            &req,
+            Some(user.id),
+            "user.login_throttled",
+            Some(("user", user.id.to_string())),
+            serde_json::json!({ "retry_after": retry_after }),
+        )
+        .await;
+
+        return Err(ApiError::RateLimited(detail, retry_after));
+    }
+
+    let is_valid: bool = user
+        .password
+        .as_deref()
+        .is_some_and(|password_hash| verify_password(&body.password, password_hash));
+
+    let attempted_user: User = db
+        .send(RecordLoginAttempt {
+            user_id: user.id,
+            ip_address,
+            succeeded: is_valid,
+            second_factor_pending: user.otp_enabled.unwrap_or(false)
+                && user.otp_verified.unwrap_or(false),
+        })
+        .await??;
+
+    if is_valid
+        && user
+            .password
+            .as_deref()
+            .is_some_and(|password_hash| needs_rehash(&state.config.passwords, password_hash))
+    {
+        match hash_password(&state.config.passwords, &body.password) {
+            Ok(new_hash) => {
+                if !matches!(
+                    db.send(UpdateUserPassword {
+                        user_id: user.id,
+                        new_password: new_hash,
+                    })
+                    .await,
+                    Ok(Ok(_))
+                ) {
+                    eprintln!("Login User: unable to upgrade password hash");
                 }
-            } else {
-                HttpResponse::Unauthorized()
-                    .json(serde_json::json!({ "message": "invalid credentials" }))
             }
+            Err(err) => eprintln!("Login User: unable to upgrade password hash: {}", err),
+        }
+    }
+
+    if !is_valid {
+        record_audit_event(
+            &db,
+            &req,
+            Some(user.id),
+            "user.login_failed",
+            Some(("user", user.id.to_string())),
+            serde_json::json!({ "failed_login_count": attempted_user.failed_login_count }),
+        )
+        .await;
+
+        if attempted_user.locked_until != user.locked_until {
+            record_audit_event(
+                &db,
+                &req,
+                Some(user.id),
+                "user.locked",
+                Some(("user", user.id.to_string())),
+                serde_json::json!({ "locked_until": attempted_user.locked_until }),
+            )
+            .await;
         }
-        Ok(Err(_)) => HttpResponse::Unauthorized()
-            .json(serde_json::json!({ "message": "invalid email or password" })),
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "unable to login user" })),
+
+        return Err(ApiError::Unauthorized(String::from(INVALID_CREDENTIALS)));
+    }
+
+    if user.password_reset_required {
+        return Err(ApiError::Forbidden(String::from(
+            "password reset required, use the link sent to your email address",
+        )));
     }
+
+    complete_login(state.as_ref(), &req, &user, "password").await
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -149,7 +437,7 @@ pub struct UpdatePasswordRequest {
     ),
     responses(
         (status = 200, description = "Password updated successfully."),
-        (status = 400, description = "Invalid old password."),
+        (status = 400, description = "Invalid old password, or the new password does not meet the password policy. Policy violations are listed in `errors`."),
         (status = 401, description = "Unauthorized access."),
         (status = 500, description = "Failed to update the password due to an internal error."),
     ),
@@ -162,80 +450,108 @@ pub async fn update_password(
     state: Data<AppState>,
     req: HttpRequest,
     body: Json<UpdatePasswordRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({"message": "unauthorized access"}));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
-    match db
+    let user: User = db
         .send(LoginAndGetUser {
             email: claims.email.clone(),
             password: String::new(),
         })
-        .await
+        .await??;
+
+    if !user
+        .password
+        .as_deref()
+        .is_some_and(|password_hash| verify_password(&body.old_password, password_hash))
     {
-        Ok(Ok(user)) => {
-            if bcrypt::verify(&body.old_password, &user.password).unwrap_or(false) {
-                let hashed_password = match bcrypt::hash(&body.new_password, bcrypt::DEFAULT_COST) {
-                    Ok(hash) => hash,
-                    Err(_) => {
-                        return HttpResponse::InternalServerError().json(serde_json::json!({
-                            "message": "password hashing failed"
-                        }));
-                    }
-                };
-
-                match db
-                    .send(UpdateUserPassword {
-                        user_id: claims.id,
-                        new_password: hashed_password,
-                    })
-                    .await
-                {
-                    Ok(Ok(_)) => {
-                        HttpResponse::Ok().json(serde_json::json!({"message": "password updated"}))
-                    }
-                    _ => HttpResponse::InternalServerError()
-                        .json(serde_json::json!({"message": "failed to update password"})),
-                }
-            } else {
-                HttpResponse::BadRequest()
-                    .json(serde_json::json!({"message": "invalid old password"}))
-            }
-        }
-        Ok(Err(_)) => HttpResponse::InternalServerError()
-            .json(serde_json::json!({"message": "failed to retrieve user"})),
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({"message": "internal server error"})),
+        return Err(ApiError::BadRequest(String::from("invalid old password")));
     }
+
+    reject_weak_password(
+        &state.config.passwords,
+        &body.new_password,
+        &user.username,
+        &user.email,
+    )?;
+
+    let hashed_password: String = hash_password(&state.config.passwords, &body.new_password)
+        .map_err(|_| ApiError::Internal(String::from("password hashing failed")))?;
+
+    db.send(UpdateUserPassword {
+        user_id: claims.id,
+        new_password: hashed_password,
+    })
+    .await??;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "user.password_changed",
+        Some(("user", claims.id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({"message": "password updated"})))
 }
 
 #[utoipa::path(
     path = "/user/logout",
     responses(
         (status = 200, description = "Successfully logged out. The user's session has been terminated."),
+        (status = 403, description = "Logged in with the token cookie but the X-CSRF-Token header is missing or wrong."),
     )
 )]
-#[get("/logout")]
-pub async fn logout_user() -> impl Responder {
+#[post("/logout")]
+pub async fn logout_user(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let token: Option<String> = match token_from_request(&req, &state.config.auth) {
+        Some((token, TokenTransport::Cookie)) => {
+            check_csrf(&req)?;
+            Some(token)
+        }
+        Some((token, TokenTransport::Header)) => Some(token),
+        None => None,
+    };
+
+    if let Some(Ok(claim)) = token.map(|token| state.jwt_keys.decode_jwt(token)) {
+        if !claim.claims.jti.is_empty() {
+            db.send(RevokeSessionByJti {
+                jti: claim.claims.jti,
+            })
+            .await??;
+
+            record_audit_event(
+                &db,
+                &req,
+                Some(claim.claims.id),
+                "user.logout",
+                Some(("user", claim.claims.id.to_string())),
+                serde_json::json!({}),
+            )
+            .await;
+        }
+    }
+
     let now: OffsetDateTime = OffsetDateTime::now_utc();
 
-    let cookie = Cookie::build("token", "logout")
-        .path("/")
-        .http_only(true)
-        .secure(false)
-        .expires(now)
-        .finish();
-
-    HttpResponse::Ok()
-        .cookie(cookie)
-        .json(serde_json::json!({ "message": "user logged out" }))
+    Ok(HttpResponse::Ok()
+        .cookie(build_token_cookie(&state.config, String::new(), now))
+        .cookie(build_csrf_cookie(&state.config, String::new(), now))
+        .json(serde_json::json!({ "message": "user logged out" })))
 }
 
 #[utoipa::path(
@@ -250,38 +566,37 @@ pub async fn logout_user() -> impl Responder {
     )
 )]
 #[delete("/delete")]
-pub async fn delete_user(state: Data<AppState>, req: HttpRequest) -> impl Responder {
+pub async fn delete_user(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({"message": "unauthorized access"}));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let now: OffsetDateTime = OffsetDateTime::now_utc();
 
-    let cookie = Cookie::build("token", "delete")
-        .path("/")
-        .http_only(true)
-        .secure(false)
-        .expires(now)
-        .finish();
-
-    let result = db.send(DeleteUser { user_id: claims.id }).await;
-
-    match result {
-        Ok(Ok(rows_deleted)) if rows_deleted > 0 => HttpResponse::Ok()
-            .cookie(cookie)
-            .json(serde_json::json!({ "message": "user deleted" })),
+    if db.send(DeleteUser { user_id: claims.id }).await?? == 0 {
+        return Err(ApiError::NotFound(String::from("user not found")));
+    }
 
-        Ok(Ok(_)) => HttpResponse::NotFound().json(serde_json::json!({
-            "message": "user not found"
-        })),
+    record_audit_event(
+        &db,
+        &req,
+        None,
+        "user.deleted",
+        Some(("user", claims.id.to_string())),
+        serde_json::json!({ "email": claims.email }),
+    )
+    .await;
 
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "failed to delete user" })),
-    }
+    Ok(HttpResponse::Ok()
+        .cookie(build_token_cookie(&state.config, String::new(), now))
+        .cookie(build_csrf_cookie(&state.config, String::new(), now))
+        .json(serde_json::json!({ "message": "user deleted" })))
 }
@@ -1,4 +1,8 @@
-use crate::schema::users;
+use crate::schema::{
+    api_keys, login_attempts, oidc_login_states, otp_recovery_codes, password_reset_tokens,
+    refresh_tokens, sessions, user_identities, users,
+};
+use chrono::NaiveDateTime;
 use diesel::{prelude::AsChangeset, Insertable};
 use serde::Serialize;
 
@@ -7,8 +11,9 @@ use serde::Serialize;
 pub struct NewUser {
     pub username: String,
     pub email: String,
-    pub password: String,
+    pub password: Option<String>,
     pub role: String,
+    pub email_verified_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable, Serialize, Clone, AsChangeset)]
@@ -19,3 +24,76 @@ pub struct OTPInfoInsertable {
     pub otp_base32: Option<String>,
     pub otp_auth_url: Option<String>,
 }
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=refresh_tokens)]
+pub struct NewRefreshToken {
+    pub user_id: i32,
+    pub token_hash: String,
+    pub family_id: String,
+    pub expires_on: NaiveDateTime,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=sessions)]
+pub struct NewSession {
+    pub jti: String,
+    pub user_id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=password_reset_tokens)]
+pub struct NewPasswordResetToken {
+    pub user_id: i32,
+    pub token_hash: String,
+    pub expires_on: NaiveDateTime,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=otp_recovery_codes)]
+pub struct NewOTPRecoveryCode {
+    pub user_id: i32,
+    pub code_hash: String,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=api_keys)]
+pub struct NewApiKey {
+    pub user_id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: String,
+    pub expires_on: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=login_attempts)]
+pub struct NewLoginAttempt {
+    pub user_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub succeeded: bool,
+    pub email: Option<String>,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=user_identities)]
+pub struct NewUserIdentity {
+    pub user_id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_on: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=oidc_login_states)]
+pub struct NewOidcLoginState {
+    pub state_hash: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub user_id: Option<i32>,
+    pub expires_on: NaiveDateTime,
+}
@@ -0,0 +1,108 @@
+use super::{messages::*, utils::UserResponse};
+use crate::{
+    handlers::audit_handlers::audit_handlers::{record_audit_event, request_actor_id},
+    middlewares::auth_middlewares::require_permission,
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+
+/// Consecutive failed logins after which the account is locked, whatever IP
+/// they came from.
+pub const LOCKOUT_THRESHOLD: i32 = 10;
+pub const LOCKOUT_MINUTES: i64 = 15;
+
+/// Consecutive failed logins from one IP after which that IP has to wait
+/// before trying the same account again. The wait doubles with every further
+/// failure up to `MAX_BACKOFF_SECONDS`.
+pub const BACKOFF_THRESHOLD: usize = 3;
+pub const BACKOFF_WINDOW_MINUTES: i64 = 15;
+pub const MAX_BACKOFF_SECONDS: i64 = 300;
+
+/// Login attempts of existing accounts are kept this long for the admin view.
+pub const LOGIN_ATTEMPT_RETENTION_DAYS: i64 = 30;
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}/login-attempts",
+    responses(
+        (status = 200, description = "Returns the lock state of the user and their 50 most recent login attempts."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the users:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to retrieve login attempts."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get(
+    "/users/{user_id}/login-attempts",
+    wrap = "require_permission(\"users:manage\")"
+)]
+pub async fn fetch_login_attempts(
+    state: Data<AppState>,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = db
+        .send(FetchUserById { user_id })
+        .await?
+        .not_found(|| format!("user {} not found", user_id))?;
+
+    let login_attempts = db.send(FetchLoginAttempts { user_id }).await??;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "failed_login_count": user.failed_login_count,
+        "locked_until": user.locked_until,
+        "login_attempts": login_attempts,
+    })))
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}/lock",
+    responses(
+        (status = 200, description = "Lockout cleared. The user can log in again immediately."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the users:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to unlock the user."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/users/{user_id}/lock", wrap = "require_permission(\"users:manage\")")]
+pub async fn unlock_user(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = db
+        .send(UnlockUser { user_id })
+        .await?
+        .not_found(|| format!("user {} not found", user_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "user.unlocked",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
+}
@@ -1,13 +1,23 @@
-use crate::models::User;
+use crate::models::{
+    ApiKey, LoginAttempt, OTPRecoveryCode, OidcLoginState, RefreshToken, Session, User,
+    UserIdentity,
+};
+use crate::utils::db::DbResult;
 use actix::Message;
-use diesel::QueryResult;
+use chrono::{DateTime, NaiveDateTime, Utc};
 
 #[derive(Message)]
-#[rtype(result = "QueryResult<Vec<User>>")]
+#[rtype(result = "DbResult<Vec<User>>")]
 pub struct FetchUser;
 
 #[derive(Message)]
-#[rtype(result = "QueryResult<User>")]
+#[rtype(result = "DbResult<User>")]
+pub struct FetchUserById {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
 pub struct CreateUser {
     pub username: String,
     pub email: String,
@@ -16,27 +26,27 @@ pub struct CreateUser {
 
 #[allow(dead_code)]
 #[derive(Message)]
-#[rtype(result = "QueryResult<User>")]
+#[rtype(result = "DbResult<User>")]
 pub struct LoginAndGetUser {
     pub email: String,
     pub password: String,
 }
 
 #[derive(Message)]
-#[rtype(result = "Result<(usize), diesel::result::Error>")]
+#[rtype(result = "DbResult<usize>")]
 pub struct UpdateUserPassword {
     pub user_id: i32,
     pub new_password: String,
 }
 
 #[derive(Message)]
-#[rtype(result = "Result<usize, diesel::result::Error>")]
+#[rtype(result = "DbResult<usize>")]
 pub struct DeleteUser {
     pub user_id: i32,
 }
 
 #[derive(Message)]
-#[rtype(result = "QueryResult<User>")]
+#[rtype(result = "DbResult<User>")]
 pub struct OTPMessage {
     pub email: String,
     pub otp_verified: bool,
@@ -44,3 +54,279 @@ pub struct OTPMessage {
     pub otp_base32: Option<String>,
     pub otp_auth_url: Option<String>,
 }
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
+pub struct SetUserSuspended {
+    pub user_id: i32,
+    pub suspended: bool,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
+pub struct RequirePasswordReset {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
+pub struct ResetUserOTP {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<DateTime<Utc>>>")]
+pub struct CheckLoginThrottle {
+    pub user_id: i32,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
+pub struct RecordLoginAttempt {
+    pub user_id: i32,
+    pub ip_address: Option<String>,
+    pub succeeded: bool,
+    /// A correct password that still needs a second factor. It keeps the
+    /// failure count, so failed OTP attempts still lead to a lockout.
+    pub second_factor_pending: bool,
+}
+
+/// Same as `CheckLoginThrottle` for an email without an account.
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<DateTime<Utc>>>")]
+pub struct CheckUnknownLoginThrottle {
+    pub email: String,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct RecordUnknownLoginAttempt {
+    pub email: String,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<LoginAttempt>>")]
+pub struct FetchLoginAttempts {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
+pub struct UnlockUser {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<RefreshToken>")]
+pub struct CreateRefreshToken {
+    pub user_id: i32,
+    pub token_hash: String,
+    pub family_id: String,
+    pub expires_on: NaiveDateTime,
+}
+
+pub enum RefreshOutcome {
+    Rotated(Box<User>, String),
+    Reused,
+    Expired,
+    Invalid,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<RefreshOutcome>")]
+pub struct RotateRefreshToken {
+    pub token_hash: String,
+    pub new_token_hash: String,
+    pub expires_on: NaiveDateTime,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Session>")]
+pub struct CreateSession {
+    pub jti: String,
+    pub user_id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<(Session, User)>>")]
+pub struct TouchSession {
+    pub jti: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<Session>>")]
+pub struct FetchUserSessions {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct RevokeSession {
+    pub user_id: i32,
+    pub session_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct RevokeSessionByJti {
+    pub jti: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct RevokeUserSessions {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<User>>")]
+pub struct FindUserByEmail {
+    pub email: String,
+}
+
+/// Records that a verification email is being sent to the unverified user,
+/// unless one was already sent after `not_sent_since`. Returns the number of
+/// users updated, 0 or 1.
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct StampVerificationEmailSent {
+    pub user_id: i32,
+    pub not_sent_since: DateTime<Utc>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<User>>")]
+pub struct CreatePasswordResetToken {
+    pub email: String,
+    pub token_hash: String,
+    pub expires_on: NaiveDateTime,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<User>>")]
+pub struct FetchPasswordResetUser {
+    pub token_hash: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<i32>>")]
+pub struct ResetPassword {
+    pub token_hash: String,
+    pub new_password: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<User>>")]
+pub struct VerifyUserEmail {
+    pub user_id: i32,
+    pub email: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct ReplaceRecoveryCodes {
+    pub user_id: i32,
+    pub code_hashes: Vec<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<OTPRecoveryCode>>")]
+pub struct FetchRecoveryCodes {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct ConsumeRecoveryCode {
+    pub code_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<ApiKey>")]
+pub struct CreateApiKey {
+    pub user_id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: String,
+    pub expires_on: Option<NaiveDateTime>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<ApiKey>>")]
+pub struct FetchUserApiKeys {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct RevokeApiKey {
+    pub user_id: i32,
+    pub api_key_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<(ApiKey, User)>>")]
+pub struct AuthenticateApiKey {
+    pub key_hash: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<OidcLoginState>")]
+pub struct CreateOidcLoginState {
+    pub state_hash: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub user_id: Option<i32>,
+    pub expires_on: NaiveDateTime,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<OidcLoginState>>")]
+pub struct ConsumeOidcLoginState {
+    pub state_hash: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Option<User>>")]
+pub struct LoginWithIdentity {
+    pub provider: String,
+    pub subject: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
+pub struct CreateUserWithIdentity {
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub provider: String,
+    pub subject: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<UserIdentity>")]
+pub struct LinkUserIdentity {
+    pub user_id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<UserIdentity>>")]
+pub struct FetchUserIdentities {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct UnlinkUserIdentity {
+    pub user_id: i32,
+    pub identity_id: i32,
+}
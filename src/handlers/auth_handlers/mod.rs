@@ -1,6 +1,15 @@
 pub mod actors;
+pub mod admin_user_handlers;
+pub mod api_key_handlers;
 pub mod auth_handlers;
 pub mod insertables;
+pub mod lockout_handlers;
 pub mod messages;
+pub mod oidc_handlers;
+pub mod password_handlers;
+pub mod session_handlers;
+pub mod token_handlers;
 pub mod two_fa_handlers;
 pub mod user_handlers;
+pub mod utils;
+pub mod verification_handlers;
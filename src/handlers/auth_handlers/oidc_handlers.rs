@@ -0,0 +1,543 @@
+use super::{
+    api_key_handlers::reject_api_key_claims, auth_handlers::complete_login, messages::*,
+    verification_handlers::send_verification_email,
+};
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::{OidcLoginState, User, UserIdentity},
+    utils::{
+        config::Config,
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::Claims,
+        oidc::{
+            authorization_url, discover, exchange_code, oidc_provider, validate_id_token,
+            IdTokenClaims, OidcProvider, ProviderMetadata,
+        },
+        tokens::{generate_opaque_token, hash_token},
+    },
+};
+use actix::Addr;
+use actix_web::{
+    cookie::{time::Duration as CookieDuration, Cookie, SameSite},
+    delete, get,
+    http::header::LOCATION,
+    post,
+    web::{Data, Path, Query},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+
+/// How long a user has to complete the login at the identity provider.
+const OIDC_STATE_MINUTES: i64 = 10;
+
+/// Holds the state of the flow the browser started, so a callback URL from a
+/// flow started elsewhere cannot be completed in it.
+const OIDC_STATE_COOKIE: &str = "oidc_state";
+
+/// Always lax: the identity provider redirects back with a cross-site
+/// navigation, on which strict cookies are not sent.
+fn oidc_state_cookie(config: &Config, oidc_state: String) -> Cookie<'static> {
+    Cookie::build(OIDC_STATE_COOKIE, oidc_state)
+        .path("/user/oidc")
+        .http_only(true)
+        .secure(config.cookie_secure())
+        .same_site(SameSite::Lax)
+        .max_age(CookieDuration::minutes(OIDC_STATE_MINUTES))
+        .finish()
+}
+
+fn configured_provider(state: &AppState) -> Result<OidcProvider, ApiError> {
+    oidc_provider(&state.config)
+        .ok_or_else(|| ApiError::NotFound(String::from("oidc login is not configured")))
+}
+
+fn provider_unavailable(err: String) -> ApiError {
+    eprintln!("OIDC: {}", err);
+    ApiError::BadGateway(String::from("identity provider is unavailable"))
+}
+
+/// Stores a fresh state, nonce and PKCE verifier and returns the URL the user
+/// has to visit at the identity provider, with the cookie binding the flow to
+/// the browser.
+async fn begin_authorization(
+    state: &AppState,
+    provider: &OidcProvider,
+    user_id: Option<i32>,
+) -> Result<(String, Cookie<'static>), ApiError> {
+    let metadata: ProviderMetadata = discover(&Client::new(), provider)
+        .await
+        .map_err(provider_unavailable)?;
+
+    let oidc_state: String = generate_opaque_token();
+    let nonce: String = generate_opaque_token();
+    let code_verifier: String = generate_opaque_token();
+
+    state
+        .db
+        .send(CreateOidcLoginState {
+            state_hash: hash_token(&oidc_state),
+            nonce: nonce.clone(),
+            code_verifier: code_verifier.clone(),
+            user_id,
+            expires_on: (Utc::now() + Duration::minutes(OIDC_STATE_MINUTES)).naive_utc(),
+        })
+        .await??;
+
+    let url: String = authorization_url(&metadata, provider, &oidc_state, &nonce, &code_verifier)
+        .map_err(provider_unavailable)?;
+
+    Ok((url, oidc_state_cookie(&state.config, oidc_state)))
+}
+
+fn username_from_claims(claims: &IdTokenClaims, email: &str) -> String {
+    let source: &str = claims
+        .preferred_username
+        .as_deref()
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+
+    let username: String = source
+        .chars()
+        .filter(|character| character.is_ascii_alphanumeric() || "._-".contains(*character))
+        .take(30)
+        .collect();
+
+    if username.is_empty() {
+        String::from("user")
+    } else {
+        username
+    }
+}
+
+#[utoipa::path(
+    path = "/user/oidc/login",
+    responses(
+        (status = 302, description = "Redirects to the identity provider to sign in."),
+        (status = 404, description = "OIDC login is not configured."),
+        (status = 502, description = "The identity provider could not be reached."),
+        (status = 500, description = "Internal Server Error: Unable to start the login."),
+    )
+)]
+#[get("/oidc/login")]
+pub async fn oidc_login(state: Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let provider: OidcProvider = configured_provider(state.as_ref())?;
+
+    let (url, state_cookie) = begin_authorization(state.as_ref(), &provider, None).await?;
+
+    Ok(HttpResponse::Found()
+        .insert_header((LOCATION, url))
+        .cookie(state_cookie)
+        .finish())
+}
+
+#[derive(Deserialize)]
+pub struct OidcCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+#[utoipa::path(
+    path = "/user/oidc/callback",
+    params(
+        ("code" = Option<String>, Query, description = "Authorization code issued by the identity provider."),
+        ("state" = Option<String>, Query, description = "State value sent with the authorization request."),
+        ("error" = Option<String>, Query, description = "Error code returned by the identity provider."),
+    ),
+    responses(
+        (status = 200, description = "Signed in, returning the same body as /user/login, or the identity was linked to the account that started the flow."),
+        (status = 400, description = "The identity provider returned an error, or the state is invalid, expired or was not started in this browser."),
+        (status = 401, description = "The ID token could not be validated."),
+        (status = 403, description = "The account is suspended or its email address has not been verified."),
+        (status = 404, description = "OIDC login is not configured."),
+        (status = 409, description = "The identity is already linked to another account, or an account with its email address already exists and must link it first."),
+        (status = 502, description = "The identity provider could not be reached."),
+        (status = 500, description = "Internal Server Error: Unable to complete the login."),
+    )
+)]
+#[get("/oidc/callback")]
+pub async fn oidc_callback(
+    state: Data<AppState>,
+    req: HttpRequest,
+    query: Query<OidcCallbackQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let mut response: HttpResponse = finish_callback(state.as_ref(), &req, &query).await?;
+
+    response
+        .add_removal_cookie(&oidc_state_cookie(&state.config, String::new()))
+        .map_err(|_| ApiError::Internal(String::from("unable to clear the oidc state cookie")))?;
+
+    Ok(response)
+}
+
+async fn finish_callback(
+    state: &AppState,
+    req: &HttpRequest,
+    query: &OidcCallbackQuery,
+) -> Result<HttpResponse, ApiError> {
+    let provider: OidcProvider = configured_provider(state)?;
+
+    if let Some(error) = &query.error {
+        return Err(ApiError::BadRequest(format!(
+            "identity provider returned {}",
+            error
+        )));
+    }
+
+    let (code, oidc_state) = match (&query.code, &query.state) {
+        (Some(code), Some(oidc_state)) => (code, oidc_state),
+        _ => {
+            return Err(ApiError::BadRequest(String::from(
+                "code and state are required",
+            )))
+        }
+    };
+
+    let started_here: bool = req
+        .cookie(OIDC_STATE_COOKIE)
+        .is_some_and(|cookie| hash_token(cookie.value()) == hash_token(oidc_state));
+
+    if !started_here {
+        return Err(ApiError::BadRequest(String::from(
+            "oidc login was not started in this browser",
+        )));
+    }
+
+    let db: Addr<DbActor> = state.db.clone();
+
+    let login_state: OidcLoginState = db
+        .send(ConsumeOidcLoginState {
+            state_hash: hash_token(oidc_state),
+        })
+        .await??
+        .ok_or_else(|| ApiError::BadRequest(String::from("invalid or expired oidc state")))?;
+
+    let claims: IdTokenClaims = authenticate_callback(&provider, code, &login_state).await?;
+
+    let email: Option<String> = claims.email.clone();
+
+    if let Some(user_id) = login_state.user_id {
+        return link_identity(&db, req, &provider, user_id, claims).await;
+    }
+
+    if let Some(user) = db
+        .send(LoginWithIdentity {
+            provider: provider.name.clone(),
+            subject: claims.sub.clone(),
+        })
+        .await??
+    {
+        return complete_login(state, req, &user, "oidc").await;
+    }
+
+    let email: String = email.ok_or_else(|| {
+        ApiError::BadRequest(String::from(
+            "identity provider did not return an email address",
+        ))
+    })?;
+
+    match db
+        .send(LoginAndGetUser {
+            email: email.clone(),
+            password: String::new(),
+        })
+        .await?
+    {
+        Ok(_) => {
+            return Err(ApiError::Conflict(String::from(
+                "an account with this email address already exists, log in and link the identity from /auth/identities/oidc",
+            )))
+        }
+        Err(ApiError::NotFound(_)) => {}
+        Err(err) => return Err(err),
+    }
+
+    let user: User = db
+        .send(CreateUserWithIdentity {
+            username: username_from_claims(&claims, &email),
+            email,
+            email_verified: claims.email_verified,
+            provider: provider.name.clone(),
+            subject: claims.sub.clone(),
+        })
+        .await??;
+
+    record_audit_event(
+        &db,
+        req,
+        Some(user.id),
+        "user.registered",
+        Some(("user", user.id.to_string())),
+        serde_json::json!({ "email": user.email, "role": user.role, "provider": provider.name }),
+    )
+    .await;
+
+    if user.email_verified_at.is_none() {
+        if let Err(err) = send_verification_email(state, &user).await {
+            eprintln!("OIDC: unable to send verification email: {}", err);
+        }
+    }
+
+    complete_login(state, req, &user, "oidc").await
+}
+
+/// Redeems the authorization code and validates the returned ID token.
+async fn authenticate_callback(
+    provider: &OidcProvider,
+    code: &str,
+    login_state: &OidcLoginState,
+) -> Result<IdTokenClaims, ApiError> {
+    let client: Client = Client::new();
+
+    let metadata: ProviderMetadata = discover(&client, provider)
+        .await
+        .map_err(provider_unavailable)?;
+
+    let id_token: String = exchange_code(
+        &client,
+        &metadata,
+        provider,
+        code,
+        &login_state.code_verifier,
+    )
+    .await
+    .map_err(provider_unavailable)?;
+
+    validate_id_token(&client, &metadata, provider, &id_token, &login_state.nonce)
+        .await
+        .map_err(|err| {
+            eprintln!("OIDC: {}", err);
+            ApiError::Unauthorized(String::from("invalid id token"))
+        })
+}
+
+async fn link_identity(
+    db: &Addr<DbActor>,
+    req: &HttpRequest,
+    provider: &OidcProvider,
+    user_id: i32,
+    claims: IdTokenClaims,
+) -> Result<HttpResponse, ApiError> {
+    let identity: UserIdentity = db
+        .send(LinkUserIdentity {
+            user_id,
+            provider: provider.name.clone(),
+            subject: claims.sub,
+            email: claims.email,
+        })
+        .await?
+        .conflict(|| String::from("this identity is already linked to an account"))?;
+
+    record_audit_event(
+        db,
+        req,
+        Some(user_id),
+        "user.identity_linked",
+        Some(("user_identity", identity.id.to_string())),
+        serde_json::json!({ "provider": identity.provider }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(identity))
+}
+
+#[utoipa::path(
+    path = "/auth/identities/oidc",
+    responses(
+        (status = 200, description = "Returns the authorization URL to visit, and sets a cookie the callback requires, so the URL has to be opened in the same browser. Signing in there links the identity to the authenticated user."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "Identities cannot be linked with an API key."),
+        (status = 404, description = "OIDC login is not configured."),
+        (status = 502, description = "The identity provider could not be reached."),
+        (status = 500, description = "Internal Server Error: Unable to start linking."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/identities/oidc")]
+pub async fn start_identity_link(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    reject_api_key_claims(&claims)?;
+
+    let provider: OidcProvider = configured_provider(state.as_ref())?;
+
+    let (url, state_cookie) =
+        begin_authorization(state.as_ref(), &provider, Some(claims.id)).await?;
+
+    Ok(HttpResponse::Ok()
+        .cookie(state_cookie)
+        .json(serde_json::json!({ "authorization_url": url })))
+}
+
+#[utoipa::path(
+    path = "/auth/identities",
+    responses(
+        (status = 200, description = "Successfully retrieved the external identities linked to the authenticated user."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 500, description = "Internal Server Error: Unable to retrieve identities."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/identities")]
+pub async fn fetch_identities(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let identities: Vec<UserIdentity> = db
+        .send(FetchUserIdentities { user_id: claims.id })
+        .await??;
+
+    Ok(HttpResponse::Ok().json(identities))
+}
+
+#[utoipa::path(
+    path = "/auth/identities/{identity_id}",
+    responses(
+        (status = 200, description = "Identity unlinked."),
+        (status = 400, description = "The identity is the only way to sign in to an account without a password."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "Identities cannot be unlinked with an API key."),
+        (status = 404, description = "Identity not found."),
+        (status = 500, description = "Internal Server Error: Unable to unlink the identity."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/identities/{identity_id}")]
+pub async fn unlink_identity(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let identity_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    reject_api_key_claims(&claims)?;
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user: User = db.send(FetchUserById { user_id: claims.id }).await??;
+    let identities: Vec<UserIdentity> = db
+        .send(FetchUserIdentities { user_id: claims.id })
+        .await??;
+
+    if !identities.iter().any(|identity| identity.id == identity_id) {
+        return Err(ApiError::NotFound(format!(
+            "identity {} not found",
+            identity_id
+        )));
+    }
+
+    if user.password.is_none() && identities.len() == 1 {
+        return Err(ApiError::BadRequest(String::from(
+            "set a password with /user/password/forgot before unlinking your only identity",
+        )));
+    }
+
+    let rows_affected: usize = db
+        .send(UnlinkUserIdentity {
+            user_id: claims.id,
+            identity_id,
+        })
+        .await??;
+
+    if rows_affected == 0 {
+        return Err(ApiError::NotFound(format!(
+            "identity {} not found",
+            identity_id
+        )));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "user.identity_unlinked",
+        Some(("user_identity", identity_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok()
+        .json(serde_json::json!({ "message": format!("unlinked identity {}", identity_id) })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::db::test_app_state;
+    use actix_web::{body::to_bytes, http::StatusCode, test, web, App};
+    use std::collections::HashMap;
+
+    fn oidc_config() -> Config {
+        let vars: HashMap<String, String> = [
+            ("DATABASE_URL", "postgres://127.0.0.1:1/unused"),
+            ("SECRET", "test-secret"),
+            ("OIDC_ISSUER", "http://127.0.0.1:1"),
+            ("OIDC_CLIENT_ID", "rust-note-api"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        Config::from_sources(None, &vars).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn callback_requires_the_state_cookie_of_the_browser_that_started_the_flow() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(test_app_state(oidc_config())))
+                .service(web::scope("/user").service(oidc_callback)),
+        )
+        .await;
+
+        for cookie in [None, Some("state-of-another-flow")] {
+            let mut request = test::TestRequest::get()
+                .uri("/user/oidc/callback?code=some-code&state=state-of-this-flow");
+
+            if let Some(cookie) = cookie {
+                request = request.cookie(Cookie::new(OIDC_STATE_COOKIE, cookie));
+            }
+
+            let response = test::call_service(&app, request.to_request()).await;
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let body: serde_json::Value =
+                serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap();
+
+            assert_eq!(body["detail"], "oidc login was not started in this browser");
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use super::messages::*;
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::User,
+    utils::{
+        config::PasswordConfig,
+        db::{AppState, DbActor},
+        errors::ApiError,
+        mailer::{deliver, Email},
+        password_policy::{validate_password, PasswordViolation},
+        passwords::hash_password,
+        tokens::{generate_opaque_token, hash_token},
+    },
+};
+use actix::Addr;
+use actix_web::{
+    post,
+    web::{Data, Json},
+    HttpRequest, HttpResponse,
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub fn reject_weak_password(
+    config: &PasswordConfig,
+    password: &str,
+    username: &str,
+    email: &str,
+) -> Result<(), ApiError> {
+    let violations: Vec<PasswordViolation> = validate_password(config, password, username, email);
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    Err(ApiError::Validation(
+        String::from("password does not meet the password policy"),
+        serde_json::json!(violations),
+    ))
+}
+
+pub async fn send_password_reset_email(
+    state: &AppState,
+    user: &User,
+    reset_token: &str,
+) -> Result<(), String> {
+    let public_url: String = state.config.public_url();
+
+    let email: Email = Email {
+        to: user.email.clone(),
+        subject: String::from("Reset your RustNoteAPI password"),
+        body: format!(
+            "Hello {},\n\nUse the token below to reset your password. It expires in 30 minutes.\n\n{}\n\nSend it with your new password to {}/user/password/reset.\n\nIf you did not request a password reset, you can ignore this email.",
+            user.username, reset_token, public_url
+        ),
+    };
+
+    deliver(state.mailer.clone(), email).await
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ForgotPasswordRequest {
+    #[schema(example = "random@gmail.com", required = true)]
+    pub email: String,
+}
+
+#[utoipa::path(
+    path = "/user/password/forgot",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 200, description = "If an account exists for the email address, a password reset token has been emailed to it."),
+        (status = 500, description = "Internal Server Error: Unable to process the request."),
+    )
+)]
+#[post("/password/forgot")]
+pub async fn forgot_password(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<ForgotPasswordRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let reset_token: String = generate_opaque_token();
+    let expires_on: NaiveDateTime = (Utc::now() + Duration::minutes(30)).naive_utc();
+
+    let user: Option<User> = db
+        .send(CreatePasswordResetToken {
+            email: body.email.clone(),
+            token_hash: hash_token(&reset_token),
+            expires_on,
+        })
+        .await??;
+
+    if let Some(user) = user {
+        record_audit_event(
+            &db,
+            &req,
+            Some(user.id),
+            "user.password_reset_requested",
+            Some(("user", user.id.to_string())),
+            serde_json::json!({}),
+        )
+        .await;
+
+        if let Err(err) = send_password_reset_email(state.as_ref(), &user, &reset_token).await {
+            eprintln!("Forgot Password: unable to send email: {}", err);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "if the email is registered, a password reset token has been sent"
+    })))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+    #[schema(example = "mfrggzdfmztwq2lk", required = true)]
+    pub token: String,
+    #[schema(example = "random", format = "password", required = true)]
+    pub new_password: String,
+}
+
+#[utoipa::path(
+    path = "/user/password/reset",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password reset. Every existing session of the user is logged out."),
+        (status = 400, description = "The reset token is invalid, expired or already used, or the new password does not meet the password policy. Policy violations are listed in `errors`."),
+        (status = 500, description = "Internal Server Error: Unable to reset the password."),
+    )
+)]
+#[post("/password/reset")]
+pub async fn reset_password(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<ResetPasswordRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let token_hash: String = hash_token(&body.token);
+
+    let user: User = db
+        .send(FetchPasswordResetUser {
+            token_hash: token_hash.clone(),
+        })
+        .await??
+        .ok_or_else(|| ApiError::BadRequest(String::from("invalid or expired reset token")))?;
+
+    reject_weak_password(
+        &state.config.passwords,
+        &body.new_password,
+        &user.username,
+        &user.email,
+    )?;
+
+    let hashed_password: String = hash_password(&state.config.passwords, &body.new_password)
+        .map_err(|_| ApiError::Internal(String::from("password hashing failed")))?;
+
+    let user_id: i32 = db
+        .send(ResetPassword {
+            token_hash,
+            new_password: hashed_password,
+        })
+        .await??
+        .ok_or_else(|| ApiError::BadRequest(String::from("invalid or expired reset token")))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(user_id),
+        "user.password_reset",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "message": "password has been reset" })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::auth_handlers::auth_handlers::{login_user, register_user},
+        utils::{config::Config, db::test_app_state},
+    };
+    use actix_web::{body::to_bytes, http::StatusCode, test, web, App};
+    use std::{collections::HashMap, fs, path::PathBuf};
+
+    /// Returns the token of the password reset email in the outbox.
+    fn reset_token_from_outbox(outbox: &PathBuf) -> String {
+        let email: String = fs::read_dir(outbox)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .find(|contents| contents.contains("Subject: Reset your RustNoteAPI password"))
+            .expect("no password reset email in the outbox");
+
+        email
+            .lines()
+            .skip_while(|line| !line.starts_with("Use the token below"))
+            .nth(2)
+            .unwrap()
+            .to_string()
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a migrated Postgres database in TEST_DATABASE_URL"]
+    async fn forgot_and_reset_password_round_trip_through_the_outbox() {
+        let database_url: String = std::env::var("TEST_DATABASE_URL").unwrap();
+        let suffix: u32 = rand::random();
+        let outbox: PathBuf = std::env::temp_dir().join(format!("reset-outbox-{}", suffix));
+        let username: String = format!("reset{}", suffix);
+        let email: String = format!("{}@example.com", username);
+
+        let vars: HashMap<String, String> = [
+            ("DATABASE_URL", database_url.as_str()),
+            ("SECRET", "test-secret"),
+            ("MAILER", "outbox"),
+            ("MAIL_OUTBOX_DIR", outbox.to_str().unwrap()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        let state: AppState = test_app_state(Config::from_sources(None, &vars).unwrap());
+        let db: Addr<DbActor> = state.db.clone();
+
+        let app = test::init_service(
+            App::new().app_data(Data::new(state)).service(
+                web::scope("/user")
+                    .service(register_user)
+                    .service(login_user)
+                    .service(forgot_password)
+                    .service(reset_password),
+            ),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/user/register")
+                .set_json(serde_json::json!({
+                    "username": username,
+                    "email": email,
+                    "password": "Very-strong-pass-9876",
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let user: serde_json::Value =
+            serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap();
+        let user_id: i32 = user["id"].as_i64().unwrap() as i32;
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/user/password/forgot")
+                .set_json(serde_json::json!({ "email": email }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let token: String = reset_token_from_outbox(&outbox);
+        let reset = serde_json::json!({
+            "token": token,
+            "new_password": "Tangerine-Orbit-Glacier-5521",
+        });
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/user/password/reset")
+                .set_json(&reset)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/user/login")
+                .set_json(serde_json::json!({
+                    "email": email,
+                    "password": "Tangerine-Orbit-Glacier-5521",
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/user/password/reset")
+                .set_json(&reset)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        db.send(DeleteUser { user_id }).await.unwrap().unwrap();
+        fs::remove_dir_all(&outbox).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+use super::{messages::*, token_handlers::issue_refresh_token};
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::{Session, User},
+    utils::{
+        db::{AppState, DbActor},
+        errors::ApiError,
+        jwt::Claims,
+        tokens::generate_opaque_token,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get,
+    http::header::USER_AGENT,
+    web::{Data, Path},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use serde::Serialize;
+
+/// IP of the client. Forwarded headers are only honored when the server is
+/// configured to sit behind a trusted proxy, since clients can set them to
+/// anything.
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let trust_proxy_headers: bool = req
+        .app_data::<Data<AppState>>()
+        .is_some_and(|state| state.config.server.trust_proxy_headers);
+
+    if trust_proxy_headers {
+        return req
+            .connection_info()
+            .realip_remote_addr()
+            .map(|value| value.chars().take(45).collect());
+    }
+
+    req.peer_addr().map(|addr| addr.ip().to_string())
+}
+
+pub fn client_user_agent(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(255).collect())
+}
+
+pub async fn start_session(
+    state: &AppState,
+    user: &User,
+    req: &HttpRequest,
+) -> Result<(String, String), ApiError> {
+    let db: &Addr<DbActor> = &state.db;
+
+    let jti: String = generate_opaque_token();
+
+    let user_agent: Option<String> = client_user_agent(req);
+
+    let ip_address: Option<String> = client_ip(req);
+
+    db.send(CreateSession {
+        jti: jti.clone(),
+        user_id: user.id,
+        user_agent,
+        ip_address,
+    })
+    .await??;
+
+    let refresh_token: String = issue_refresh_token(db, user.id, jti.clone()).await?;
+
+    let token: String = state
+        .jwt_keys
+        .encode_jwt(user.email.clone(), user.id, user.role.clone(), jti)
+        .map_err(|_| ApiError::Internal(String::from("failed to generate token")))?;
+
+    Ok((token, refresh_token))
+}
+
+#[derive(Serialize)]
+struct SessionResponse {
+    #[serde(flatten)]
+    session: Session,
+    current: bool,
+}
+
+#[utoipa::path(
+    path = "/auth/sessions",
+    responses(
+        (status = 200, description = "Successfully retrieved the active sessions of the authenticated user."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 500, description = "Internal Server Error: Unable to retrieve sessions."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/sessions")]
+pub async fn fetch_sessions(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let sessions: Vec<SessionResponse> = db
+        .send(FetchUserSessions { user_id: claims.id })
+        .await??
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: session.jti == claims.jti,
+            session,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+#[utoipa::path(
+    path = "/auth/sessions/{session_id}",
+    responses(
+        (status = 200, description = "Session revoked. Its access and refresh tokens can no longer be used."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 404, description = "Session not found."),
+        (status = 500, description = "Internal Server Error: Unable to revoke the session."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/sessions/{session_id}")]
+pub async fn revoke_session(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let session_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let rows_affected: usize = db
+        .send(RevokeSession {
+            user_id: claims.id,
+            session_id,
+        })
+        .await??;
+
+    if rows_affected == 0 {
+        return Err(ApiError::NotFound(format!(
+            "session {} not found",
+            session_id
+        )));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "session.revoked",
+        Some(("session", session_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok()
+        .json(serde_json::json!({ "message": format!("revoked session {}", session_id) })))
+}
+
+#[utoipa::path(
+    path = "/auth/sessions",
+    responses(
+        (status = 200, description = "Logged out everywhere. Every session of the authenticated user, including the current one, is revoked."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 500, description = "Internal Server Error: Unable to revoke sessions."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/sessions")]
+pub async fn revoke_all_sessions(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let rows_affected: usize = db.send(RevokeUserSessions { user_id: claims.id }).await??;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "session.revoked_all",
+        Some(("user", claims.id.to_string())),
+        serde_json::json!({ "sessions": rows_affected }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("revoked {} sessions", rows_affected)
+    })))
+}
@@ -0,0 +1,130 @@
+use super::{
+    auth_handlers::{build_csrf_cookie, build_token_cookie},
+    messages::*,
+};
+use crate::utils::{
+    db::{AppState, DbActor},
+    errors::ApiError,
+    jwt::{ACCESS_TOKEN_MINUTES, REFRESH_TOKEN_DAYS},
+    tokens::{generate_opaque_token, hash_token},
+};
+use actix::Addr;
+use actix_web::{
+    cookie::time::{Duration, OffsetDateTime},
+    get,
+    http::header::{CacheControl, CacheDirective},
+    post,
+    web::{Data, Json},
+    HttpResponse, Responder,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+fn refresh_token_expiry() -> NaiveDateTime {
+    (Utc::now() + chrono::Duration::days(REFRESH_TOKEN_DAYS)).naive_utc()
+}
+
+pub async fn issue_refresh_token(
+    db: &Addr<DbActor>,
+    user_id: i32,
+    family_id: String,
+) -> Result<String, ApiError> {
+    let refresh_token: String = generate_opaque_token();
+
+    db.send(CreateRefreshToken {
+        user_id,
+        token_hash: hash_token(&refresh_token),
+        family_id,
+        expires_on: refresh_token_expiry(),
+    })
+    .await??;
+
+    Ok(refresh_token)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshTokenRequest {
+    #[schema(example = "mfrggzdfmztwq2lk", required = true)]
+    pub refresh_token: String,
+}
+
+#[utoipa::path(
+    path = "/user/token/refresh",
+    request_body = RefreshTokenRequest,
+    responses(
+        (status = 200, description = "Returns a new access token and a new refresh token. The submitted refresh token can no longer be used."),
+        (status = 401, description = "Refresh token is invalid, expired or was already used. Reusing a refresh token revokes every token issued from the same login."),
+        (status = 500, description = "Internal Server Error: Unable to refresh the token."),
+    )
+)]
+#[post("/token/refresh")]
+pub async fn refresh_token_handler(
+    state: Data<AppState>,
+    body: Json<RefreshTokenRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let new_refresh_token: String = generate_opaque_token();
+
+    let (user, jti) = match db
+        .send(RotateRefreshToken {
+            token_hash: hash_token(&body.refresh_token),
+            new_token_hash: hash_token(&new_refresh_token),
+            expires_on: refresh_token_expiry(),
+        })
+        .await??
+    {
+        RefreshOutcome::Rotated(user, jti) => (user, jti),
+        RefreshOutcome::Reused => {
+            return Err(ApiError::Unauthorized(String::from(
+                "refresh token reuse detected, please log in again",
+            )))
+        }
+        RefreshOutcome::Expired => {
+            return Err(ApiError::Unauthorized(String::from(
+                "refresh token has expired",
+            )))
+        }
+        RefreshOutcome::Invalid => {
+            return Err(ApiError::Unauthorized(String::from(
+                "invalid refresh token",
+            )))
+        }
+    };
+
+    let token: String = state
+        .jwt_keys
+        .encode_jwt(user.email.clone(), user.id, user.role, jti)
+        .map_err(|_| ApiError::Internal(String::from("failed to generate token")))?;
+
+    let expires: OffsetDateTime =
+        OffsetDateTime::now_utc() + Duration::minutes(ACCESS_TOKEN_MINUTES);
+
+    Ok(HttpResponse::Ok()
+        .cookie(build_token_cookie(&state.config, token.clone(), expires))
+        .cookie(build_csrf_cookie(
+            &state.config,
+            generate_opaque_token(),
+            expires,
+        ))
+        .json(serde_json::json!({
+            "token": token,
+            "refresh_token": new_refresh_token,
+        })))
+}
+
+#[utoipa::path(
+    path = "/.well-known/jwks.json",
+    responses(
+        (status = 200, description = "Public keys that verify access tokens, identified by the kid header of the token. Empty when tokens are signed with a shared secret."),
+    )
+)]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks_handler(state: Data<AppState>) -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(300),
+        ]))
+        .json(state.jwt_keys.jwks())
+}
@@ -1,21 +1,92 @@
+use super::{
+    api_key_handlers::reject_api_key_claims,
+    auth_handlers::{build_csrf_cookie, build_token_cookie},
+    session_handlers::{client_ip, start_session},
+    utils::UserResponse,
+};
 use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::{OTPRecoveryCode, User},
     utils::{
         db::{AppState, DbActor},
-        jwt::Claims,
+        errors::ApiError,
+        jwt::{Claims, ACCESS_TOKEN_MINUTES},
+        passwords::{hash_password, verify_password},
+        tokens::{generate_opaque_token, generate_recovery_code, normalize_recovery_code},
     },
-    LoginAndGetUser, OTPMessage,
+    CheckLoginThrottle, ConsumeRecoveryCode, FetchRecoveryCodes, LoginAndGetUser, OTPMessage,
+    RecordLoginAttempt, ReplaceRecoveryCodes,
 };
 use actix::Addr;
 use actix_web::{
+    cookie::time::{Duration, OffsetDateTime},
     get, post,
     web::{Data, Json},
-    HttpMessage, HttpRequest, HttpResponse, Responder,
+    HttpMessage, HttpRequest, HttpResponse,
 };
+use chrono::Utc;
 use rand::{rngs::ThreadRng, Rng};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use totp_rs::{Algorithm, Secret, TOTP};
 use utoipa::ToSchema;
 
+const RECOVERY_CODE_COUNT: usize = 10;
+
+fn check_otp(otp_base32: String, otp_token: &str) -> bool {
+    match Secret::Encoded(otp_base32).to_bytes() {
+        Ok(secret) => match TOTP::new(Algorithm::SHA1, 6, 1, 30, secret) {
+            Ok(totp) => totp.check_current(otp_token).unwrap_or(false),
+            Err(_) => false,
+        },
+        Err(_) => false,
+    }
+}
+
+async fn issue_recovery_codes(state: &AppState, user_id: i32) -> Result<Vec<String>, ApiError> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+
+    let mut code_hashes: Vec<String> = Vec::with_capacity(codes.len());
+
+    for code in &codes {
+        let code_hash: String =
+            hash_password(&state.config.passwords, &normalize_recovery_code(code)).map_err(
+                |_| ApiError::Internal(String::from("failed to generate recovery codes")),
+            )?;
+        code_hashes.push(code_hash);
+    }
+
+    state
+        .db
+        .send(ReplaceRecoveryCodes {
+            user_id,
+            code_hashes,
+        })
+        .await??;
+
+    Ok(codes)
+}
+
+async fn redeem_recovery_code(
+    db: &Addr<DbActor>,
+    user_id: i32,
+    recovery_code: &str,
+) -> Result<bool, ApiError> {
+    let recovery_code: String = normalize_recovery_code(recovery_code);
+
+    let codes: Vec<OTPRecoveryCode> = db.send(FetchRecoveryCodes { user_id }).await??;
+
+    let matching_code: Option<OTPRecoveryCode> = codes
+        .into_iter()
+        .find(|code| verify_password(&recovery_code, &code.code_hash));
+
+    match matching_code {
+        Some(code) => Ok(db.send(ConsumeRecoveryCode { code_id: code.id }).await?? > 0),
+        None => Ok(false),
+    }
+}
+
 #[utoipa::path(
     path = "/auth/otp/generate",
     responses(
@@ -27,13 +98,15 @@ use utoipa::ToSchema;
         ("bearer_auth" = [])
     )
 )]
-#[get("/otp/generate")]
-pub async fn generate_otp_handler(state: Data<AppState>, req: HttpRequest) -> impl Responder {
+#[post("/otp/generate")]
+pub async fn generate_otp_handler(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({"message": "unauthorized access"}));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
@@ -62,24 +135,20 @@ pub async fn generate_otp_handler(state: Data<AppState>, req: HttpRequest) -> im
     let otp_verified: bool = false;
     let otp_enabled: bool = true;
 
-    match db
-        .send(OTPMessage {
-            email,
-            otp_verified,
-            otp_enabled,
-            otp_auth_url: Some(otp_auth_url.clone()),
-            otp_base32: Some(otp_base32.clone()),
-        })
-        .await
-    {
-        Ok(Ok(_)) => HttpResponse::Ok().json(serde_json::json!({
-            "status": "success",
-            "otp_auth_url": otp_auth_url,
-            "otp_base32": otp_base32,
-        })),
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "failed to generate otp" })),
-    }
+    db.send(OTPMessage {
+        email,
+        otp_verified,
+        otp_enabled,
+        otp_auth_url: Some(otp_auth_url.clone()),
+        otp_base32: Some(otp_base32.clone()),
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "otp_auth_url": otp_auth_url,
+        "otp_base32": otp_base32,
+    })))
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -88,16 +157,12 @@ pub struct VerifyOTPRequest {
     pub otp_token: String,
 }
 
-#[derive(Serialize)]
-pub struct GenericResponse {
-    pub status: String,
-    pub message: String,
-}
 #[utoipa::path(
     path = "/auth/otp/verify",
     request_body = VerifyOTPRequest,
     responses(
-        (status = 200, description = "OTP successfully verified, returns updated user details with OTP verification status"),
+        (status = 200, description = "OTP successfully verified, returns updated user details with OTP verification status. The first verification also returns single-use recovery codes, which are shown only once."),
+        (status = 400, description = "No OTP secret has been generated yet."),
         (status = 403, description = "Invalid OTP token."),
         (status = 500, description = "Failed to verify OTP or update OTP status."),
         (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
@@ -111,94 +176,88 @@ pub async fn verify_otp_handler(
     state: Data<AppState>,
     req: HttpRequest,
     body: Json<VerifyOTPRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized().json(GenericResponse {
-                status: String::from("fail"),
-                message: String::from("unauthorized access"),
-            });
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let db: Addr<DbActor> = state.as_ref().db.clone();
     let user_email: String = claims.email.clone();
 
-    match db
+    let user = db
         .send(LoginAndGetUser {
             email: user_email.clone(),
             password: String::new(),
         })
-        .await
-    {
-        Ok(Ok(user)) => {
-            let otp_base32: String = user.otp_base32.clone().unwrap();
-
-            let totp: TOTP = TOTP::new(
-                Algorithm::SHA1,
-                6,
-                1,
-                30,
-                Secret::Encoded(otp_base32).to_bytes().unwrap(),
-            )
-            .unwrap();
-
-            let is_valid: bool = totp.check_current(&body.otp_token).unwrap();
-
-            if !is_valid {
-                let json_error: GenericResponse = GenericResponse {
-                    status: String::from("fail"),
-                    message: String::from("invalid token"),
-                };
-                return HttpResponse::Forbidden().json(json_error);
-            }
-
-            let otp_verified: bool = true;
-            let otp_enabled: bool = true;
-
-            match db
-                .send(OTPMessage {
-                    email: user_email,
-                    otp_verified,
-                    otp_enabled,
-                    otp_base32: Some(user.otp_base32.unwrap().clone()),
-                    otp_auth_url: Some(user.otp_auth_url.unwrap().clone()),
-                })
-                .await
-            {
-                Ok(Ok(updated_user)) => HttpResponse::Ok().json(serde_json::json!({
-                    "otp_verified": true,
-                    "user": updated_user,
-                })),
-                _ => HttpResponse::InternalServerError().json(GenericResponse {
-                    status: String::from("fail"),
-                    message: String::from("failed to update otp status"),
-                }),
-            }
-        }
-        Ok(Err(_)) => HttpResponse::InternalServerError().json(GenericResponse {
-            status: String::from("fail"),
-            message: String::from("failed to retrieve user"),
-        }),
-        _ => HttpResponse::InternalServerError().json(GenericResponse {
-            status: String::from("fail"),
-            message: String::from("internal server error"),
-        }),
+        .await??;
+
+    let (Some(otp_base32), Some(otp_auth_url)) = (user.otp_base32, user.otp_auth_url) else {
+        return Err(ApiError::BadRequest(String::from(
+            "generate an otp secret first",
+        )));
+    };
+
+    if !check_otp(otp_base32.clone(), &body.otp_token) {
+        return Err(ApiError::Forbidden(String::from("invalid token")));
     }
+
+    let otp_verified: bool = true;
+    let otp_enabled: bool = true;
+    let first_verification: bool = !user.otp_verified.unwrap_or(false);
+
+    let updated_user = db
+        .send(OTPMessage {
+            email: user_email,
+            otp_verified,
+            otp_enabled,
+            otp_base32: Some(otp_base32),
+            otp_auth_url: Some(otp_auth_url),
+        })
+        .await??;
+
+    if !first_verification {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "otp_verified": true,
+            "user": updated_user,
+        })));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(updated_user.id),
+        "user.otp_enabled",
+        Some(("user", updated_user.id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    let recovery_codes: Vec<String> = issue_recovery_codes(state.as_ref(), updated_user.id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "otp_verified": true,
+        "user": updated_user,
+        "recovery_codes": recovery_codes,
+    })))
 }
 
 #[derive(Deserialize, ToSchema)]
 pub struct ValidateOTPRequest {
     #[schema(example = "123456")]
-    pub otp_token: String,
+    pub otp_token: Option<String>,
+    #[schema(example = "abcde-fghij")]
+    pub recovery_code: Option<String>,
 }
 #[utoipa::path(
     path = "/auth/otp/validate",
     request_body = ValidateOTPRequest,
     responses(
-        (status = 200, description = "OTP successfully validated, returns the user details if OTP is valid."),
-        (status = 403, description = "OTP not validated or invalid OTP token."),
+        (status = 200, description = "OTP successfully validated, returns a session token and a refresh token along with the user details. Accepts the challenge token issued by /user/login."),
+        (status = 403, description = "OTP not validated, or invalid OTP token or recovery code."),
+        (status = 429, description = "Too many failed attempts. Failed codes count towards the same lockout as failed passwords; see the Retry-After header."),
         (status = 500, description = "Failed to validate OTP or retrieve user."),
         (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
     ),
@@ -206,98 +265,238 @@ pub struct ValidateOTPRequest {
         ("bearer_auth" = [])
     )
 )]
-#[post("/otp/validate")]
+#[post("")]
 pub async fn token_validate_handler(
     state: Data<AppState>,
     req: HttpRequest,
     body: Json<ValidateOTPRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized().json(GenericResponse {
-                status: String::from("fail"),
-                message: String::from("unauthorized access"),
-            });
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let user_email: String = claims.email.clone();
-    let otp_token: String = body.otp_token.clone();
 
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    match db
+    let user = db
         .send(LoginAndGetUser {
             email: user_email.clone(),
             password: String::new(),
         })
-        .await
+        .await??;
+
+    if user.suspended_at.is_some() {
+        return Err(ApiError::Forbidden(String::from("account is suspended")));
+    }
+
+    if !user.otp_verified.unwrap_or(false) {
+        return Err(ApiError::Forbidden(String::from("otp not validated")));
+    }
+
+    let ip_address: Option<String> = client_ip(&req);
+
+    if let Some(retry_on) = db
+        .send(CheckLoginThrottle {
+            user_id: user.id,
+            ip_address: ip_address.clone(),
+        })
+        .await??
     {
-        Ok(Ok(user)) => {
-            if !user.otp_verified.unwrap_or(false) {
-                return HttpResponse::Forbidden().json(GenericResponse {
-                    status: String::from("fail"),
-                    message: String::from("otp not validated"),
-                });
-            }
-
-            let otp_base32: String = user.otp_base32.clone().unwrap();
-            let totp: TOTP = TOTP::new(
-                Algorithm::SHA1,
-                6,
-                1,
-                30,
-                Secret::Encoded(otp_base32).to_bytes().unwrap(),
-            )
-            .unwrap();
+        let retry_after: i64 = (retry_on - Utc::now()).num_seconds().max(1);
+
+        record_audit_event(
+            &db,
+            &req,
+            Some(user.id),
+            "user.login_throttled",
+            Some(("user", user.id.to_string())),
+            serde_json::json!({ "retry_after": retry_after, "mfa": true }),
+        )
+        .await;
+
+        return Err(ApiError::RateLimited(
+            String::from("too many failed login attempts, try again later"),
+            retry_after,
+        ));
+    }
 
-            let is_valid: bool = totp.check_current(&otp_token).unwrap();
+    let is_valid: bool = match (&body.otp_token, &body.recovery_code) {
+        (_, Some(recovery_code)) => redeem_recovery_code(&db, user.id, recovery_code).await?,
+        (Some(otp_token), None) => {
+            check_otp(user.otp_base32.clone().unwrap_or_default(), otp_token)
+        }
+        (None, None) => false,
+    };
+
+    let method: &str = if body.recovery_code.is_some() {
+        "recovery_code"
+    } else {
+        "otp"
+    };
+
+    let attempted_user: User = db
+        .send(RecordLoginAttempt {
+            user_id: user.id,
+            ip_address,
+            succeeded: is_valid,
+            second_factor_pending: false,
+        })
+        .await??;
 
-            if !is_valid {
-                return HttpResponse::Forbidden().json(GenericResponse {
-                    status: String::from("fail"),
-                    message: String::from("invalid otp token"),
-                });
-            }
+    if !is_valid {
+        record_audit_event(
+            &db,
+            &req,
+            Some(user.id),
+            "user.otp_failed",
+            Some(("user", user.id.to_string())),
+            serde_json::json!({
+                "method": method,
+                "failed_login_count": attempted_user.failed_login_count,
+            }),
+        )
+        .await;
 
-            HttpResponse::Ok().json(serde_json::json!({ "status": "success", "user": user }))
+        if attempted_user.locked_until != user.locked_until {
+            record_audit_event(
+                &db,
+                &req,
+                Some(user.id),
+                "user.locked",
+                Some(("user", user.id.to_string())),
+                serde_json::json!({ "locked_until": attempted_user.locked_until }),
+            )
+            .await;
         }
-        Ok(Err(_)) => HttpResponse::InternalServerError().json(GenericResponse {
-            status: String::from("fail"),
-            message: String::from("failed to retrieve user"),
-        }),
-        Err(_) => HttpResponse::InternalServerError().json(GenericResponse {
-            status: String::from("fail"),
-            message: String::from("internal server error"),
-        }),
+
+        return Err(ApiError::Forbidden(String::from(
+            "invalid otp token or recovery code",
+        )));
     }
+
+    let (token, refresh_token) = start_session(state.as_ref(), &user, &req).await?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(user.id),
+        "user.login",
+        Some(("user", user.id.to_string())),
+        serde_json::json!({ "mfa": true, "method": method }),
+    )
+    .await;
+
+    let expires: OffsetDateTime =
+        OffsetDateTime::now_utc() + Duration::minutes(ACCESS_TOKEN_MINUTES);
+
+    Ok(HttpResponse::Ok()
+        .cookie(build_token_cookie(&state.config, token.clone(), expires))
+        .cookie(build_csrf_cookie(
+            &state.config,
+            generate_opaque_token(),
+            expires,
+        ))
+        .json(serde_json::json!({
+            "status": "success",
+            "token": token,
+            "refresh_token": refresh_token,
+            "user": UserResponse::from(user),
+        })))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DisableOTPRequest {
+    #[schema(example = "123456")]
+    pub otp_token: Option<String>,
+    #[schema(example = "abcde-fghij")]
+    pub recovery_code: Option<String>,
 }
 
 #[utoipa::path(
     path = "/auth/otp/disable",
+    request_body = DisableOTPRequest,
     responses(
-        (status = 200, description = "OTP successfully disabled, returns confirmation."),
-        (status = 500, description = "Failed to disable OTP."),
+        (status = 200, description = "OTP and recovery codes removed, returns confirmation. Requires a current OTP token or an unused recovery code."),
+        (status = 400, description = "OTP is not enabled."),
         (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "Invalid OTP token or recovery code, or the request was made with an API key."),
+        (status = 429, description = "Too many failed attempts. Failed codes count towards the login lockout; see the Retry-After header."),
+        (status = 500, description = "Failed to disable OTP."),
     ),
     security(
         ("bearer_auth" = [])
     )
 )]
-#[get("/otp/disable")]
-pub async fn disable_otp_handler(state: Data<AppState>, req: HttpRequest) -> impl Responder {
+#[post("/otp/disable")]
+pub async fn disable_otp_handler(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<DisableOTPRequest>,
+) -> Result<HttpResponse, ApiError> {
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({"message": "unauthorized access"}));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
+    reject_api_key_claims(&claims)?;
+
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    match db
+    let user: User = db
+        .send(LoginAndGetUser {
+            email: claims.email.clone(),
+            password: String::new(),
+        })
+        .await??;
+
+    if !user.otp_enabled.unwrap_or(false) {
+        return Err(ApiError::BadRequest(String::from("otp is not enabled")));
+    }
+
+    let ip_address: Option<String> = client_ip(&req);
+
+    if let Some(retry_on) = db
+        .send(CheckLoginThrottle {
+            user_id: user.id,
+            ip_address: ip_address.clone(),
+        })
+        .await??
+    {
+        return Err(ApiError::RateLimited(
+            String::from("too many failed attempts, try again later"),
+            (retry_on - Utc::now()).num_seconds().max(1),
+        ));
+    }
+
+    let is_valid: bool = match (&body.otp_token, &body.recovery_code) {
+        (_, Some(recovery_code)) => redeem_recovery_code(&db, user.id, recovery_code).await?,
+        (Some(otp_token), None) => {
+            check_otp(user.otp_base32.clone().unwrap_or_default(), otp_token)
+        }
+        (None, None) => false,
+    };
+
+    if !is_valid {
+        db.send(RecordLoginAttempt {
+            user_id: user.id,
+            ip_address,
+            succeeded: false,
+            second_factor_pending: false,
+        })
+        .await??;
+
+        return Err(ApiError::Forbidden(String::from(
+            "invalid otp token or recovery code",
+        )));
+    }
+
+    let data = db
         .send(OTPMessage {
             email: claims.email,
             otp_verified: false,
@@ -305,13 +504,120 @@ pub async fn disable_otp_handler(state: Data<AppState>, req: HttpRequest) -> imp
             otp_auth_url: Some(String::new()),
             otp_base32: Some(String::new()),
         })
-        .await
-    {
-        Ok(Ok(data)) => HttpResponse::Ok().json(serde_json::json!({
-            "status": "success",
-            "data": data,
-        })),
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "failed to disable otp" })),
+        .await??;
+
+    db.send(ReplaceRecoveryCodes {
+        user_id: data.id,
+        code_hashes: Vec::new(),
+    })
+    .await??;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(data.id),
+        "user.otp_disabled",
+        Some(("user", data.id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "otp_enabled": false,
+    })))
+}
+
+#[utoipa::path(
+    path = "/auth/otp/recovery-codes",
+    responses(
+        (status = 200, description = "Returns the number of unused recovery codes."),
+        (status = 500, description = "Failed to retrieve recovery codes."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/otp/recovery-codes")]
+pub async fn recovery_codes_handler(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let codes: Vec<OTPRecoveryCode> = db.send(FetchRecoveryCodes { user_id: claims.id }).await??;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "remaining": codes.len(),
+    })))
+}
+
+#[utoipa::path(
+    path = "/auth/otp/recovery-codes/regenerate",
+    request_body = VerifyOTPRequest,
+    responses(
+        (status = 200, description = "Returns a new set of recovery codes. Previously issued codes stop working."),
+        (status = 403, description = "OTP not enabled or invalid OTP token."),
+        (status = 500, description = "Failed to generate recovery codes."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/otp/recovery-codes/regenerate")]
+pub async fn regenerate_recovery_codes_handler(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<VerifyOTPRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = db
+        .send(LoginAndGetUser {
+            email: claims.email.clone(),
+            password: String::new(),
+        })
+        .await??;
+
+    if !user.otp_verified.unwrap_or(false) {
+        return Err(ApiError::Forbidden(String::from("otp not validated")));
+    }
+
+    if !check_otp(user.otp_base32.unwrap_or_default(), &body.otp_token) {
+        return Err(ApiError::Forbidden(String::from("invalid otp token")));
     }
+
+    let recovery_codes: Vec<String> = issue_recovery_codes(state.as_ref(), user.id).await?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(user.id),
+        "user.recovery_codes_regenerated",
+        Some(("user", user.id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "recovery_codes": recovery_codes,
+    })))
 }
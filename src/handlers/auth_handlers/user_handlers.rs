@@ -1,12 +1,14 @@
 use crate::{
+    middlewares::auth_middlewares::require_permission,
     utils::{
         db::{AppState, DbActor},
+        errors::ApiError,
         jwt::Claims,
     },
     FetchUser, LoginAndGetUser,
 };
 use actix::Addr;
-use actix_web::{get, web::Data, HttpMessage, HttpRequest, HttpResponse, Responder};
+use actix_web::{get, web::Data, HttpMessage, HttpRequest, HttpResponse};
 
 #[utoipa::path(
     path = "/admin/dashboard/users",
@@ -19,20 +21,13 @@ use actix_web::{get, web::Data, HttpMessage, HttpRequest, HttpResponse, Responde
         ("bearer_auth" = [])
     )
 )]
-#[get("/users")]
-pub async fn fetch_users(state: Data<AppState>) -> impl Responder {
+#[get("/users", wrap = "require_permission(\"users:read_all\")")]
+pub async fn fetch_users(state: Data<AppState>) -> Result<HttpResponse, ApiError> {
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    match db.send(FetchUser).await {
-        Ok(Ok(users)) => HttpResponse::Ok().json(users),
+    let users = db.send(FetchUser).await??;
 
-        Ok(Err(_)) => {
-            HttpResponse::NotFound().json(serde_json::json!({ "message": "no users found" }))
-        }
-
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "unable to retrieve users" })),
-    }
+    Ok(HttpResponse::Ok().json(users))
 }
 
 #[utoipa::path(
@@ -47,30 +42,33 @@ pub async fn fetch_users(state: Data<AppState>) -> impl Responder {
     )
 )]
 #[get("/user")]
-pub async fn get_user(state: Data<AppState>, req: HttpRequest) -> impl Responder {
+pub async fn get_user(state: Data<AppState>, req: HttpRequest) -> Result<HttpResponse, ApiError> {
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({"message": "unauthorized access"}));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    match db
+    let user = match db
         .send(LoginAndGetUser {
             email: claims.email.clone(),
             password: String::new(),
         })
-        .await
+        .await?
     {
-        Ok(Ok(user)) => HttpResponse::Ok().json(serde_json::json!({
-            "user": user
-        })),
-        Ok(Err(_)) => HttpResponse::Unauthorized()
-            .json(serde_json::json!({ "message": "invalid email or password" })),
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "unable to retrieve user" })),
-    }
+        Ok(user) => user,
+        Err(ApiError::NotFound(_)) => {
+            return Err(ApiError::Unauthorized(String::from(
+                "invalid email or password",
+            )))
+        }
+        Err(err) => return Err(err),
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "user": user
+    })))
 }
@@ -0,0 +1,38 @@
+use crate::models::User;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A user as returned by the API. Leaves out the password hash and the TOTP
+/// secret, which must never leave the server once 2FA has been set up.
+#[derive(Serialize)]
+pub struct UserResponse {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    pub otp_verified: Option<bool>,
+    pub otp_enabled: Option<bool>,
+    pub role: String,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub password_reset_required: bool,
+    pub failed_login_count: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> UserResponse {
+        UserResponse {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            otp_verified: user.otp_verified,
+            otp_enabled: user.otp_enabled,
+            role: user.role,
+            email_verified_at: user.email_verified_at,
+            suspended_at: user.suspended_at,
+            password_reset_required: user.password_reset_required,
+            failed_login_count: user.failed_login_count,
+            locked_until: user.locked_until,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use super::messages::*;
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::User,
+    utils::{
+        db::{AppState, DbActor},
+        errors::ApiError,
+        jwt::Claims,
+        mailer::{deliver, Email},
+        tokens::{sign_payload, verify_payload_signature},
+    },
+};
+use actix::Addr;
+use actix_web::{
+    get, post,
+    web::{Data, Json, Query},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// How long the unauthenticated resend endpoint waits before emailing the
+/// same address again.
+const RESEND_COOLDOWN_MINUTES: i64 = 5;
+
+fn verification_payload(user_id: i32, email: &str, expires: i64) -> String {
+    format!("verify-email:{}:{}:{}", user_id, email, expires)
+}
+
+pub async fn send_verification_email(state: &AppState, user: &User) -> Result<(), String> {
+    let expires: i64 = (Utc::now() + Duration::hours(24)).timestamp();
+    let signature: String = sign_payload(
+        &state.config.auth.secret,
+        &verification_payload(user.id, &user.email, expires),
+    );
+    let public_url: String = state.config.public_url();
+
+    let email: Email = Email {
+        to: user.email.clone(),
+        subject: String::from("Verify your RustNoteAPI email address"),
+        body: format!(
+            "Hello {},\n\nOpen the link below to verify your email address. It expires in 24 hours.\n\n{}/user/verify-email?user_id={}&expires={}&signature={}",
+            user.username, public_url, user.id, expires, signature
+        ),
+    };
+
+    deliver(state.mailer.clone(), email).await
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailQuery {
+    user_id: i32,
+    expires: i64,
+    signature: String,
+}
+
+#[utoipa::path(
+    path = "/user/verify-email",
+    params(
+        ("user_id" = i32, Query, description = "Id of the user the link was sent to."),
+        ("expires" = i64, Query, description = "Unix timestamp after which the link is no longer valid."),
+        ("signature" = String, Query, description = "Signature of the verification link."),
+    ),
+    responses(
+        (status = 200, description = "Email address verified."),
+        (status = 400, description = "The verification link is invalid or has expired."),
+        (status = 500, description = "Internal Server Error: Unable to verify the email address."),
+    )
+)]
+#[get("/verify-email")]
+pub async fn verify_email(
+    state: Data<AppState>,
+    req: HttpRequest,
+    query: Query<VerifyEmailQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if query.expires < Utc::now().timestamp() {
+        return Err(ApiError::BadRequest(String::from(
+            "verification link has expired",
+        )));
+    }
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user: User = match db
+        .send(FetchUserById {
+            user_id: query.user_id,
+        })
+        .await?
+    {
+        Ok(user) => user,
+        Err(ApiError::NotFound(_)) => {
+            return Err(ApiError::BadRequest(String::from(
+                "invalid verification link",
+            )))
+        }
+        Err(err) => return Err(err),
+    };
+
+    let payload: String = verification_payload(user.id, &user.email, query.expires);
+
+    if !verify_payload_signature(&state.config.auth.secret, &payload, &query.signature) {
+        return Err(ApiError::BadRequest(String::from(
+            "invalid verification link",
+        )));
+    }
+
+    let verified_user: User = db
+        .send(VerifyUserEmail {
+            user_id: user.id,
+            email: user.email,
+        })
+        .await??
+        .ok_or_else(|| ApiError::BadRequest(String::from("invalid verification link")))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(verified_user.id),
+        "user.email_verified",
+        Some(("user", verified_user.id.to_string())),
+        serde_json::json!({ "email": verified_user.email }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "message": "email address verified" })))
+}
+
+#[utoipa::path(
+    path = "/auth/email/resend",
+    responses(
+        (status = 200, description = "A new verification link has been emailed."),
+        (status = 400, description = "The email address is already verified."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 500, description = "Internal Server Error: Unable to send the verification email."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/email/resend")]
+pub async fn resend_verification_email(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user: User = db
+        .send(LoginAndGetUser {
+            email: claims.email.clone(),
+            password: String::new(),
+        })
+        .await??;
+
+    if user.email_verified_at.is_some() {
+        return Err(ApiError::BadRequest(String::from(
+            "email address is already verified",
+        )));
+    }
+
+    send_verification_email(state.as_ref(), &user)
+        .await
+        .map_err(|_| ApiError::Internal(String::from("failed to send verification email")))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "message": "verification email sent" })))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResendVerificationRequest {
+    #[schema(example = "random@gmail.com", required = true)]
+    pub email: String,
+}
+
+#[utoipa::path(
+    path = "/user/email/resend",
+    request_body = ResendVerificationRequest,
+    responses(
+        (status = 200, description = "If an account with an unverified email address exists for it, a new verification link has been emailed, at most once every 5 minutes. For users who cannot sign in before verifying."),
+        (status = 500, description = "Internal Server Error: Unable to process the request."),
+    )
+)]
+#[post("/email/resend")]
+pub async fn resend_verification_email_by_address(
+    state: Data<AppState>,
+    body: Json<ResendVerificationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user: Option<User> = db
+        .send(FindUserByEmail {
+            email: body.email.clone(),
+        })
+        .await??;
+
+    if let Some(user) = user.filter(|user| user.email_verified_at.is_none()) {
+        let stamped: usize = db
+            .send(StampVerificationEmailSent {
+                user_id: user.id,
+                not_sent_since: Utc::now() - Duration::minutes(RESEND_COOLDOWN_MINUTES),
+            })
+            .await??;
+
+        // Sent in the background so the response time does not reveal
+        // whether the address is registered.
+        if stamped > 0 {
+            actix_web::rt::spawn(async move {
+                if let Err(err) = send_verification_email(state.as_ref(), &user).await {
+                    eprintln!("Resend Verification: unable to send email: {}", err);
+                }
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "if the email is registered and not yet verified, a verification link has been sent"
+    })))
+}
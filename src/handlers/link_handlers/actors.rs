@@ -0,0 +1,108 @@
+use super::insertables::NewNoteLink;
+use super::messages::*;
+use crate::models::{Note, NoteLink};
+use crate::schema::{note_links, notes};
+use crate::utils::db::{DbActor, DbResult};
+use actix::Handler;
+use chrono::Utc;
+use diesel::prelude::*;
+
+impl Handler<FetchNoteLinks> for DbActor {
+    type Result = DbResult<Vec<NoteLink>>;
+
+    fn handle(&mut self, msg: FetchNoteLinks, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(note_links::table
+            .filter(note_links::note_id.eq(msg.note_id))
+            .order(note_links::created_on.desc())
+            .get_results::<NoteLink>(&mut connection)?)
+    }
+}
+
+impl Handler<CreateNoteLink> for DbActor {
+    type Result = DbResult<NoteLink>;
+
+    fn handle(&mut self, msg: CreateNoteLink, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::insert_into(note_links::table)
+            .values(NewNoteLink {
+                note_id: msg.note_id,
+                slug: msg.slug,
+                password_hash: msg.password_hash,
+                expires_at: msg.expires_at,
+                created_by: msg.created_by,
+            })
+            .get_result::<NoteLink>(&mut connection)?)
+    }
+}
+
+impl Handler<RevokeNoteLink> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: RevokeNoteLink, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(
+            note_links::table
+                .filter(note_links::id.eq(msg.link_id))
+                .filter(note_links::note_id.eq(msg.note_id))
+                .filter(note_links::revoked_at.is_null()),
+        )
+        .set(note_links::revoked_at.eq(Utc::now()))
+        .execute(&mut connection)?)
+    }
+}
+
+impl Handler<FetchPublicNote> for DbActor {
+    type Result = DbResult<(NoteLink, Note)>;
+
+    fn handle(&mut self, msg: FetchPublicNote, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(note_links::table
+            .inner_join(notes::table)
+            .filter(note_links::slug.eq(msg.slug))
+            .filter(note_links::revoked_at.is_null())
+            .filter(
+                note_links::expires_at
+                    .is_null()
+                    .or(note_links::expires_at.gt(Utc::now())),
+            )
+            .filter(notes::deleted_at.is_null())
+            .select((note_links::all_columns, notes::all_columns))
+            .first::<(NoteLink, Note)>(&mut connection)?)
+    }
+}
+
+impl Handler<RecordLinkView> for DbActor {
+    type Result = DbResult<i64>;
+
+    fn handle(&mut self, msg: RecordLinkView, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(note_links::table.find(msg.link_id))
+            .set((
+                note_links::view_count.eq(note_links::view_count + 1),
+                note_links::failed_password_count.eq(0),
+            ))
+            .returning(note_links::view_count)
+            .get_result::<i64>(&mut connection)?)
+    }
+}
+
+impl Handler<RecordLinkPasswordFailure> for DbActor {
+    type Result = DbResult<NoteLink>;
+
+    fn handle(&mut self, msg: RecordLinkPasswordFailure, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(note_links::table.find(msg.link_id))
+            .set((
+                note_links::failed_password_count.eq(note_links::failed_password_count + 1),
+                note_links::last_failed_password_at.eq(Utc::now()),
+            ))
+            .get_result::<NoteLink>(&mut connection)?)
+    }
+}
@@ -0,0 +1,14 @@
+use crate::schema::note_links;
+use chrono::{DateTime, Utc};
+use diesel::Insertable;
+use serde::Serialize;
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=note_links)]
+pub struct NewNoteLink {
+    pub note_id: i32,
+    pub slug: String,
+    pub password_hash: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: i32,
+}
@@ -0,0 +1,234 @@
+use super::messages::*;
+use crate::{
+    handlers::{
+        audit_handlers::audit_handlers::record_audit_event,
+        share_handlers::utils::{authorize_note, NoteAccess},
+    },
+    models::NoteLink,
+    utils::{
+        db::{AppState, DbActor},
+        errors::ApiError,
+        jwt::Claims,
+        passwords::hash_password,
+        tokens::generate_opaque_token,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize)]
+struct NoteLinkResponse {
+    #[serde(flatten)]
+    link: NoteLink,
+    url: String,
+    password_protected: bool,
+}
+
+impl NoteLinkResponse {
+    fn new(link: NoteLink, public_url: &str) -> NoteLinkResponse {
+        NoteLinkResponse {
+            url: format!("{}/p/{}", public_url, link.slug),
+            password_protected: link.password_hash.is_some(),
+            link,
+        }
+    }
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/links",
+    responses(
+        (status = 200, description = "Successfully retrieved the note's public links, including revoked and expired ones."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Only the owner can manage a note's public links."),
+        (status = 404, description = "Note not found."),
+        (status = 500, description = "Internal server error: Unable to retrieve public links."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn fetch_note_links(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    authorize_note(&db, claims.id, note_id, NoteAccess::Owner).await?;
+
+    let public_url: String = state.config.public_url();
+
+    let links: Vec<NoteLinkResponse> = db
+        .send(FetchNoteLinks { note_id })
+        .await??
+        .into_iter()
+        .map(|link| NoteLinkResponse::new(link, &public_url))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(links))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateNoteLinkRequest {
+    /// Visitors must send this password to read the note.
+    #[schema(example = "open sesame")]
+    pub password: Option<String>,
+    /// The link stops working after this time.
+    #[schema(example = "2030-01-01T00:00:00Z")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/links",
+    request_body = CreateNoteLinkRequest,
+    responses(
+        (status = 201, description = "Public link created. Anyone with its URL can read the note without signing in."),
+        (status = 400, description = "Empty password or an expiry in the past."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Only the owner can create public links."),
+        (status = 404, description = "Note not found."),
+        (status = 500, description = "Internal server error: Unable to create the public link."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("")]
+pub async fn create_note_link(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    body: Json<CreateNoteLinkRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    if body
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(ApiError::BadRequest(String::from(
+            "expires_at must be in the future",
+        )));
+    }
+
+    let password_hash: Option<String> = match body.password.as_deref() {
+        Some("") => {
+            return Err(ApiError::BadRequest(String::from(
+                "password cannot be empty",
+            )));
+        }
+        Some(password) => Some(
+            hash_password(&state.config.passwords, password)
+                .map_err(|_| ApiError::Internal(String::from("password hashing failed")))?,
+        ),
+        None => None,
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    authorize_note(&db, claims.id, note_id, NoteAccess::Owner).await?;
+
+    let link: NoteLink = db
+        .send(CreateNoteLink {
+            note_id,
+            slug: generate_opaque_token(),
+            password_hash,
+            expires_at: body.expires_at,
+            created_by: claims.id,
+        })
+        .await??;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.link_created",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({
+            "link_id": link.id,
+            "password_protected": link.password_hash.is_some(),
+            "expires_at": link.expires_at,
+        }),
+    )
+    .await;
+
+    Ok(HttpResponse::Created().json(NoteLinkResponse::new(link, &state.config.public_url())))
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/links/{link_id}",
+    responses(
+        (status = 200, description = "Public link revoked. Its URL stops working immediately."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Only the owner can revoke public links."),
+        (status = 404, description = "Note not found, or no active link with that id."),
+        (status = 500, description = "Internal server error: Unable to revoke the public link."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/{link_id}")]
+pub async fn revoke_note_link(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (note_id, link_id) = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    authorize_note(&db, claims.id, note_id, NoteAccess::Owner).await?;
+
+    if db.send(RevokeNoteLink { note_id, link_id }).await?? == 0 {
+        return Err(ApiError::NotFound(format!(
+            "note {} has no active link {}",
+            note_id, link_id
+        )));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.link_revoked",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({ "link_id": link_id }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("link {} of note {} revoked", link_id, note_id)
+    })))
+}
@@ -0,0 +1,48 @@
+use crate::models::{Note, NoteLink};
+use crate::utils::db::DbResult;
+use actix::Message;
+use chrono::{DateTime, Utc};
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<NoteLink>>")]
+pub struct FetchNoteLinks {
+    pub note_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<NoteLink>")]
+pub struct CreateNoteLink {
+    pub note_id: i32,
+    pub slug: String,
+    pub password_hash: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct RevokeNoteLink {
+    pub note_id: i32,
+    pub link_id: i32,
+}
+
+/// A link that is neither revoked nor expired, with its note unless the note
+/// is in the trash.
+#[derive(Message)]
+#[rtype(result = "DbResult<(NoteLink, Note)>")]
+pub struct FetchPublicNote {
+    pub slug: String,
+}
+
+/// Counts a read of the link and clears its wrong password count.
+#[derive(Message)]
+#[rtype(result = "DbResult<i64>")]
+pub struct RecordLinkView {
+    pub link_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<NoteLink>")]
+pub struct RecordLinkPasswordFailure {
+    pub link_id: i32,
+}
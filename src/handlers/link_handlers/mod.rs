@@ -0,0 +1,5 @@
+pub mod actors;
+pub mod insertables;
+pub mod link_handlers;
+pub mod messages;
+pub mod public_handlers;
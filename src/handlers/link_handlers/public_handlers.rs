@@ -0,0 +1,277 @@
+use super::messages::*;
+use crate::{
+    models::{Note, NoteLink},
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        html::escape_html,
+        passwords::verify_password,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    get,
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const PASSWORD_HEADER: &str = "X-Share-Password";
+
+/// Consecutive wrong passwords after which a link makes everyone wait before
+/// the next guess. The wait doubles with every further failure up to
+/// `MAX_PASSWORD_BACKOFF_SECONDS`.
+const PASSWORD_BACKOFF_THRESHOLD: i32 = 5;
+const MAX_PASSWORD_BACKOFF_SECONDS: i64 = 900;
+
+const HTML_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; img-src https:; style-src 'unsafe-inline'; form-action 'self'";
+
+#[derive(Deserialize, IntoParams)]
+pub struct PublicNoteQuery {
+    /// json or html. Defaults to html when the Accept header asks for it and
+    /// to json otherwise.
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PublicNotePasswordForm {
+    #[schema(example = "open sesame", required = true)]
+    pub password: String,
+}
+
+#[derive(Serialize)]
+struct PublicNoteResponse {
+    title: String,
+    content: String,
+    image_url: Option<String>,
+    created_on: Option<DateTime<Utc>>,
+    updated_on: Option<DateTime<Utc>>,
+    view_count: i64,
+}
+
+fn wants_html(req: &HttpRequest, query: &PublicNoteQuery) -> Result<bool, ApiError> {
+    match query.format.as_deref() {
+        Some("json") => Ok(false),
+        Some("html") => Ok(true),
+        Some(_) => Err(ApiError::BadRequest(String::from(
+            "format must be json or html",
+        ))),
+        None => Ok(req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/html"))),
+    }
+}
+
+fn html_page(status: StatusCode, title: &str, body: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("text/html; charset=utf-8")
+        .insert_header((
+            header::CONTENT_SECURITY_POLICY,
+            HTML_CONTENT_SECURITY_POLICY,
+        ))
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .insert_header(("X-Robots-Tag", "noindex"))
+        .body(format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n<style>body{{max-width:48rem;margin:2rem auto;padding:0 1rem;\
+             font-family:sans-serif;line-height:1.5}}img{{max-width:100%}}\
+             .content{{white-space:pre-wrap}}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+            escape_html(title),
+            body
+        ))
+}
+
+/// When the next password guess for the link is allowed, if its wrong
+/// passwords put it into backoff.
+fn password_retry_on(link: &NoteLink) -> Option<DateTime<Utc>> {
+    if link.failed_password_count < PASSWORD_BACKOFF_THRESHOLD {
+        return None;
+    }
+
+    let exponent: u32 = (link.failed_password_count - PASSWORD_BACKOFF_THRESHOLD).min(16) as u32;
+    let delay: Duration = Duration::seconds((1_i64 << exponent).min(MAX_PASSWORD_BACKOFF_SECONDS));
+    let retry_on: DateTime<Utc> = link.last_failed_password_at? + delay;
+
+    (retry_on > Utc::now()).then_some(retry_on)
+}
+
+fn password_page(wrong_password: bool) -> HttpResponse {
+    let message: &str = if wrong_password {
+        "<p>That password is not correct.</p>\n"
+    } else {
+        ""
+    };
+
+    html_page(
+        StatusCode::UNAUTHORIZED,
+        "Password required",
+        &format!(
+            "<h1>Password required</h1>\n{}<form method=\"post\">\n\
+             <input type=\"password\" name=\"password\" autofocus required>\n\
+             <button type=\"submit\">Open note</button>\n</form>",
+            message
+        ),
+    )
+}
+
+fn note_page(note: &Note) -> HttpResponse {
+    let image: String = note
+        .image_url
+        .as_deref()
+        .map(|image_url| format!("<p><img src=\"{}\" alt=\"\"></p>\n", escape_html(image_url)))
+        .unwrap_or_default();
+
+    html_page(
+        StatusCode::OK,
+        &note.title,
+        &format!(
+            "<article>\n<h1>{}</h1>\n{}<div class=\"content\">{}</div>\n</article>",
+            escape_html(&note.title),
+            image,
+            escape_html(&note.content)
+        ),
+    )
+}
+
+/// Serves the note behind a public link once the link's password, if it has
+/// one, has been given. Only successful reads count as views.
+async fn serve_public_note(
+    state: Data<AppState>,
+    slug: String,
+    password: Option<&str>,
+    as_html: bool,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let (link, note): (NoteLink, Note) = db
+        .send(FetchPublicNote { slug })
+        .await?
+        .not_found(|| String::from("this link does not exist, has expired or was revoked"))?;
+
+    if let (Some(_), Some(retry_on)) = (password, password_retry_on(&link)) {
+        let retry_after: i64 = (retry_on - Utc::now()).num_seconds().max(1);
+
+        if as_html {
+            let mut response: HttpResponse = html_page(
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many attempts",
+                "<h1>Too many attempts</h1>\n<p>Too many wrong passwords were tried for \
+                 this link. Please try again later.</p>",
+            );
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from(retry_after));
+
+            return Ok(response);
+        }
+
+        return Err(ApiError::RateLimited(
+            String::from("too many wrong passwords for this link, try again later"),
+            retry_after,
+        ));
+    }
+
+    if let Some(password_hash) = link.password_hash.as_deref() {
+        match password {
+            Some(password) if verify_password(password, password_hash) => {}
+            Some(_) => {
+                db.send(RecordLinkPasswordFailure { link_id: link.id })
+                    .await??;
+
+                if as_html {
+                    return Ok(password_page(true));
+                }
+
+                return Err(ApiError::Unauthorized(String::from("wrong password")));
+            }
+            None if as_html => return Ok(password_page(false)),
+            None => {
+                return Err(ApiError::Unauthorized(format!(
+                    "this link requires a password, send it in the {} header",
+                    PASSWORD_HEADER
+                )));
+            }
+        }
+    }
+
+    let view_count: i64 = db.send(RecordLinkView { link_id: link.id }).await??;
+
+    if as_html {
+        return Ok(note_page(&note));
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .insert_header(("X-Robots-Tag", "noindex"))
+        .json(PublicNoteResponse {
+            title: note.title,
+            content: note.content,
+            image_url: note.image_url,
+            created_on: note.created_on,
+            updated_on: note.updated_on,
+            view_count,
+        }))
+}
+
+#[utoipa::path(
+    path = "/p/{slug}",
+    params(PublicNoteQuery),
+    responses(
+        (status = 200, description = "The shared note as JSON, or as a web page when format is html. Every successful read adds one to the link's view count."),
+        (status = 400, description = "Invalid format."),
+        (status = 401, description = "The link is password protected and the X-Share-Password header is missing or wrong. Web page requests get a password form instead."),
+        (status = 404, description = "The link does not exist, has expired or was revoked, or the note is in the trash."),
+        (status = 429, description = "Too many wrong passwords were tried for the link; see the Retry-After header."),
+        (status = 500, description = "Internal server error: Unable to retrieve the note."),
+    )
+)]
+#[get("/{slug}")]
+pub async fn view_public_note(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<PublicNoteQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let as_html: bool = wants_html(&req, &query)?;
+
+    let password: Option<&str> = req
+        .headers()
+        .get(PASSWORD_HEADER)
+        .and_then(|password| password.to_str().ok());
+
+    serve_public_note(state, path.into_inner(), password, as_html).await
+}
+
+#[utoipa::path(
+    path = "/p/{slug}",
+    params(PublicNoteQuery),
+    request_body(content = PublicNotePasswordForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "The password protected note, submitted from the password form."),
+        (status = 400, description = "Invalid format or missing password."),
+        (status = 401, description = "Wrong password."),
+        (status = 404, description = "The link does not exist, has expired or was revoked, or the note is in the trash."),
+        (status = 429, description = "Too many wrong passwords were tried for the link; see the Retry-After header."),
+        (status = 500, description = "Internal server error: Unable to retrieve the note."),
+    )
+)]
+#[post("/{slug}")]
+pub async fn unlock_public_note(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<PublicNoteQuery>,
+    form: Form<PublicNotePasswordForm>,
+) -> Result<HttpResponse, ApiError> {
+    let as_html: bool = wants_html(&req, &query)?;
+
+    serve_public_note(state, path.into_inner(), Some(&form.password), as_html).await
+}
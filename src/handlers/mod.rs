@@ -1,4 +1,13 @@
+pub mod audit_handlers;
 pub mod auth_handlers;
+pub mod link_handlers;
 pub mod note_handlers;
+pub mod notebook_handlers;
+pub mod revision_handlers;
+pub mod role_handlers;
+pub mod search_handlers;
+pub mod share_handlers;
+pub mod tag_handlers;
 pub mod test_handlers;
 pub mod transaction_handlers;
+pub mod trash_handlers;
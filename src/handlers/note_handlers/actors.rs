@@ -1,62 +1,80 @@
 use super::insertables::NewNote;
 use super::messages::*;
+use crate::handlers::{
+    revision_handlers::actors::{prune_revisions, record_revision},
+    search_handlers::actors::{matches_search, search_rank},
+    tag_handlers::actors::{replace_note_tags, tagged_with},
+};
 use crate::models::Note;
 use crate::schema::notes::dsl::*;
-use crate::utils::db::DbActor;
+use crate::utils::db::{DbActor, DbResult};
 use actix::Handler;
+use chrono::Utc;
 use diesel::associations::HasTable;
 use diesel::prelude::*;
 
 impl Handler<FetchNotes> for DbActor {
-    type Result = QueryResult<(i64, Vec<Note>, i64, i64)>;
+    type Result = DbResult<(i64, Vec<Note>, i64, i64)>;
 
     fn handle(&mut self, msg: FetchNotes, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Fetch Notes: Unable to establish connection");
+        let mut connection = self.0.get()?;
 
-        let mut query = notes::table().into_boxed();
+        let mut query = notes::table().filter(deleted_at.is_null()).into_boxed();
 
-        if let Some(ref search_term) = msg.search {
-            let search_pattern: String = format!("%{}%", search_term);
-
-            query = query.filter(
-                title
-                    .ilike(search_pattern.clone())
-                    .or(content.ilike(search_pattern)),
-            );
+        if let Some(ref search_query) = msg.search {
+            query = query.filter(matches_search(search_query));
         }
 
         if let Some(active_status) = msg.active_status {
             query = query.filter(active.eq(active_status.as_bool()));
         }
 
-        let mut count_query = notes::table().into_boxed();
+        if let Some(ref tag_names) = msg.tags {
+            query = query.filter(tagged_with(tag_names, msg.match_all));
+        }
+
+        let mut count_query = notes::table().filter(deleted_at.is_null()).into_boxed();
 
-        if let Some(search_term) = msg.search {
-            let search_pattern: String = format!("%{}%", search_term);
-            count_query = count_query.filter(
-                title
-                    .ilike(search_pattern.clone())
-                    .or(content.ilike(search_pattern)),
-            );
+        if let Some(ref search_query) = msg.search {
+            count_query = count_query.filter(matches_search(search_query));
         }
 
         if let Some(active_status) = msg.active_status {
             count_query = count_query.filter(active.eq(active_status.as_bool()));
         }
 
+        if let Some(ref tag_names) = msg.tags {
+            count_query = count_query.filter(tagged_with(tag_names, msg.match_all));
+        }
+
         let total_notes: i64 = count_query.count().get_result(&mut connection)?;
 
-        let sort_field: String = msg
-            .sort_field
-            .clone()
-            .unwrap_or_else(|| "title".to_string());
+        let sort_field: String = msg.sort_field.clone().unwrap_or_else(|| {
+            match msg.search {
+                Some(_) => "relevance",
+                None => "title",
+            }
+            .to_string()
+        });
 
-        let sort_order: String = msg.sort_order.clone().unwrap_or_else(|| "asc".to_string());
+        let sort_order: String = msg.sort_order.clone().unwrap_or_else(|| {
+            match sort_field.as_str() {
+                "relevance" => "desc",
+                _ => "asc",
+            }
+            .to_string()
+        });
 
         match sort_field.as_str() {
+            "relevance" => {
+                if let Some(ref search_query) = msg.search {
+                    if sort_order == "asc" {
+                        query = query.order((search_rank(search_query).asc(), id.asc()));
+                    } else if sort_order == "desc" {
+                        query = query.order((search_rank(search_query).desc(), id.asc()));
+                    }
+                }
+            }
             "title" => {
                 if sort_order == "asc" {
                     query = query.order(title.asc());
@@ -89,28 +107,33 @@ impl Handler<FetchNotes> for DbActor {
 }
 
 impl Handler<FetchUserNotes> for DbActor {
-    type Result = QueryResult<Vec<Note>>;
+    type Result = DbResult<Vec<Note>>;
 
     fn handle(&mut self, msg: FetchUserNotes, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Fetch User Notes: Unable to establish connection");
+        let mut connection = self.0.get()?;
 
-        notes
+        let mut query = notes
             .filter(created_by.eq(msg.user_id))
-            .get_results::<Note>(&mut connection)
+            .filter(deleted_at.is_null())
+            .into_boxed();
+
+        if let Some(notebook) = msg.notebook_id {
+            query = query.filter(notebook_id.eq(notebook));
+        }
+
+        if let Some(tag_names) = msg.tags {
+            query = query.filter(tagged_with(&tag_names, msg.match_all));
+        }
+
+        Ok(query.get_results::<Note>(&mut connection)?)
     }
 }
 
 impl Handler<CreateNote> for DbActor {
-    type Result = QueryResult<Note>;
+    type Result = DbResult<Note>;
 
     fn handle(&mut self, msg: CreateNote, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Create Note: Unable to establish connection");
+        let mut connection = self.0.get()?;
 
         let new_note: NewNote = NewNote {
             title: msg.title,
@@ -119,45 +142,63 @@ impl Handler<CreateNote> for DbActor {
             created_by: msg.created_by,
             created_on: msg.created_on,
             updated_on: msg.updated_on,
+            notebook_id: msg.notebook_id,
         };
 
-        diesel::insert_into(notes)
-            .values(new_note)
-            .get_result::<Note>(&mut connection)
+        connection.transaction(|connection| {
+            let note: Note = diesel::insert_into(notes)
+                .values(new_note)
+                .get_result::<Note>(connection)?;
+
+            replace_note_tags(connection, msg.created_by, note.id, &msg.tags)?;
+            record_revision(connection, &note, msg.created_by)?;
+
+            Ok(note)
+        })
     }
 }
 
 impl Handler<UpdateNote> for DbActor {
-    type Result = QueryResult<Note>;
+    type Result = DbResult<Note>;
 
     fn handle(&mut self, msg: UpdateNote, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Update Note: Unable to establish connection");
-
-        diesel::update(notes.find(msg.id))
-            .set((
-                title.eq(msg.title),
-                content.eq(msg.content),
-                created_by.eq(msg.created_by),
-                image_url.eq(msg.image_url),
-                active.eq(msg.active),
-                updated_on.eq(msg.updated_on),
-            ))
-            .get_result::<Note>(&mut connection)
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let note: Note = diesel::update(notes.find(msg.id))
+                .set((
+                    title.eq(msg.title),
+                    content.eq(msg.content),
+                    created_by.eq(msg.created_by),
+                    image_url.eq(msg.image_url),
+                    active.eq(msg.active),
+                    updated_on.eq(msg.updated_on),
+                    notebook_id.eq(msg.notebook_id),
+                ))
+                .get_result::<Note>(connection)?;
+
+            if let Some(tag_names) = msg.tags {
+                replace_note_tags(connection, msg.created_by, note.id, &tag_names)?;
+            }
+
+            record_revision(connection, &note, msg.edited_by)?;
+            prune_revisions(connection, note.id, &msg.retention)?;
+
+            Ok(note)
+        })
     }
 }
 
-impl Handler<DeleteNote> for DbActor {
-    type Result = Result<usize, diesel::result::Error>;
+impl Handler<TrashNote> for DbActor {
+    type Result = DbResult<usize>;
 
-    fn handle(&mut self, msg: DeleteNote, _ctx: &mut Self::Context) -> Self::Result {
-        let mut connection = self
-            .0
-            .get()
-            .expect("Delete Note: Unable to establish connection");
+    fn handle(&mut self, msg: TrashNote, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
 
-        diesel::delete(notes.find(msg.note_id)).execute(&mut connection)
+        Ok(
+            diesel::update(notes.find(msg.note_id).filter(deleted_at.is_null()))
+                .set(deleted_at.eq(Utc::now()))
+                .execute(&mut connection)?,
+        )
     }
 }
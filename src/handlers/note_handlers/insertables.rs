@@ -12,4 +12,5 @@ pub struct NewNote {
     pub created_by: i32,
     pub created_on: NaiveDateTime,
     pub updated_on: NaiveDateTime,
+    pub notebook_id: Option<i32>,
 }
@@ -1,17 +1,20 @@
 use super::utils::ActiveStatus;
 use crate::models::Note;
+use crate::utils::{config::RevisionConfig, db::DbResult};
 use actix::Message;
 use chrono::NaiveDateTime;
-use diesel::QueryResult;
 
 #[derive(Message)]
-#[rtype(result = "QueryResult<Vec<Note>>")]
+#[rtype(result = "DbResult<Vec<Note>>")]
 pub struct FetchUserNotes {
     pub user_id: i32,
+    pub notebook_id: Option<i32>,
+    pub tags: Option<Vec<String>>,
+    pub match_all: bool,
 }
 
 #[derive(Message)]
-#[rtype(result = "QueryResult<(i64, Vec<Note>, i64, i64)>")]
+#[rtype(result = "DbResult<(i64, Vec<Note>, i64, i64)>")]
 pub struct FetchNotes {
     pub search: Option<String>,
     pub sort_field: Option<String>,
@@ -19,10 +22,12 @@ pub struct FetchNotes {
     pub limit: Option<i64>,
     pub page: Option<i64>,
     pub active_status: Option<ActiveStatus>,
+    pub tags: Option<Vec<String>>,
+    pub match_all: bool,
 }
 
 #[derive(Message)]
-#[rtype(result = "QueryResult<Note>")]
+#[rtype(result = "DbResult<Note>")]
 pub struct CreateNote {
     pub title: String,
     pub content: String,
@@ -30,22 +35,28 @@ pub struct CreateNote {
     pub created_by: i32,
     pub created_on: NaiveDateTime,
     pub updated_on: NaiveDateTime,
+    pub notebook_id: Option<i32>,
+    pub tags: Vec<String>,
 }
 
 #[derive(Message)]
-#[rtype(result = "QueryResult<Note>")]
+#[rtype(result = "DbResult<Note>")]
 pub struct UpdateNote {
     pub id: i32,
     pub title: String,
     pub image_url: Option<String>,
     pub content: String,
     pub created_by: i32,
+    pub edited_by: i32,
     pub active: bool,
     pub updated_on: NaiveDateTime,
+    pub notebook_id: Option<i32>,
+    pub tags: Option<Vec<String>>,
+    pub retention: RevisionConfig,
 }
 
 #[derive(Message)]
-#[rtype(result = "Result<usize, diesel::result::Error>")]
-pub struct DeleteNote {
+#[rtype(result = "DbResult<usize>")]
+pub struct TrashNote {
     pub note_id: i32,
 }
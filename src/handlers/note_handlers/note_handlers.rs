@@ -1,10 +1,18 @@
 use super::messages::*;
-use crate::handlers::note_handlers::utils::*;
+use crate::handlers::{
+    audit_handlers::audit_handlers::record_audit_event,
+    auth_handlers::messages::LoginAndGetUser,
+    note_handlers::utils::*,
+    search_handlers::utils::parse_search_query,
+    share_handlers::utils::{authorize_note, NoteAccess},
+    tag_handlers::tag_handlers::parse_tag_names,
+};
 use crate::{
+    middlewares::auth_middlewares::require_permission,
     models::Note,
     utils::{
-        self,
         db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
         jwt::Claims,
     },
 };
@@ -13,7 +21,7 @@ use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
 use actix_web::{
     delete, get, patch, post,
     web::{Data, Path, Query},
-    HttpMessage, HttpRequest, HttpResponse, Responder,
+    HttpMessage, HttpRequest, HttpResponse,
 };
 use chrono::{NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -27,6 +35,8 @@ pub struct NoteQuery {
     limit: Option<i64>,
     page: Option<i64>,
     active_status: Option<String>,
+    tags: Option<String>,
+    tag_match: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -34,21 +44,24 @@ struct NotesResponse {
     total_notes: i64,
     number_of_page: i64,
     page: i64,
-    notes: Vec<Note>,
+    notes: Vec<NoteResponse>,
 }
 
 #[utoipa::path(
     path = "/admin/dashboard/notes",
     params(
-        ("search" = Option<String>, Query, description = "Search term for filtering notes."),
-        ("sort_field" = Option<String>, Query, description = "Field to sort by (example: title or content)."),
-        ("sort_order" = Option<String>, Query, description = "Order to sort by (example: asc or desc)."),
+        ("search" = Option<String>, Query, description = "Full-text search over titles and contents, with the same syntax as /api/search (example: \"release notes\" deploy* -draft)."),
+        ("sort_field" = Option<String>, Query, description = "Field to sort by (example: relevance, title or content). Defaults to relevance when searching and to title otherwise."),
+        ("sort_order" = Option<String>, Query, description = "Order to sort by (example: asc or desc). Defaults to desc for relevance and to asc otherwise."),
         ("page" = Option<i64>, Query, description = "Page number for pagination (default: 1)."),
         ("limit" = Option<i64>, Query, description = "Limit of notes per page (default: 10)."),
         ("active_status" = Option<String>, Query, description = "Filter by active status (example: active or inactive)."),
+        ("tags" = Option<String>, Query, description = "Comma-separated tag names to filter by (example: rust,web)."),
+        ("tag_match" = Option<String>, Query, description = "Whether notes must have any or all of the tags (default: any)."),
     ),
     responses(
         (status = 200, description = "Successfully retrieved all notes."),
+        (status = 400, description = "Invalid tag filter, or an empty or too long search."),
         (status = 404, description = "No notes found matching the query."),
         (status = 500, description = "Internal server error: Unable to retrieve notes."),
     ),
@@ -56,8 +69,11 @@ struct NotesResponse {
         ("bearer_auth" = []),
     )
 )]
-#[get("/notes")]
-pub async fn fetch_notes(state: Data<AppState>, query: Query<NoteQuery>) -> impl Responder {
+#[get("/notes", wrap = "require_permission(\"notes:read_all\")")]
+pub async fn fetch_notes(
+    state: Data<AppState>,
+    query: Query<NoteQuery>,
+) -> Result<HttpResponse, ApiError> {
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
     let active_status: Option<ActiveStatus> = match query.active_status.as_deref() {
@@ -66,37 +82,51 @@ pub async fn fetch_notes(state: Data<AppState>, query: Query<NoteQuery>) -> impl
         _ => None,
     };
 
-    match db
+    let (tags, match_all) = tag_filter(query.tags.as_deref(), query.tag_match.as_deref())?;
+
+    let search: Option<String> = match query.search.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => Some(parse_search_query(raw)?),
+        _ => None,
+    };
+
+    let (total_notes, notes, number_of_page, page) = db
         .send(FetchNotes {
-            search: query.search.clone(),
+            search,
             sort_field: query.sort_field.clone(),
             sort_order: query.sort_order.clone(),
             limit: query.limit.clone(),
             page: query.page.clone(),
             active_status,
+            tags,
+            match_all,
         })
-        .await
-    {
-        Ok(Ok((total_notes, notes, number_of_page, page))) => {
-            HttpResponse::Ok().json(NotesResponse {
-                total_notes,
-                number_of_page,
-                page,
-                notes,
-            })
-        }
-        Ok(Err(_)) => {
-            HttpResponse::NotFound().json(serde_json::json!({ "message": "no notes found" }))
-        }
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "unable to retrieve notes" })),
-    }
+        .await??;
+
+    Ok(HttpResponse::Ok().json(NotesResponse {
+        total_notes,
+        number_of_page,
+        page,
+        notes: with_tags(&db, notes).await?,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct UserNoteQuery {
+    notebook_id: Option<i32>,
+    tags: Option<String>,
+    tag_match: Option<String>,
 }
 
 #[utoipa::path(
     path = "/api/my/notes",
+    params(
+        ("notebook_id" = Option<i32>, Query, description = "Only return notes filed in this notebook, not counting nested notebooks."),
+        ("tags" = Option<String>, Query, description = "Comma-separated tag names to filter by (example: rust,web)."),
+        ("tag_match" = Option<String>, Query, description = "Whether notes must have any or all of the tags (default: any)."),
+    ),
     responses(
         (status = 200, description = "Successfully retrieved all notes for the authenticated user."),
+        (status = 400, description = "Invalid tag filter."),
         (status = 401, description = "Unauthorized: Bearer authentication required."),
         (status = 500, description = "Internal server error: Unable to retrieve user's notes."),
     ),
@@ -105,24 +135,32 @@ pub async fn fetch_notes(state: Data<AppState>, query: Query<NoteQuery>) -> impl
     )
 )]
 #[get("/my/notes")]
-pub async fn fetch_user_notes(state: Data<AppState>, req: HttpRequest) -> impl Responder {
+pub async fn fetch_user_notes(
+    state: Data<AppState>,
+    req: HttpRequest,
+    query: Query<UserNoteQuery>,
+) -> Result<HttpResponse, ApiError> {
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({ "message": "unauthorized access" }));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    match db.send(FetchUserNotes { user_id: claims.id }).await {
-        Ok(Ok(notes)) => HttpResponse::Ok().json(notes),
-        Ok(Err(_)) => HttpResponse::NotFound()
-            .json(serde_json::json!({ "message": format!("no notes for user {}", claims.id) })),
-        _ => HttpResponse::InternalServerError()
-            .json(serde_json::json!({ "message": "unable to retrieve user notes" })),
-    }
+    let (tags, match_all) = tag_filter(query.tags.as_deref(), query.tag_match.as_deref())?;
+
+    let notes: Vec<Note> = db
+        .send(FetchUserNotes {
+            user_id: claims.id,
+            notebook_id: query.notebook_id,
+            tags,
+            match_all,
+        })
+        .await??;
+
+    Ok(HttpResponse::Ok().json(with_tags(&db, notes).await?))
 }
 
 #[derive(Debug, MultipartForm, ToSchema)]
@@ -131,6 +169,10 @@ pub struct CreateNoteRequest {
     title: Text<String>,
     #[schema(example = "my note content", value_type = String)]
     content: Text<String>,
+    #[schema(example = "rust,web", value_type = Option<String>)]
+    tags: Option<Text<String>>,
+    #[schema(example = 1, value_type = Option<i32>)]
+    notebook_id: Option<Text<i32>>,
     #[schema(example = "image.jpg/png", value_type = Option<String>, format = Binary)]
     #[multipart(limit = "10 MiB")]
     image: Option<TempFile>,
@@ -141,8 +183,12 @@ pub struct CreateNoteRequest {
     request_body(content = CreateNoteRequest, content_type = "multipart/form-data"),
     responses(
         (status = 200, description = "Successfully created a new note."),
+        (status = 400, description = "Invalid tags."),
         (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Forbidden: The email address has not been verified."),
+        (status = 404, description = "Notebook not found."),
         (status = 500, description = "Internal server error: Failed to create note."),
+        (status = 503, description = "An image was attached but image uploads are not configured."),
     ),
     security(
         ("bearer_auth" = [])
@@ -153,16 +199,41 @@ pub async fn create_user_notes(
     state: Data<AppState>,
     req: HttpRequest,
     body: MultipartForm<CreateNoteRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({"message": "unauthorized access"}));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    if state.config.auth.email_verification == "notes" {
+        let user = db
+            .send(LoginAndGetUser {
+                email: claims.email.clone(),
+                password: String::new(),
+            })
+            .await??;
+
+        if user.email_verified_at.is_none() {
+            return Err(ApiError::Forbidden(String::from(
+                "verify your email address to create notes",
+            )));
+        }
+    }
+
+    let tags: Vec<String> = match body.tags.as_ref() {
+        Some(raw) => parse_tag_names(raw)?,
+        None => Vec::new(),
+    };
+
+    let notebook_id: Option<i32> = match body.notebook_id.as_ref() {
+        Some(notebook_id) => Some(owned_notebook_id(&db, claims.id, notebook_id.0).await?),
+        None => None,
+    };
+
     let created_on: NaiveDateTime = Utc::now().naive_local();
     let updated_on: NaiveDateTime = Utc::now().naive_local();
 
@@ -172,12 +243,9 @@ pub async fn create_user_notes(
         let max_file_size: u64 = 10485760;
         let temp_file_path: &std::path::Path = image.file.path();
 
-        if let Err(err) = upload_image_validation(file_name, file_size, max_file_size) {
-            return err;
-        }
+        upload_image_validation(file_name, file_size, max_file_size)?;
 
-        let cloud_name: String = (*utils::constants::CLOUDINARY_CLOUD_NAME).clone();
-        let upload_preset: String = (*utils::constants::CLOUDINARY_UPLOAD_PRESET).clone();
+        let (cloud_name, upload_preset) = cloudinary_credentials(state.as_ref())?;
 
         let image_url: Option<String> =
             match upload_image_to_cloudinary(temp_file_path, cloud_name, upload_preset).await {
@@ -186,12 +254,11 @@ pub async fn create_user_notes(
                     Some(url)
                 }
                 Err(_) => {
-                    return HttpResponse::InternalServerError()
-                        .json(serde_json::json!({"message": "failed to upload image"}));
+                    return Err(ApiError::BadGateway(String::from("failed to upload image")));
                 }
             };
 
-        match db
+        let note: Note = db
             .send(CreateNote {
                 title: body.title.clone(),
                 content: body.content.clone(),
@@ -199,17 +266,24 @@ pub async fn create_user_notes(
                 image_url,
                 created_on,
                 updated_on,
+                notebook_id,
+                tags,
             })
-            .await
-        {
-            Ok(Ok(note)) => return HttpResponse::Ok().json(note),
-            _ => {
-                return HttpResponse::InternalServerError()
-                    .json(serde_json::json!({ "message": "failed to create note" }))
-            }
-        }
+            .await??;
+
+        record_audit_event(
+            &db,
+            &req,
+            Some(claims.id),
+            "note.created",
+            Some(("note", note.id.to_string())),
+            serde_json::json!({ "title": note.title }),
+        )
+        .await;
+
+        Ok(HttpResponse::Ok().json(with_tags(&db, vec![note]).await?.pop()))
     } else {
-        match db
+        let note: Note = db
             .send(CreateNote {
                 title: body.title.clone(),
                 content: body.content.clone(),
@@ -217,15 +291,22 @@ pub async fn create_user_notes(
                 image_url: None,
                 created_on,
                 updated_on,
+                notebook_id,
+                tags,
             })
-            .await
-        {
-            Ok(Ok(note)) => return HttpResponse::Ok().json(note),
-            _ => {
-                return HttpResponse::InternalServerError()
-                    .json(serde_json::json!({ "message": "failed to create note" }))
-            }
-        }
+            .await??;
+
+        record_audit_event(
+            &db,
+            &req,
+            Some(claims.id),
+            "note.created",
+            Some(("note", note.id.to_string())),
+            serde_json::json!({ "title": note.title }),
+        )
+        .await;
+
+        Ok(HttpResponse::Ok().json(with_tags(&db, vec![note]).await?.pop()))
     }
 }
 
@@ -237,6 +318,11 @@ pub struct UpdateNoteRequest {
     pub content: Option<Text<String>>,
     #[schema(example = "false", value_type = Option<bool>)]
     pub active: Option<Text<bool>>,
+    #[schema(example = "rust,web", value_type = Option<String>)]
+    pub tags: Option<Text<String>>,
+    /// A notebook id, or an empty value to take the note out of its notebook.
+    #[schema(example = "1", value_type = Option<String>)]
+    pub notebook_id: Option<Text<String>>,
     #[schema(example = "image.jpg/png", value_type = Option<String>, format = Binary)]
     #[multipart(limit = "10 MiB")]
     pub image: Option<TempFile>,
@@ -246,10 +332,13 @@ pub struct UpdateNoteRequest {
     path = "/api/update/note/{note_id}",
     request_body(content = UpdateNoteRequest, content_type = "multipart/form-data"),
     responses(
-        (status = 200, description = "Note successfully updated."),
+        (status = 200, description = "Note successfully updated. Sending tags replaces all of the note's tags. A change to the title or content is recorded as a new revision."),
+        (status = 400, description = "Invalid tags or notebook_id."),
         (status = 401, description = "Unauthorized: Bearer authentication required."),
-        (status = 404, description = "Note not found."),
+        (status = 403, description = "The note is shared read-only, or an editor tried to change its tags or notebook."),
+        (status = 404, description = "Note or notebook not found."),
         (status = 500, description = "Internal server error: Failed to update note."),
+        (status = 503, description = "An image was attached but image uploads are not configured."),
     ),
     security(
         ("bearer_auth" = [])
@@ -261,98 +350,118 @@ pub async fn update_user_note(
     req: HttpRequest,
     path: Path<i32>,
     body: MultipartForm<UpdateNoteRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let note_id: i32 = path.into_inner();
 
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({ "message": "unauthorized access" }));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    let existing_note: Option<crate::models::Note> =
-        match db.send(FetchUserNotes { user_id: claims.id }).await {
-            Ok(Ok(notes)) => notes.into_iter().find(|note| note.id == note_id),
-            _ => None,
-        };
+    let (note, access) = authorize_note(&db, claims.id, note_id, NoteAccess::Edit).await?;
+
+    let updated_title: String = body
+        .0
+        .title
+        .as_ref()
+        .map(|text| text.to_string())
+        .unwrap_or(note.title);
+    let updated_content: String = body
+        .0
+        .content
+        .as_ref()
+        .map(|text| text.to_string())
+        .unwrap_or(note.content);
+    let active_status: bool = body.0.active.as_ref().map(|text| text.0).unwrap_or(true);
+
+    if access != NoteAccess::Owner && (body.0.tags.is_some() || body.0.notebook_id.is_some()) {
+        return Err(ApiError::Forbidden(String::from(
+            "only the owner of a note can change its tags or notebook",
+        )));
+    }
 
-    if let Some(note) = existing_note {
-        let updated_title: String = body
-            .0
-            .title
-            .as_ref()
-            .map(|text| text.to_string())
-            .unwrap_or(note.title);
-        let updated_content: String = body
-            .0
-            .content
-            .as_ref()
-            .map(|text| text.to_string())
-            .unwrap_or(note.content);
-        let active_status: bool = body.0.active.as_ref().map(|text| text.0).unwrap_or(true);
-
-        let mut updated_image_url: Option<String> = note.image_url.clone();
-
-        if let Some(image) = &body.0.image.as_ref() {
-            let file_name: Option<String> = image.file_name.clone();
-            let file_size: usize = image.size;
-            let max_file_size: u64 = 10485760;
-            let temp_file_path: &std::path::Path = image.file.path();
-
-            if let Err(err) = upload_image_validation(file_name, file_size, max_file_size) {
-                return err;
-            }
+    let updated_tags: Option<Vec<String>> = match body.0.tags.as_ref() {
+        Some(raw) => Some(parse_tag_names(raw)?),
+        None => None,
+    };
+    let updated_notebook_id: Option<i32> = match body.0.notebook_id.as_ref() {
+        Some(raw) if raw.trim().is_empty() => None,
+        Some(raw) => {
+            let notebook_id: i32 = raw
+                .trim()
+                .parse()
+                .map_err(|_| ApiError::BadRequest(String::from("notebook_id must be a number")))?;
+
+            Some(owned_notebook_id(&db, claims.id, notebook_id).await?)
+        }
+        None => note.notebook_id,
+    };
 
-            let cloud_name: String = (*utils::constants::CLOUDINARY_CLOUD_NAME).clone();
-            let upload_preset: String = (*utils::constants::CLOUDINARY_UPLOAD_PRESET).clone();
+    let mut updated_image_url: Option<String> = note.image_url.clone();
 
-            match upload_image_to_cloudinary(temp_file_path, cloud_name, upload_preset).await {
-                Ok(url) => {
-                    std::fs::remove_file(temp_file_path).unwrap_or_default();
-                    updated_image_url = Some(url);
-                }
-                Err(_) => {
-                    return HttpResponse::InternalServerError()
-                        .json(serde_json::json!({ "message": "failed to upload image" }));
-                }
-            };
-        }
+    if let Some(image) = &body.0.image.as_ref() {
+        let file_name: Option<String> = image.file_name.clone();
+        let file_size: usize = image.size;
+        let max_file_size: u64 = 10485760;
+        let temp_file_path: &std::path::Path = image.file.path();
 
-        let updated_on: NaiveDateTime = Utc::now().naive_local();
+        upload_image_validation(file_name, file_size, max_file_size)?;
 
-        match db
-            .send(UpdateNote {
-                id: note_id,
-                title: updated_title,
-                content: updated_content,
-                image_url: updated_image_url,
-                active: active_status,
-                created_by: claims.id,
-                updated_on,
-            })
-            .await
-        {
-            Ok(Ok(updated_note)) => HttpResponse::Ok().json(updated_note),
-            Ok(Err(_)) => HttpResponse::NotFound()
-                .json(serde_json::json!({ "message": format!("note {note_id} not found") })),
-            _ => HttpResponse::InternalServerError()
-                .json(serde_json::json!({ "message": "failed to update note" })),
-        }
-    } else {
-        HttpResponse::NotFound()
-            .json(serde_json::json!({ "message": format!("note {note_id} not found") }))
+        let (cloud_name, upload_preset) = cloudinary_credentials(state.as_ref())?;
+
+        match upload_image_to_cloudinary(temp_file_path, cloud_name, upload_preset).await {
+            Ok(url) => {
+                std::fs::remove_file(temp_file_path).unwrap_or_default();
+                updated_image_url = Some(url);
+            }
+            Err(_) => {
+                return Err(ApiError::BadGateway(String::from("failed to upload image")));
+            }
+        };
     }
+
+    let updated_on: NaiveDateTime = Utc::now().naive_local();
+
+    let updated_note: Note = db
+        .send(UpdateNote {
+            id: note_id,
+            title: updated_title,
+            content: updated_content,
+            image_url: updated_image_url,
+            active: active_status,
+            created_by: note.created_by,
+            edited_by: claims.id,
+            updated_on,
+            notebook_id: updated_notebook_id,
+            tags: updated_tags,
+            retention: state.config.revisions.clone(),
+        })
+        .await?
+        .not_found(|| format!("note {note_id} not found"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.updated",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({ "active": updated_note.active }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(with_tags(&db, vec![updated_note]).await?.pop()))
 }
 
 #[utoipa::path(
     path = "/api/delete/note/{note_id}",
     responses(
-        (status = 200, description = "Note successfully deleted."),
+        (status = 200, description = "Note moved to the trash. It can be restored until it is purged."),
         (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Only the owner can delete a shared note."),
         (status = 404, description = "Note not found."),
         (status = 500, description = "Internal server error: Failed to delete note."),
     ),
@@ -365,36 +474,34 @@ pub async fn delete_user_note(
     state: Data<AppState>,
     req: HttpRequest,
     path: Path<i32>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let note_id: i32 = path.into_inner();
 
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({ "message": "unauthorized access" }));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
     let db: Addr<DbActor> = state.as_ref().db.clone();
 
-    let existing_note: Option<crate::models::Note> =
-        match db.send(FetchUserNotes { user_id: claims.id }).await {
-            Ok(Ok(notes)) => notes.into_iter().find(|note| note.id == note_id),
-            _ => None,
-        };
+    authorize_note(&db, claims.id, note_id, NoteAccess::Owner).await?;
 
-    if let Some(_) = existing_note {
-        match db.send(DeleteNote { note_id }).await {
-            Ok(Ok(rows_affected)) if rows_affected > 0 => HttpResponse::Ok()
-                .json(serde_json::json!({ "message": format!("deleted note {}", note_id) })),
-            Ok(_) => HttpResponse::NotFound()
-                .json(serde_json::json!({ "message": format!("note {} not found", note_id) })),
-            _ => HttpResponse::InternalServerError()
-                .json(serde_json::json!({ "message": "failed to delete note" })),
-        }
-    } else {
-        HttpResponse::NotFound()
-            .json(serde_json::json!({ "message": format!("note {} not found", note_id) }))
+    if db.send(TrashNote { note_id }).await?? == 0 {
+        return Err(ApiError::NotFound(format!("note {} not found", note_id)));
     }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.trashed",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok()
+        .json(serde_json::json!({ "message": format!("moved note {} to the trash", note_id) })))
 }
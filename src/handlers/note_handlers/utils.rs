@@ -1,36 +1,44 @@
-use actix_web::HttpResponse;
+use crate::handlers::{
+    notebook_handlers::messages::FetchNotebook,
+    tag_handlers::{messages::FetchNoteTags, tag_handlers::parse_tag_names},
+};
+use crate::models::Note;
+use crate::utils::{
+    db::{AppState, DbActor},
+    errors::{ApiError, ApiResultExt},
+};
+use actix::Addr;
+use chrono::Utc;
 use reqwest::{
     multipart::{Form, Part},
     Client,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
 
 pub fn upload_image_validation(
     file_name: Option<String>,
     file_size: usize,
     max_file_size: u64,
-) -> Result<(), HttpResponse> {
+) -> Result<(), ApiError> {
     match file_name {
         Some(name) => {
             if !name.ends_with(".png") && !name.ends_with(".jpg") {
-                return Err(HttpResponse::BadRequest()
-                    .json(serde_json::json!({ "message": "invalid file type"})));
+                return Err(ApiError::BadRequest(String::from("invalid file type")));
             }
         }
         None => {
-            return Err(HttpResponse::BadRequest()
-                .json(serde_json::json!({ "message": "file name is missing"})));
+            return Err(ApiError::BadRequest(String::from("file name is missing")));
         }
     }
 
     match file_size {
         0 => {
-            return Err(HttpResponse::BadRequest()
-                .json(serde_json::json!({ "message": "invalid file size"})));
+            return Err(ApiError::BadRequest(String::from("invalid file size")));
         }
         length if length > max_file_size as usize => {
-            return Err(HttpResponse::BadRequest()
-                .json(serde_json::json!({ "message": "file size too long"})));
+            return Err(ApiError::BadRequest(String::from("file size too long")));
         }
         _ => {}
     }
@@ -38,6 +46,18 @@ pub fn upload_image_validation(
     Ok(())
 }
 
+/// Cloud name and upload preset, or a 503 when image uploads are disabled.
+pub fn cloudinary_credentials(state: &AppState) -> Result<(String, String), ApiError> {
+    state
+        .config
+        .cloudinary
+        .credentials()
+        .map(|(cloud_name, upload_preset)| (cloud_name.to_string(), upload_preset.to_string()))
+        .ok_or_else(|| {
+            ApiError::ServiceUnavailable(String::from("image uploads are not configured"))
+        })
+}
+
 #[derive(Deserialize)]
 struct CloudinaryResponse {
     secure_url: String,
@@ -80,6 +100,61 @@ pub async fn upload_image_to_cloudinary(
     Ok(response.secure_url)
 }
 
+/// Public id of an uploaded image, taken from its secure URL, e.g.
+/// `folder/name` from `.../image/upload/v1712345678/folder/name.jpg`.
+fn cloudinary_public_id(image_url: &str) -> Option<&str> {
+    let path: &str = image_url.split_once("/upload/")?.1;
+    let path: &str = match path.split_once('/') {
+        Some((version, rest))
+            if version.strip_prefix('v').is_some_and(|digits| {
+                !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+            }) =>
+        {
+            rest
+        }
+        _ => path,
+    };
+
+    Some(path.rsplit_once('.').map(|(id, _)| id).unwrap_or(path))
+}
+
+pub async fn delete_image_from_cloudinary(
+    image_url: &str,
+    cloud_name: &str,
+    api_key: &str,
+    api_secret: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let public_id: &str = cloudinary_public_id(image_url).ok_or("not a Cloudinary image URL")?;
+    let timestamp: String = Utc::now().timestamp().to_string();
+
+    let signature: String = format!(
+        "{:x}",
+        Sha1::digest(format!(
+            "public_id={}&timestamp={}{}",
+            public_id, timestamp, api_secret
+        ))
+    );
+
+    let url: String = format!(
+        "https://api.cloudinary.com/v1_1/{}/image/destroy",
+        cloud_name
+    );
+
+    Client::new()
+        .post(url)
+        .form(&[
+            ("public_id", public_id),
+            ("timestamp", timestamp.as_str()),
+            ("api_key", api_key),
+            ("signature", signature.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
 #[derive(Debug, Deserialize, Clone, Copy)]
 pub enum ActiveStatus {
     Active,
@@ -94,3 +169,74 @@ impl ActiveStatus {
         }
     }
 }
+
+/// Parses the `tags` and `tag_match` query parameters into the tag names to
+/// filter by and whether a note must carry all of them rather than any.
+pub fn tag_filter(
+    tags: Option<&str>,
+    tag_match: Option<&str>,
+) -> Result<(Option<Vec<String>>, bool), ApiError> {
+    let match_all: bool = match tag_match {
+        None | Some("any") => false,
+        Some("all") => true,
+        Some(_) => {
+            return Err(ApiError::BadRequest(String::from(
+                "tag_match must be any or all",
+            )));
+        }
+    };
+
+    let tags: Option<Vec<String>> = match tags {
+        Some(raw) => Some(parse_tag_names(raw)?).filter(|names| !names.is_empty()),
+        None => None,
+    };
+
+    Ok((tags, match_all))
+}
+
+#[derive(Serialize)]
+pub struct NoteResponse {
+    #[serde(flatten)]
+    pub note: Note,
+    pub tags: Vec<String>,
+}
+
+pub async fn with_tags(
+    db: &Addr<DbActor>,
+    notes: Vec<Note>,
+) -> Result<Vec<NoteResponse>, ApiError> {
+    let mut note_tags: HashMap<i32, Vec<String>> = HashMap::new();
+
+    for (note_id, name) in db
+        .send(FetchNoteTags {
+            note_ids: notes.iter().map(|note| note.id).collect(),
+        })
+        .await??
+    {
+        note_tags.entry(note_id).or_default().push(name);
+    }
+
+    Ok(notes
+        .into_iter()
+        .map(|note| NoteResponse {
+            tags: note_tags.remove(&note.id).unwrap_or_default(),
+            note,
+        })
+        .collect())
+}
+
+/// Checks that the notebook exists and belongs to the user before a note is filed in it.
+pub async fn owned_notebook_id(
+    db: &Addr<DbActor>,
+    user_id: i32,
+    notebook_id: i32,
+) -> Result<i32, ApiError> {
+    Ok(db
+        .send(FetchNotebook {
+            user_id,
+            notebook_id,
+        })
+        .await?
+        .not_found(|| format!("notebook {} not found", notebook_id))?
+        .id)
+}
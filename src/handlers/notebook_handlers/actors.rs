@@ -0,0 +1,192 @@
+use super::insertables::NewNotebook;
+use super::messages::*;
+use crate::models::Notebook;
+use crate::schema::{notebooks, notes};
+use crate::utils::{
+    db::{DbActor, DbResult},
+    errors::ApiError,
+};
+use actix::Handler;
+use chrono::Utc;
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+fn find_notebook(
+    connection: &mut PgConnection,
+    user_id: i32,
+    notebook_id: i32,
+) -> QueryResult<Notebook> {
+    notebooks::table
+        .filter(notebooks::id.eq(notebook_id))
+        .filter(notebooks::user_id.eq(user_id))
+        .first::<Notebook>(connection)
+}
+
+/// Ids of the notebook and every notebook nested below it.
+fn subtree_ids(user_notebooks: &[Notebook], root_id: i32) -> Vec<i32> {
+    let mut ids: Vec<i32> = vec![root_id];
+    let mut index: usize = 0;
+
+    while index < ids.len() {
+        let parent_id: i32 = ids[index];
+
+        ids.extend(
+            user_notebooks
+                .iter()
+                .filter(|notebook| notebook.parent_id == Some(parent_id))
+                .map(|notebook| notebook.id),
+        );
+
+        index += 1;
+    }
+
+    ids
+}
+
+impl Handler<FetchNotebooks> for DbActor {
+    type Result = DbResult<(Vec<Notebook>, HashMap<Option<i32>, i64>)>;
+
+    fn handle(&mut self, msg: FetchNotebooks, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let user_notebooks: Vec<Notebook> = notebooks::table
+            .filter(notebooks::user_id.eq(msg.user_id))
+            .order(notebooks::name.asc())
+            .get_results::<Notebook>(&mut connection)?;
+
+        let mut note_counts: HashMap<Option<i32>, i64> = HashMap::new();
+
+        for notebook_id in notes::table
+            .filter(notes::created_by.eq(msg.user_id))
+            .filter(notes::deleted_at.is_null())
+            .select(notes::notebook_id)
+            .get_results::<Option<i32>>(&mut connection)?
+        {
+            *note_counts.entry(notebook_id).or_default() += 1;
+        }
+
+        Ok((user_notebooks, note_counts))
+    }
+}
+
+impl Handler<FetchNotebook> for DbActor {
+    type Result = DbResult<Notebook>;
+
+    fn handle(&mut self, msg: FetchNotebook, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(find_notebook(
+            &mut connection,
+            msg.user_id,
+            msg.notebook_id,
+        )?)
+    }
+}
+
+impl Handler<CreateNotebook> for DbActor {
+    type Result = DbResult<Notebook>;
+
+    fn handle(&mut self, msg: CreateNotebook, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            if let Some(parent_id) = msg.parent_id {
+                find_notebook(connection, msg.user_id, parent_id)?;
+            }
+
+            Ok(diesel::insert_into(notebooks::table)
+                .values(NewNotebook {
+                    user_id: msg.user_id,
+                    parent_id: msg.parent_id,
+                    name: msg.name,
+                })
+                .get_result::<Notebook>(connection)?)
+        })
+    }
+}
+
+impl Handler<RenameNotebook> for DbActor {
+    type Result = DbResult<Notebook>;
+
+    fn handle(&mut self, msg: RenameNotebook, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(
+            notebooks::table
+                .filter(notebooks::id.eq(msg.notebook_id))
+                .filter(notebooks::user_id.eq(msg.user_id)),
+        )
+        .set(notebooks::name.eq(msg.name))
+        .get_result::<Notebook>(&mut connection)?)
+    }
+}
+
+impl Handler<MoveNotebook> for DbActor {
+    type Result = DbResult<Notebook>;
+
+    fn handle(&mut self, msg: MoveNotebook, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let notebook: Notebook = find_notebook(connection, msg.user_id, msg.notebook_id)?;
+
+            if let Some(parent_id) = msg.parent_id {
+                find_notebook(connection, msg.user_id, parent_id)?;
+
+                let user_notebooks: Vec<Notebook> = notebooks::table
+                    .filter(notebooks::user_id.eq(msg.user_id))
+                    .get_results::<Notebook>(connection)?;
+
+                if subtree_ids(&user_notebooks, notebook.id).contains(&parent_id) {
+                    return Err(ApiError::BadRequest(String::from(
+                        "a notebook cannot be moved into itself or one of its descendants",
+                    )));
+                }
+            }
+
+            Ok(diesel::update(notebooks::table.find(notebook.id))
+                .set(notebooks::parent_id.eq(msg.parent_id))
+                .get_result::<Notebook>(connection)?)
+        })
+    }
+}
+
+impl Handler<DeleteNotebook> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: DeleteNotebook, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let notebook: Notebook = find_notebook(connection, msg.user_id, msg.notebook_id)?;
+
+            let affected_notes: usize = if msg.delete_notes {
+                let user_notebooks: Vec<Notebook> = notebooks::table
+                    .filter(notebooks::user_id.eq(msg.user_id))
+                    .get_results::<Notebook>(connection)?;
+
+                diesel::update(
+                    notes::table
+                        .filter(
+                            notes::notebook_id.eq_any(subtree_ids(&user_notebooks, notebook.id)),
+                        )
+                        .filter(notes::deleted_at.is_null()),
+                )
+                .set(notes::deleted_at.eq(Utc::now()))
+                .execute(connection)?
+            } else {
+                diesel::update(notebooks::table.filter(notebooks::parent_id.eq(notebook.id)))
+                    .set(notebooks::parent_id.eq(notebook.parent_id))
+                    .execute(connection)?;
+
+                diesel::update(notes::table.filter(notes::notebook_id.eq(notebook.id)))
+                    .set(notes::notebook_id.eq(notebook.parent_id))
+                    .execute(connection)?
+            };
+
+            diesel::delete(notebooks::table.find(notebook.id)).execute(connection)?;
+
+            Ok(affected_notes)
+        })
+    }
+}
@@ -0,0 +1,11 @@
+use crate::schema::notebooks;
+use diesel::Insertable;
+use serde::Serialize;
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=notebooks)]
+pub struct NewNotebook {
+    pub user_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+}
@@ -0,0 +1,49 @@
+use crate::models::Notebook;
+use crate::utils::db::DbResult;
+use actix::Message;
+use std::collections::HashMap;
+
+#[derive(Message)]
+#[rtype(result = "DbResult<(Vec<Notebook>, HashMap<Option<i32>, i64>)>")]
+pub struct FetchNotebooks {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Notebook>")]
+pub struct FetchNotebook {
+    pub user_id: i32,
+    pub notebook_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Notebook>")]
+pub struct CreateNotebook {
+    pub user_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Notebook>")]
+pub struct RenameNotebook {
+    pub user_id: i32,
+    pub notebook_id: i32,
+    pub name: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Notebook>")]
+pub struct MoveNotebook {
+    pub user_id: i32,
+    pub notebook_id: i32,
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct DeleteNotebook {
+    pub user_id: i32,
+    pub notebook_id: i32,
+    pub delete_notes: bool,
+}
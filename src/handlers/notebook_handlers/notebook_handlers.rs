@@ -0,0 +1,382 @@
+use super::messages::*;
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::Notebook,
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::Claims,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get, patch, post,
+    web::{Data, Json, Path, Query},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+#[derive(Serialize)]
+struct NotebookNode {
+    id: i32,
+    name: String,
+    parent_id: Option<i32>,
+    created_on: DateTime<Utc>,
+    /// Notes filed directly in this notebook.
+    note_count: i64,
+    /// Notes in this notebook and every notebook nested below it.
+    total_note_count: i64,
+    children: Vec<NotebookNode>,
+}
+
+#[derive(Serialize)]
+struct NotebookTreeResponse {
+    notebooks: Vec<NotebookNode>,
+    unfiled_note_count: i64,
+}
+
+fn build_tree(
+    parent_id: Option<i32>,
+    user_notebooks: &[Notebook],
+    note_counts: &HashMap<Option<i32>, i64>,
+) -> Vec<NotebookNode> {
+    user_notebooks
+        .iter()
+        .filter(|notebook| notebook.parent_id == parent_id)
+        .map(|notebook| {
+            let children: Vec<NotebookNode> =
+                build_tree(Some(notebook.id), user_notebooks, note_counts);
+            let note_count: i64 = note_counts
+                .get(&Some(notebook.id))
+                .copied()
+                .unwrap_or_default();
+
+            NotebookNode {
+                id: notebook.id,
+                name: notebook.name.clone(),
+                parent_id: notebook.parent_id,
+                created_on: notebook.created_on,
+                note_count,
+                total_note_count: note_count
+                    + children
+                        .iter()
+                        .map(|child| child.total_note_count)
+                        .sum::<i64>(),
+                children,
+            }
+        })
+        .collect()
+}
+
+fn validate_notebook_name(name: &str) -> Result<String, ApiError> {
+    let name: &str = name.trim();
+
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(ApiError::BadRequest(String::from(
+            "notebook names must be between 1 and 100 characters",
+        )));
+    }
+
+    Ok(name.to_string())
+}
+
+#[utoipa::path(
+    path = "/api/notebooks",
+    responses(
+        (status = 200, description = "Successfully retrieved the authenticated user's notebooks as a tree, with the number of notes in each notebook and the number of notes outside any notebook."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 500, description = "Internal server error: Unable to retrieve notebooks."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn fetch_notebooks(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let (user_notebooks, note_counts) = db.send(FetchNotebooks { user_id: claims.id }).await??;
+
+    Ok(HttpResponse::Ok().json(NotebookTreeResponse {
+        notebooks: build_tree(None, &user_notebooks, &note_counts),
+        unfiled_note_count: note_counts.get(&None).copied().unwrap_or_default(),
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateNotebookRequest {
+    #[schema(example = "work", required = true)]
+    pub name: String,
+    #[schema(example = 1)]
+    pub parent_id: Option<i32>,
+}
+
+#[utoipa::path(
+    path = "/api/notebooks",
+    request_body = CreateNotebookRequest,
+    responses(
+        (status = 201, description = "Notebook created."),
+        (status = 400, description = "Invalid notebook name."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Parent notebook not found."),
+        (status = 500, description = "Internal server error: Unable to create the notebook."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("")]
+pub async fn create_notebook(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<CreateNotebookRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let name: String = validate_notebook_name(&body.name)?;
+
+    let notebook: Notebook = db
+        .send(CreateNotebook {
+            user_id: claims.id,
+            parent_id: body.parent_id,
+            name,
+        })
+        .await?
+        .not_found(|| String::from("parent notebook not found"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "notebook.created",
+        Some(("notebook", notebook.id.to_string())),
+        serde_json::json!({ "name": notebook.name, "parent_id": notebook.parent_id }),
+    )
+    .await;
+
+    Ok(HttpResponse::Created().json(notebook))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RenameNotebookRequest {
+    #[schema(example = "personal", required = true)]
+    pub name: String,
+}
+
+#[utoipa::path(
+    path = "/api/notebooks/{notebook_id}",
+    request_body = RenameNotebookRequest,
+    responses(
+        (status = 200, description = "Notebook renamed."),
+        (status = 400, description = "Invalid notebook name."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Notebook not found."),
+        (status = 500, description = "Internal server error: Unable to rename the notebook."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[patch("/{notebook_id}")]
+pub async fn rename_notebook(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    body: Json<RenameNotebookRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let notebook_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let name: String = validate_notebook_name(&body.name)?;
+
+    let notebook: Notebook = db
+        .send(RenameNotebook {
+            user_id: claims.id,
+            notebook_id,
+            name,
+        })
+        .await?
+        .not_found(|| format!("notebook {} not found", notebook_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "notebook.renamed",
+        Some(("notebook", notebook_id.to_string())),
+        serde_json::json!({ "name": notebook.name }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(notebook))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MoveNotebookRequest {
+    /// The new parent notebook, or null to move the notebook to the top level.
+    #[schema(example = 1)]
+    pub parent_id: Option<i32>,
+}
+
+#[utoipa::path(
+    path = "/api/notebooks/{notebook_id}/move",
+    request_body = MoveNotebookRequest,
+    responses(
+        (status = 200, description = "Notebook moved along with everything nested below it."),
+        (status = 400, description = "A notebook cannot be moved into itself or one of its descendants."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Notebook or parent notebook not found."),
+        (status = 500, description = "Internal server error: Unable to move the notebook."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/{notebook_id}/move")]
+pub async fn move_notebook(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    body: Json<MoveNotebookRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let notebook_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let notebook: Notebook = db
+        .send(MoveNotebook {
+            user_id: claims.id,
+            notebook_id,
+            parent_id: body.parent_id,
+        })
+        .await?
+        .not_found(|| String::from("notebook not found"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "notebook.moved",
+        Some(("notebook", notebook_id.to_string())),
+        serde_json::json!({ "parent_id": notebook.parent_id }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(notebook))
+}
+
+#[derive(Deserialize)]
+pub struct DeleteNotebookQuery {
+    notes: Option<String>,
+}
+
+#[utoipa::path(
+    path = "/api/notebooks/{notebook_id}",
+    params(
+        ("notes" = Option<String>, Query, description = "What happens to the notebook's contents: move (default) hands its notes and child notebooks to its parent notebook, delete removes it with every nested notebook and moves their notes to the trash."),
+    ),
+    responses(
+        (status = 200, description = "Notebook deleted."),
+        (status = 400, description = "Invalid notes option."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Notebook not found."),
+        (status = 500, description = "Internal server error: Unable to delete the notebook."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/{notebook_id}")]
+pub async fn delete_notebook(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<DeleteNotebookQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let notebook_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let delete_notes: bool = match query.notes.as_deref() {
+        None | Some("move") => false,
+        Some("delete") => true,
+        Some(_) => {
+            return Err(ApiError::BadRequest(String::from(
+                "notes must be move or delete",
+            )));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let affected_notes: usize = db
+        .send(DeleteNotebook {
+            user_id: claims.id,
+            notebook_id,
+            delete_notes,
+        })
+        .await?
+        .not_found(|| format!("notebook {} not found", notebook_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "notebook.deleted",
+        Some(("notebook", notebook_id.to_string())),
+        serde_json::json!({ "delete_notes": delete_notes, "affected_notes": affected_notes }),
+    )
+    .await;
+
+    let message: String = if delete_notes {
+        format!(
+            "deleted notebook {} and moved {} notes to the trash",
+            notebook_id, affected_notes
+        )
+    } else {
+        format!(
+            "deleted notebook {} and moved {} notes to its parent",
+            notebook_id, affected_notes
+        )
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "message": message })))
+}
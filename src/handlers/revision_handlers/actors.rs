@@ -0,0 +1,161 @@
+use super::insertables::NewNoteRevision;
+use super::messages::*;
+use crate::models::{Note, NoteRevision};
+use crate::schema::{note_revisions, notes};
+use crate::utils::{
+    config::RevisionConfig,
+    db::{DbActor, DbResult},
+};
+use actix::Handler;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+
+/// Records the note's current title and content as its newest revision,
+/// unless they are unchanged since the last one. Returns the newest revision
+/// number.
+pub fn record_revision(
+    connection: &mut PgConnection,
+    note: &Note,
+    edited_by: i32,
+) -> QueryResult<i32> {
+    let head: Option<NoteRevision> = note_revisions::table
+        .filter(note_revisions::note_id.eq(note.id))
+        .order(note_revisions::revision.desc())
+        .first::<NoteRevision>(connection)
+        .optional()?;
+
+    if let Some(ref head) = head {
+        if head.title == note.title && head.content == note.content {
+            return Ok(head.revision);
+        }
+    }
+
+    let revision: i32 = head.map(|head| head.revision).unwrap_or_default() + 1;
+
+    diesel::insert_into(note_revisions::table)
+        .values(NewNoteRevision {
+            note_id: note.id,
+            revision,
+            title: note.title.clone(),
+            content: note.content.clone(),
+            edited_by: Some(edited_by),
+        })
+        .execute(connection)?;
+
+    Ok(revision)
+}
+
+/// Deletes the revisions of a note that fall outside the retention policy,
+/// always keeping the newest one.
+pub fn prune_revisions(
+    connection: &mut PgConnection,
+    note_id: i32,
+    retention: &RevisionConfig,
+) -> QueryResult<usize> {
+    let head: Option<i32> = note_revisions::table
+        .filter(note_revisions::note_id.eq(note_id))
+        .select(diesel::dsl::max(note_revisions::revision))
+        .first::<Option<i32>>(connection)?;
+
+    let Some(head) = head else {
+        return Ok(0);
+    };
+
+    let mut pruned: usize = 0;
+
+    if retention.keep_per_note > 0 {
+        pruned += diesel::delete(
+            note_revisions::table
+                .filter(note_revisions::note_id.eq(note_id))
+                .filter(note_revisions::revision.le(head - retention.keep_per_note as i32)),
+        )
+        .execute(connection)?;
+    }
+
+    if retention.keep_days > 0 {
+        pruned += diesel::delete(
+            note_revisions::table
+                .filter(note_revisions::note_id.eq(note_id))
+                .filter(note_revisions::revision.lt(head))
+                .filter(
+                    note_revisions::created_on
+                        .lt(Utc::now() - Duration::days(retention.keep_days as i64)),
+                ),
+        )
+        .execute(connection)?;
+    }
+
+    Ok(pruned)
+}
+
+fn find_user_note(connection: &mut PgConnection, user_id: i32, note_id: i32) -> QueryResult<Note> {
+    notes::table
+        .filter(notes::id.eq(note_id))
+        .filter(notes::created_by.eq(user_id))
+        .filter(notes::deleted_at.is_null())
+        .first::<Note>(connection)
+}
+
+fn find_revision(
+    connection: &mut PgConnection,
+    note_id: i32,
+    revision: i32,
+) -> QueryResult<NoteRevision> {
+    note_revisions::table
+        .filter(note_revisions::note_id.eq(note_id))
+        .filter(note_revisions::revision.eq(revision))
+        .first::<NoteRevision>(connection)
+}
+
+impl Handler<FetchRevisions> for DbActor {
+    type Result = DbResult<Vec<NoteRevision>>;
+
+    fn handle(&mut self, msg: FetchRevisions, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let note: Note = find_user_note(&mut connection, msg.user_id, msg.note_id)?;
+
+        Ok(note_revisions::table
+            .filter(note_revisions::note_id.eq(note.id))
+            .order(note_revisions::revision.desc())
+            .get_results::<NoteRevision>(&mut connection)?)
+    }
+}
+
+impl Handler<FetchRevision> for DbActor {
+    type Result = DbResult<NoteRevision>;
+
+    fn handle(&mut self, msg: FetchRevision, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let note: Note = find_user_note(&mut connection, msg.user_id, msg.note_id)?;
+
+        Ok(find_revision(&mut connection, note.id, msg.revision)?)
+    }
+}
+
+impl Handler<RestoreRevision> for DbActor {
+    type Result = DbResult<Note>;
+
+    fn handle(&mut self, msg: RestoreRevision, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let note: Note = find_user_note(connection, msg.user_id, msg.note_id)?;
+            let revision: NoteRevision = find_revision(connection, note.id, msg.revision)?;
+
+            let note: Note = diesel::update(notes::table.find(note.id))
+                .set((
+                    notes::title.eq(revision.title),
+                    notes::content.eq(revision.content),
+                    notes::updated_on.eq(Utc::now()),
+                ))
+                .get_result::<Note>(connection)?;
+
+            record_revision(connection, &note, msg.user_id)?;
+            prune_revisions(connection, note.id, &msg.retention)?;
+
+            Ok(note)
+        })
+    }
+}
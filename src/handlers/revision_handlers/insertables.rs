@@ -0,0 +1,13 @@
+use crate::schema::note_revisions;
+use diesel::Insertable;
+use serde::Serialize;
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=note_revisions)]
+pub struct NewNoteRevision {
+    pub note_id: i32,
+    pub revision: i32,
+    pub title: String,
+    pub content: String,
+    pub edited_by: Option<i32>,
+}
@@ -0,0 +1,27 @@
+use crate::models::{Note, NoteRevision};
+use crate::utils::{config::RevisionConfig, db::DbResult};
+use actix::Message;
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<NoteRevision>>")]
+pub struct FetchRevisions {
+    pub user_id: i32,
+    pub note_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<NoteRevision>")]
+pub struct FetchRevision {
+    pub user_id: i32,
+    pub note_id: i32,
+    pub revision: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Note>")]
+pub struct RestoreRevision {
+    pub user_id: i32,
+    pub note_id: i32,
+    pub revision: i32,
+    pub retention: RevisionConfig,
+}
@@ -0,0 +1,282 @@
+use super::messages::*;
+use crate::{
+    handlers::{
+        audit_handlers::audit_handlers::record_audit_event, note_handlers::utils::with_tags,
+    },
+    models::{Note, NoteRevision},
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::Claims,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    get, post,
+    web::{Data, Path, Query},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+#[derive(Serialize)]
+struct RevisionSummary {
+    revision: i32,
+    title: String,
+    edited_by: Option<i32>,
+    created_on: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct WordChange {
+    op: &'static str,
+    text: String,
+}
+
+/// Groups a word-level diff into runs of inserted, deleted and unchanged text.
+fn word_diff(old: &str, new: &str) -> Vec<WordChange> {
+    let mut changes: Vec<WordChange> = Vec::new();
+
+    for change in TextDiff::from_words(old, new).iter_all_changes() {
+        let op: &'static str = match change.tag() {
+            ChangeTag::Equal => "equal",
+            ChangeTag::Insert => "insert",
+            ChangeTag::Delete => "delete",
+        };
+
+        match changes.last_mut() {
+            Some(last) if last.op == op => last.text.push_str(change.value()),
+            _ => changes.push(WordChange {
+                op,
+                text: change.value().to_string(),
+            }),
+        }
+    }
+
+    changes
+}
+
+async fn fetch_revision(
+    db: &Addr<DbActor>,
+    user_id: i32,
+    note_id: i32,
+    revision: i32,
+) -> Result<NoteRevision, ApiError> {
+    db.send(FetchRevision {
+        user_id,
+        note_id,
+        revision,
+    })
+    .await?
+    .not_found(|| format!("revision {} of note {} not found", revision, note_id))
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/revisions",
+    responses(
+        (status = 200, description = "Successfully retrieved the note's revisions, newest first, without their content."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Note not found."),
+        (status = 500, description = "Internal server error: Unable to retrieve revisions."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn fetch_revisions(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let revisions: Vec<RevisionSummary> = db
+        .send(FetchRevisions {
+            user_id: claims.id,
+            note_id,
+        })
+        .await?
+        .not_found(|| format!("note {} not found", note_id))?
+        .into_iter()
+        .map(|revision| RevisionSummary {
+            revision: revision.revision,
+            title: revision.title,
+            edited_by: revision.edited_by,
+            created_on: revision.created_on,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+#[derive(Deserialize)]
+pub struct RevisionDiffQuery {
+    from: i32,
+    to: i32,
+    mode: Option<String>,
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/revisions/diff",
+    params(
+        ("from" = i32, Query, description = "The older revision number."),
+        ("to" = i32, Query, description = "The newer revision number."),
+        ("mode" = Option<String>, Query, description = "unified (default) returns a unified diff of the content, word returns runs of equal, inserted and deleted words."),
+    ),
+    responses(
+        (status = 200, description = "The difference between the two revisions."),
+        (status = 400, description = "Invalid diff mode."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Note or revision not found."),
+        (status = 500, description = "Internal server error: Unable to diff the revisions."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/diff")]
+pub async fn diff_revisions(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<RevisionDiffQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let mode: &str = query.mode.as_deref().unwrap_or("unified");
+
+    if !matches!(mode, "unified" | "word") {
+        return Err(ApiError::BadRequest(String::from(
+            "mode must be unified or word",
+        )));
+    }
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let from: NoteRevision = fetch_revision(&db, claims.id, note_id, query.from).await?;
+    let to: NoteRevision = fetch_revision(&db, claims.id, note_id, query.to).await?;
+
+    let diff: serde_json::Value = if mode == "word" {
+        serde_json::json!(word_diff(&from.content, &to.content))
+    } else {
+        serde_json::json!(TextDiff::from_lines(&from.content, &to.content)
+            .unified_diff()
+            .header(
+                &format!("revision {}", from.revision),
+                &format!("revision {}", to.revision),
+            )
+            .to_string())
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "from": from.revision,
+        "to": to.revision,
+        "mode": mode,
+        "title": { "from": from.title, "to": to.title },
+        "diff": diff,
+    })))
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/revisions/{revision}",
+    responses(
+        (status = 200, description = "Successfully retrieved the revision with its content."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Note or revision not found."),
+        (status = 500, description = "Internal server error: Unable to retrieve the revision."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/{revision}")]
+pub async fn fetch_note_revision(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (note_id, revision) = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let revision: NoteRevision = fetch_revision(&db, claims.id, note_id, revision).await?;
+
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/revisions/{revision}/restore",
+    responses(
+        (status = 200, description = "The note's title and content were replaced with the revision's, recorded as a new revision."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Note or revision not found."),
+        (status = 500, description = "Internal server error: Unable to restore the revision."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/{revision}/restore")]
+pub async fn restore_note_revision(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (note_id, revision) = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let note: Note = db
+        .send(RestoreRevision {
+            user_id: claims.id,
+            note_id,
+            revision,
+            retention: state.config.revisions.clone(),
+        })
+        .await?
+        .not_found(|| format!("revision {} of note {} not found", revision, note_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.revision_restored",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({ "revision": revision }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(with_tags(&db, vec![note]).await?.pop()))
+}
@@ -0,0 +1,147 @@
+use super::insertables::{NewRole, NewRolePermission};
+use super::messages::*;
+use crate::models::{Permission, Role, User};
+use crate::schema::{permissions, role_permissions, roles, users};
+use crate::utils::db::{DbActor, DbResult};
+use actix::Handler;
+use diesel::prelude::*;
+
+fn replace_role_permissions(
+    connection: &mut PgConnection,
+    role_id: i32,
+    permission_names: &[String],
+) -> QueryResult<usize> {
+    diesel::delete(role_permissions::table.filter(role_permissions::role_id.eq(role_id)))
+        .execute(connection)?;
+
+    let new_role_permissions: Vec<NewRolePermission> = permissions::table
+        .filter(permissions::name.eq_any(permission_names))
+        .select(permissions::id)
+        .get_results::<i32>(connection)?
+        .into_iter()
+        .map(|permission_id| NewRolePermission {
+            role_id,
+            permission_id,
+        })
+        .collect();
+
+    if new_role_permissions.is_empty() {
+        return Ok(0);
+    }
+
+    diesel::insert_into(role_permissions::table)
+        .values(new_role_permissions)
+        .execute(connection)
+}
+
+impl Handler<FetchRoles> for DbActor {
+    type Result = DbResult<Vec<(Role, Vec<String>)>>;
+
+    fn handle(&mut self, _msg: FetchRoles, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let all_roles: Vec<Role> = roles::table
+            .order(roles::name.asc())
+            .get_results::<Role>(&mut connection)?;
+
+        let grants: Vec<(i32, String)> = role_permissions::table
+            .inner_join(permissions::table)
+            .select((role_permissions::role_id, permissions::name))
+            .order(permissions::name.asc())
+            .get_results::<(i32, String)>(&mut connection)?;
+
+        Ok(all_roles
+            .into_iter()
+            .map(|role| {
+                let role_permission_names: Vec<String> = grants
+                    .iter()
+                    .filter(|(role_id, _)| *role_id == role.id)
+                    .map(|(_, name)| name.clone())
+                    .collect();
+
+                (role, role_permission_names)
+            })
+            .collect())
+    }
+}
+
+impl Handler<FetchPermissions> for DbActor {
+    type Result = DbResult<Vec<Permission>>;
+
+    fn handle(&mut self, _msg: FetchPermissions, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(permissions::table
+            .order(permissions::name.asc())
+            .get_results::<Permission>(&mut connection)?)
+    }
+}
+
+impl Handler<FetchUserPermissions> for DbActor {
+    type Result = DbResult<Vec<String>>;
+
+    fn handle(&mut self, msg: FetchUserPermissions, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let user_role: String = users::table
+            .find(msg.user_id)
+            .select(users::role)
+            .first::<String>(&mut connection)?;
+
+        Ok(permissions::table
+            .inner_join(role_permissions::table.inner_join(roles::table))
+            .filter(roles::name.eq(user_role))
+            .select(permissions::name)
+            .get_results::<String>(&mut connection)?)
+    }
+}
+
+impl Handler<CreateRole> for DbActor {
+    type Result = DbResult<Role>;
+
+    fn handle(&mut self, msg: CreateRole, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let role: Role = diesel::insert_into(roles::table)
+                .values(NewRole {
+                    name: msg.name,
+                    description: msg.description,
+                })
+                .get_result::<Role>(connection)?;
+
+            replace_role_permissions(connection, role.id, &msg.permissions)?;
+
+            Ok(role)
+        })
+    }
+}
+
+impl Handler<SetRolePermissions> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: SetRolePermissions, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection.transaction(|connection| {
+            let role_id: i32 = roles::table
+                .filter(roles::name.eq(&msg.role_name))
+                .select(roles::id)
+                .first::<i32>(connection)?;
+
+            replace_role_permissions(connection, role_id, &msg.permissions)
+        })?)
+    }
+}
+
+impl Handler<AssignUserRole> for DbActor {
+    type Result = DbResult<User>;
+
+    fn handle(&mut self, msg: AssignUserRole, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(users::table.find(msg.user_id))
+            .set(users::role.eq(msg.role))
+            .get_result::<User>(&mut connection)?)
+    }
+}
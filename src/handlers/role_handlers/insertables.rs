@@ -0,0 +1,17 @@
+use crate::schema::{role_permissions, roles};
+use diesel::Insertable;
+use serde::Serialize;
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=roles)]
+pub struct NewRole {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=role_permissions)]
+pub struct NewRolePermission {
+    pub role_id: i32,
+    pub permission_id: i32,
+}
@@ -0,0 +1,39 @@
+use crate::models::{Permission, Role, User};
+use crate::utils::db::DbResult;
+use actix::Message;
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<(Role, Vec<String>)>>")]
+pub struct FetchRoles;
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<Permission>>")]
+pub struct FetchPermissions;
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<String>>")]
+pub struct FetchUserPermissions {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Role>")]
+pub struct CreateRole {
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct SetRolePermissions {
+    pub role_name: String,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<User>")]
+pub struct AssignUserRole {
+    pub user_id: i32,
+    pub role: String,
+}
@@ -0,0 +1,4 @@
+pub mod actors;
+pub mod insertables;
+pub mod messages;
+pub mod role_handlers;
@@ -0,0 +1,303 @@
+use super::messages::*;
+use crate::{
+    handlers::{
+        audit_handlers::audit_handlers::{record_audit_event, request_actor_id},
+        auth_handlers::utils::UserResponse,
+    },
+    middlewares::auth_middlewares::require_permission,
+    models::{Permission, Role},
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+    },
+};
+use actix::Addr;
+use actix_web::{
+    get, post, put,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize)]
+struct RoleResponse {
+    id: i32,
+    name: String,
+    description: Option<String>,
+    permissions: Vec<String>,
+    created_on: DateTime<Utc>,
+}
+
+impl From<(Role, Vec<String>)> for RoleResponse {
+    fn from((role, permissions): (Role, Vec<String>)) -> Self {
+        RoleResponse {
+            id: role.id,
+            name: role.name,
+            description: role.description,
+            permissions,
+            created_on: role.created_on,
+        }
+    }
+}
+
+async fn find_unknown_permission(
+    db: &Addr<DbActor>,
+    requested: &[String],
+) -> Result<Option<String>, ApiError> {
+    let known: Vec<Permission> = db.send(FetchPermissions).await??;
+
+    Ok(requested
+        .iter()
+        .find(|name| !known.iter().any(|permission| &permission.name == *name))
+        .cloned())
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/roles",
+    responses(
+        (status = 200, description = "Successfully retrieved every role with the permissions it grants."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the roles:manage permission."),
+        (status = 500, description = "Internal Server Error: Unable to retrieve roles."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/roles", wrap = "require_permission(\"roles:manage\")")]
+pub async fn fetch_roles(state: Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let roles: Vec<RoleResponse> = db
+        .send(FetchRoles)
+        .await??
+        .into_iter()
+        .map(RoleResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/permissions",
+    responses(
+        (status = 200, description = "Successfully retrieved every permission that can be granted to a role."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the roles:manage permission."),
+        (status = 500, description = "Internal Server Error: Unable to retrieve permissions."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("/permissions", wrap = "require_permission(\"roles:manage\")")]
+pub async fn fetch_permissions(state: Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let permissions: Vec<Permission> = db.send(FetchPermissions).await??;
+
+    Ok(HttpResponse::Ok().json(permissions))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateRoleRequest {
+    #[schema(example = "moderator", required = true)]
+    pub name: String,
+    #[schema(example = "Can read every note")]
+    pub description: Option<String>,
+    #[schema(example = json!(["admin:access", "notes:read_all"]), required = true)]
+    pub permissions: Vec<String>,
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/roles",
+    request_body = CreateRoleRequest,
+    responses(
+        (status = 201, description = "Role created."),
+        (status = 400, description = "Invalid role name or unknown permission."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the roles:manage permission."),
+        (status = 409, description = "A role with that name already exists."),
+        (status = 500, description = "Internal Server Error: Unable to create the role."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/roles", wrap = "require_permission(\"roles:manage\")")]
+pub async fn create_role(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<CreateRoleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let name: String = body.name.trim().to_lowercase();
+
+    if name.is_empty() || name.len() > 50 {
+        return Err(ApiError::BadRequest(String::from(
+            "name must be between 1 and 50 characters",
+        )));
+    }
+
+    if let Some(permission) = find_unknown_permission(&db, &body.permissions).await? {
+        return Err(ApiError::BadRequest(format!(
+            "unknown permission {}",
+            permission
+        )));
+    }
+
+    let role: Role = db
+        .send(CreateRole {
+            name,
+            description: body.description.clone(),
+            permissions: body.permissions.clone(),
+        })
+        .await?
+        .conflict(|| String::from("role already exists"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "role.created",
+        Some(("role", role.name.clone())),
+        serde_json::json!({ "permissions": body.permissions }),
+    )
+    .await;
+
+    Ok(HttpResponse::Created().json(RoleResponse::from((role, body.permissions.clone()))))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateRolePermissionsRequest {
+    #[schema(example = json!(["admin:access", "notes:read_all"]), required = true)]
+    pub permissions: Vec<String>,
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/roles/{role_name}/permissions",
+    request_body = UpdateRolePermissionsRequest,
+    responses(
+        (status = 200, description = "The role now grants exactly the given permissions."),
+        (status = 400, description = "Unknown permission."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the roles:manage permission."),
+        (status = 404, description = "Role not found."),
+        (status = 500, description = "Internal Server Error: Unable to update the role."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[put(
+    "/roles/{role_name}/permissions",
+    wrap = "require_permission(\"roles:manage\")"
+)]
+pub async fn update_role_permissions(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<String>,
+    body: Json<UpdateRolePermissionsRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let role_name: String = path.into_inner();
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    if let Some(permission) = find_unknown_permission(&db, &body.permissions).await? {
+        return Err(ApiError::BadRequest(format!(
+            "unknown permission {}",
+            permission
+        )));
+    }
+
+    db.send(SetRolePermissions {
+        role_name: role_name.clone(),
+        permissions: body.permissions.clone(),
+    })
+    .await?
+    .not_found(|| format!("role {} not found", role_name))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "role.permissions_updated",
+        Some(("role", role_name.clone())),
+        serde_json::json!({ "permissions": body.permissions }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("updated permissions of role {}", role_name),
+        "permissions": body.permissions,
+    })))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignRoleRequest {
+    #[schema(example = "moderator", required = true)]
+    pub role: String,
+}
+
+#[utoipa::path(
+    path = "/admin/dashboard/users/{user_id}/role",
+    request_body = AssignRoleRequest,
+    responses(
+        (status = 200, description = "Role assigned. It applies to the user's next request."),
+        (status = 400, description = "Role does not exist."),
+        (status = 401, description = "Unauthorized access, JWT token is missing or invalid."),
+        (status = 403, description = "The authenticated user is missing the roles:manage permission."),
+        (status = 404, description = "User not found."),
+        (status = 500, description = "Internal Server Error: Unable to assign the role."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[put("/users/{user_id}/role", wrap = "require_permission(\"roles:manage\")")]
+pub async fn assign_user_role(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    body: Json<AssignRoleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id: i32 = path.into_inner();
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let user = match db
+        .send(AssignUserRole {
+            user_id,
+            role: body.role.clone(),
+        })
+        .await?
+    {
+        Ok(user) => user,
+        Err(ApiError::NotFound(_)) => {
+            return Err(ApiError::NotFound(format!("user {} not found", user_id)))
+        }
+        Err(ApiError::Database(DieselError::DatabaseError(
+            DatabaseErrorKind::ForeignKeyViolation,
+            _,
+        ))) => {
+            return Err(ApiError::BadRequest(format!(
+                "role {} does not exist",
+                body.role
+            )))
+        }
+        Err(err) => return Err(err),
+    };
+
+    record_audit_event(
+        &db,
+        &req,
+        request_actor_id(&req),
+        "user.role_changed",
+        Some(("user", user_id.to_string())),
+        serde_json::json!({ "role": user.role }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
+}
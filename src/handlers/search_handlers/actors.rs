@@ -0,0 +1,110 @@
+use super::messages::*;
+use crate::models::Note;
+use crate::schema::notes;
+use crate::utils::db::{DbActor, DbResult};
+use actix::Handler;
+use diesel::{
+    dsl::sql,
+    pg::Pg,
+    prelude::*,
+    sql_types::{BigInt, Bool, Float, Int4, Int8, Text},
+};
+use std::collections::HashMap;
+
+// The generated `notes.search_vector` column is indexed with the `english`
+// configuration, so every query below has to use the same one to hit the index.
+
+const COUNT_MATCHES_SQL: &str = "\
+    SELECT COUNT(*) AS total \
+    FROM notes n \
+    LEFT JOIN note_shares s ON s.note_id = n.id AND s.user_id = $2 \
+    WHERE n.search_vector @@ to_tsquery('english', $1) \
+      AND n.deleted_at IS NULL \
+      AND (n.created_by = $2 OR s.user_id IS NOT NULL)";
+
+// Headlines are only built for the requested page since ts_headline has to
+// re-parse each document.
+const SEARCH_HITS_SQL: &str = "\
+    WITH search AS (SELECT to_tsquery('english', $1) AS query), \
+    matches AS ( \
+      SELECT n.id, n.title, n.content, n.updated_on, \
+        ts_rank_cd(n.search_vector, search.query) AS rank, \
+        CASE WHEN n.created_by = $2 THEN 'owner' ELSE s.permission END AS permission \
+      FROM notes n \
+      CROSS JOIN search \
+      LEFT JOIN note_shares s ON s.note_id = n.id AND s.user_id = $2 \
+      WHERE n.search_vector @@ search.query \
+        AND n.deleted_at IS NULL \
+        AND (n.created_by = $2 OR s.user_id IS NOT NULL) \
+      ORDER BY rank DESC, n.updated_on DESC NULLS LAST, n.id \
+      LIMIT $3 OFFSET $4 \
+    ) \
+    SELECT m.id, m.rank, m.permission, \
+      ts_headline('english', translate(m.title, E'\\x02\\x03', ''), search.query, \
+        E'HighlightAll=true, StartSel=\\x02, StopSel=\\x03') AS title_highlight, \
+      ts_headline('english', translate(m.content, E'\\x02\\x03', ''), search.query, \
+        E'MaxFragments=2, MinWords=10, MaxWords=30, StartSel=\\x02, StopSel=\\x03') AS snippet \
+    FROM matches m \
+    CROSS JOIN search \
+    ORDER BY m.rank DESC, m.updated_on DESC NULLS LAST, m.id";
+
+#[derive(QueryableByName)]
+struct MatchCount {
+    #[diesel(sql_type = BigInt)]
+    total: i64,
+}
+
+/// `search_vector @@ tsquery`, for filtering boxed `notes` queries.
+pub fn matches_search(
+    tsquery: &str,
+) -> Box<dyn BoxableExpression<notes::table, Pg, SqlType = Bool>> {
+    Box::new(
+        sql::<Bool>("search_vector @@ to_tsquery('english', ")
+            .bind::<Text, _>(tsquery.to_string())
+            .sql(")"),
+    )
+}
+
+/// How well a note matches a tsquery, for ordering boxed `notes` queries.
+pub fn search_rank(tsquery: &str) -> Box<dyn BoxableExpression<notes::table, Pg, SqlType = Float>> {
+    Box::new(
+        sql::<Float>("ts_rank_cd(search_vector, to_tsquery('english', ")
+            .bind::<Text, _>(tsquery.to_string())
+            .sql("))"),
+    )
+}
+
+impl Handler<SearchNotes> for DbActor {
+    type Result = DbResult<(i64, Vec<(Note, SearchHit)>)>;
+
+    fn handle(&mut self, msg: SearchNotes, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let total_matches: i64 = diesel::sql_query(COUNT_MATCHES_SQL)
+            .bind::<Text, _>(&msg.query)
+            .bind::<Int4, _>(msg.user_id)
+            .get_result::<MatchCount>(&mut connection)?
+            .total;
+
+        let hits: Vec<SearchHit> = diesel::sql_query(SEARCH_HITS_SQL)
+            .bind::<Text, _>(&msg.query)
+            .bind::<Int4, _>(msg.user_id)
+            .bind::<Int8, _>(msg.limit)
+            .bind::<Int8, _>(msg.offset)
+            .load::<SearchHit>(&mut connection)?;
+
+        let mut matched_notes: HashMap<i32, Note> = notes::table
+            .filter(notes::id.eq_any(hits.iter().map(|hit| hit.id).collect::<Vec<i32>>()))
+            .get_results::<Note>(&mut connection)?
+            .into_iter()
+            .map(|note| (note.id, note))
+            .collect();
+
+        Ok((
+            total_matches,
+            hits.into_iter()
+                .filter_map(|hit| matched_notes.remove(&hit.id).map(|note| (note, hit)))
+                .collect(),
+        ))
+    }
+}
@@ -0,0 +1,33 @@
+use crate::models::Note;
+use crate::utils::db::DbResult;
+use actix::Message;
+use diesel::{
+    sql_types::{Float, Int4, Text},
+    QueryableByName,
+};
+
+#[derive(QueryableByName, Debug)]
+pub struct SearchHit {
+    #[diesel(sql_type = Int4)]
+    pub id: i32,
+    #[diesel(sql_type = Float)]
+    pub rank: f32,
+    /// owner, edit or read.
+    #[diesel(sql_type = Text)]
+    pub permission: String,
+    #[diesel(sql_type = Text)]
+    pub title_highlight: String,
+    #[diesel(sql_type = Text)]
+    pub snippet: String,
+}
+
+/// Notes the user owns or that were shared with them matching a `to_tsquery`
+/// query, best match first, with the total number of matches.
+#[derive(Message)]
+#[rtype(result = "DbResult<(i64, Vec<(Note, SearchHit)>)>")]
+pub struct SearchNotes {
+    pub user_id: i32,
+    pub query: String,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,124 @@
+use super::{
+    messages::*,
+    utils::{escape_headline, parse_search_query},
+};
+use crate::{
+    handlers::note_handlers::utils::{with_tags, NoteResponse},
+    models::Note,
+    utils::{
+        db::{AppState, DbActor},
+        errors::ApiError,
+        jwt::Claims,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    get,
+    web::{Data, Query},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    q: String,
+    limit: Option<i64>,
+    page: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct SearchResultResponse {
+    #[serde(flatten)]
+    note: NoteResponse,
+    permission: String,
+    rank: f32,
+    title_highlight: String,
+    snippet: String,
+}
+
+#[derive(Serialize)]
+struct SearchResponse {
+    total_results: i64,
+    number_of_page: i64,
+    page: i64,
+    results: Vec<SearchResultResponse>,
+}
+
+#[utoipa::path(
+    path = "/api/search",
+    params(
+        ("q" = String, Query, description = "Words to search for in titles and contents. Use \"quoted words\" for a phrase, word* for a prefix, -word to exclude and or between two terms to accept either (example: \"release notes\" deploy* -draft)."),
+        ("page" = Option<i64>, Query, description = "Page number for pagination (default: 1)."),
+        ("limit" = Option<i64>, Query, description = "Limit of results per page, between 1 and 100 (default: 20)."),
+    ),
+    responses(
+        (status = 200, description = "Notes the user owns or that were shared with them, best match first. title_highlight and snippet are HTML-escaped with the matching words wrapped in <mark> tags."),
+        (status = 400, description = "Empty or too long search, or invalid pagination."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 500, description = "Internal server error: Unable to search notes."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn search_notes(
+    state: Data<AppState>,
+    req: HttpRequest,
+    query: Query<SearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let limit: i64 = query.limit.unwrap_or(20);
+    let page: i64 = query.page.unwrap_or(1);
+
+    if !(1..=100).contains(&limit) || page < 1 {
+        return Err(ApiError::BadRequest(String::from(
+            "limit must be between 1 and 100 and page must be positive",
+        )));
+    }
+
+    let offset: i64 = (page - 1)
+        .checked_mul(limit)
+        .ok_or_else(|| ApiError::BadRequest(String::from("page is too large")))?;
+
+    let tsquery: String = parse_search_query(&query.q)?;
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let (total_results, matches) = db
+        .send(SearchNotes {
+            user_id: claims.id,
+            query: tsquery,
+            limit,
+            offset,
+        })
+        .await??;
+
+    let (notes, hits): (Vec<Note>, Vec<SearchHit>) = matches.into_iter().unzip();
+
+    let results: Vec<SearchResultResponse> = with_tags(&db, notes)
+        .await?
+        .into_iter()
+        .zip(hits)
+        .map(|(note, hit)| SearchResultResponse {
+            note,
+            permission: hit.permission,
+            rank: hit.rank,
+            title_highlight: escape_headline(&hit.title_highlight),
+            snippet: escape_headline(&hit.snippet),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(SearchResponse {
+        total_results,
+        number_of_page: (total_results as f64 / limit as f64).ceil() as i64,
+        page,
+        results,
+    }))
+}
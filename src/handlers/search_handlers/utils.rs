@@ -0,0 +1,186 @@
+use crate::utils::{errors::ApiError, html::escape_html};
+
+pub const MAX_SEARCH_LENGTH: usize = 256;
+
+/// Turns a search box query into `to_tsquery` syntax. Every term has to
+/// match, `"quoted words"` have to appear next to each other in that order,
+/// `word*` matches any word starting with `word`, `-term` excludes notes
+/// containing the term and `or` between two terms accepts either of them.
+pub fn parse_search_query(raw: &str) -> Result<String, ApiError> {
+    if raw.chars().count() > MAX_SEARCH_LENGTH {
+        return Err(ApiError::BadRequest(format!(
+            "search cannot be longer than {} characters",
+            MAX_SEARCH_LENGTH
+        )));
+    }
+
+    let mut clauses: Vec<Vec<String>> = Vec::new();
+    let mut pending_or: bool = false;
+    let mut chars = raw.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let negated: bool = chars.next_if_eq(&'-').is_some();
+        let phrase: bool = chars.next_if_eq(&'"').is_some();
+
+        let text: String = if phrase {
+            let text: String = chars.by_ref().take_while(|c| *c != '"').collect();
+            text
+        } else {
+            let mut text: String = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                text.push(c);
+            }
+            text
+        };
+
+        if !negated && !phrase && text.eq_ignore_ascii_case("or") {
+            pending_or = !clauses.is_empty();
+            continue;
+        }
+
+        let Some(term) = tsquery_term(&text) else {
+            continue;
+        };
+
+        let term: String = if negated { format!("!{}", term) } else { term };
+
+        match clauses.last_mut() {
+            Some(clause) if pending_or => clause.push(term),
+            _ => clauses.push(vec![term]),
+        }
+
+        pending_or = false;
+    }
+
+    if clauses.is_empty() {
+        return Err(ApiError::BadRequest(String::from(
+            "search must contain at least one word",
+        )));
+    }
+
+    Ok(clauses
+        .into_iter()
+        .map(|clause| match clause.len() {
+            1 => clause.join(""),
+            _ => format!("({})", clause.join(" | ")),
+        })
+        .collect::<Vec<String>>()
+        .join(" & "))
+}
+
+/// The words of a term or phrase joined as a `to_tsquery` phrase. Anything
+/// but letters and digits separates words, so user input can never contain
+/// tsquery operators.
+fn tsquery_term(text: &str) -> Option<String> {
+    let prefix: bool = text.ends_with('*');
+
+    let mut lexemes: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect();
+
+    if prefix {
+        lexemes.last_mut()?.push_str(":*");
+    }
+
+    match lexemes.len() {
+        0 => None,
+        1 => lexemes.pop(),
+        _ => Some(format!("({})", lexemes.join(" <-> "))),
+    }
+}
+
+/// Marks `ts_headline` puts around matches in `SEARCH_HITS_SQL`. They are
+/// control characters stripped from the note beforehand, so they can only
+/// come from `ts_headline` and survive HTML escaping unchanged.
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_STOP: char = '\u{3}';
+
+/// HTML-escapes a `ts_headline` result, then wraps the matches it marked in
+/// `<mark>` tags.
+pub fn escape_headline(headline: &str) -> String {
+    escape_html(headline)
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_STOP, "</mark>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> String {
+        parse_search_query(raw).unwrap()
+    }
+
+    #[test]
+    fn terms_must_all_match_unless_joined_by_or() {
+        assert_eq!(parse("rust notes"), "rust & notes");
+        assert_eq!(parse("rust or go notes"), "(rust | go) & notes");
+        assert_eq!(parse("rust OR go or zig"), "(rust | go | zig)");
+        assert_eq!(parse("or rust or"), "rust");
+    }
+
+    #[test]
+    fn negation_phrases_and_prefixes() {
+        assert_eq!(parse("deploy -draft"), "deploy & !draft");
+        assert_eq!(
+            parse("\"release notes\" deploy"),
+            "(release <-> notes) & deploy"
+        );
+        assert_eq!(parse("-\"old draft\""), "!(old <-> draft)");
+        assert_eq!(parse("depl*"), "depl:*");
+        assert_eq!(parse("\"release not*\""), "(release <-> not:*)");
+        assert_eq!(parse("\"unterminated phrase"), "(unterminated <-> phrase)");
+    }
+
+    #[test]
+    fn tsquery_operators_in_input_are_only_separators() {
+        assert_eq!(parse("a&b|c"), "(a <-> b <-> c)");
+        assert_eq!(parse("!secret"), "secret");
+        assert_eq!(parse("(x) <-> 'y':*"), "x & y:*");
+        assert_eq!(parse("rust & ! |"), "rust");
+    }
+
+    #[test]
+    fn empty_whitespace_and_punctuation_only_searches_are_rejected() {
+        for raw in ["", "   ", "\t\n", "-", "\"\"", "& | !", "or"] {
+            assert!(
+                matches!(parse_search_query(raw), Err(ApiError::BadRequest(_))),
+                "{:?} was accepted",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn searches_longer_than_the_limit_are_rejected() {
+        assert!(parse_search_query(&"a".repeat(MAX_SEARCH_LENGTH)).is_ok());
+        assert!(matches!(
+            parse_search_query(&"a".repeat(MAX_SEARCH_LENGTH + 1)),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn headlines_are_escaped_before_matches_are_marked() {
+        assert_eq!(
+            escape_headline("if a < b && \u{2}c\u{3} > d"),
+            "if a &lt; b &amp;&amp; <mark>c</mark> &gt; d"
+        );
+        assert_eq!(
+            escape_headline("\u{2}<b>&amp;\u{3}"),
+            "<mark>&lt;b&gt;&amp;amp;</mark>"
+        );
+        assert_eq!(
+            escape_headline("<mark>\u{2}x\u{3}</mark>"),
+            "&lt;mark&gt;<mark>x</mark>&lt;/mark&gt;"
+        );
+    }
+}
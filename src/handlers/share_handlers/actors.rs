@@ -0,0 +1,119 @@
+use super::insertables::NewNoteShare;
+use super::messages::*;
+use super::utils::NoteAccess;
+use crate::models::{Note, NoteShare};
+use crate::schema::{note_shares, notes, users};
+use crate::utils::{
+    db::{DbActor, DbResult},
+    errors::ApiError,
+};
+use actix::Handler;
+use diesel::prelude::*;
+
+impl Handler<FetchNoteAccess> for DbActor {
+    type Result = DbResult<(Note, NoteAccess)>;
+
+    fn handle(&mut self, msg: FetchNoteAccess, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let note: Note = notes::table
+            .filter(notes::id.eq(msg.note_id))
+            .filter(notes::deleted_at.is_null())
+            .first::<Note>(&mut connection)?;
+
+        if note.created_by == msg.user_id {
+            return Ok((note, NoteAccess::Owner));
+        }
+
+        let permission: String = note_shares::table
+            .filter(note_shares::note_id.eq(note.id))
+            .filter(note_shares::user_id.eq(msg.user_id))
+            .select(note_shares::permission)
+            .first::<String>(&mut connection)?;
+
+        Ok((note, NoteAccess::from_permission(&permission)))
+    }
+}
+
+impl Handler<FetchNoteShares> for DbActor {
+    type Result = DbResult<Vec<(NoteShare, String, String)>>;
+
+    fn handle(&mut self, msg: FetchNoteShares, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(note_shares::table
+            .inner_join(users::table)
+            .filter(note_shares::note_id.eq(msg.note_id))
+            .order(note_shares::created_on.asc())
+            .select((note_shares::all_columns, users::username, users::email))
+            .get_results::<(NoteShare, String, String)>(&mut connection)?)
+    }
+}
+
+impl Handler<ShareNote> for DbActor {
+    type Result = DbResult<NoteShare>;
+
+    fn handle(&mut self, msg: ShareNote, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        connection.transaction(|connection| {
+            let owner_id: i32 = notes::table
+                .find(msg.note_id)
+                .select(notes::created_by)
+                .first::<i32>(connection)?;
+
+            let user_id: i32 = users::table
+                .filter(users::email.eq(&msg.email))
+                .select(users::id)
+                .first::<i32>(connection)?;
+
+            if user_id == owner_id {
+                return Err(ApiError::BadRequest(String::from(
+                    "a note cannot be shared with its owner",
+                )));
+            }
+
+            Ok(diesel::insert_into(note_shares::table)
+                .values(NewNoteShare {
+                    note_id: msg.note_id,
+                    user_id,
+                    permission: msg.permission.clone(),
+                })
+                .on_conflict((note_shares::note_id, note_shares::user_id))
+                .do_update()
+                .set(note_shares::permission.eq(&msg.permission))
+                .get_result::<NoteShare>(connection)?)
+        })
+    }
+}
+
+impl Handler<UnshareNote> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: UnshareNote, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::delete(
+            note_shares::table
+                .filter(note_shares::note_id.eq(msg.note_id))
+                .filter(note_shares::user_id.eq(msg.user_id)),
+        )
+        .execute(&mut connection)?)
+    }
+}
+
+impl Handler<FetchSharedNotes> for DbActor {
+    type Result = DbResult<Vec<(Note, String, String)>>;
+
+    fn handle(&mut self, msg: FetchSharedNotes, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(note_shares::table
+            .inner_join(notes::table.inner_join(users::table))
+            .filter(note_shares::user_id.eq(msg.user_id))
+            .filter(notes::deleted_at.is_null())
+            .order(notes::updated_on.desc())
+            .select((notes::all_columns, note_shares::permission, users::username))
+            .get_results::<(Note, String, String)>(&mut connection)?)
+    }
+}
@@ -0,0 +1,11 @@
+use crate::schema::note_shares;
+use diesel::Insertable;
+use serde::Serialize;
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=note_shares)]
+pub struct NewNoteShare {
+    pub note_id: i32,
+    pub user_id: i32,
+    pub permission: String,
+}
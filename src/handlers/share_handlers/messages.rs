@@ -0,0 +1,39 @@
+use super::utils::NoteAccess;
+use crate::models::{Note, NoteShare};
+use crate::utils::db::DbResult;
+use actix::Message;
+
+/// A non-trashed note together with the user's access to it.
+#[derive(Message)]
+#[rtype(result = "DbResult<(Note, NoteAccess)>")]
+pub struct FetchNoteAccess {
+    pub user_id: i32,
+    pub note_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<(NoteShare, String, String)>>")]
+pub struct FetchNoteShares {
+    pub note_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<NoteShare>")]
+pub struct ShareNote {
+    pub note_id: i32,
+    pub email: String,
+    pub permission: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct UnshareNote {
+    pub note_id: i32,
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<(Note, String, String)>>")]
+pub struct FetchSharedNotes {
+    pub user_id: i32,
+}
@@ -0,0 +1,5 @@
+pub mod actors;
+pub mod insertables;
+pub mod messages;
+pub mod share_handlers;
+pub mod utils;
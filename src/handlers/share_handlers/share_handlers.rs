@@ -0,0 +1,269 @@
+use super::{
+    messages::*,
+    utils::{authorize_note, NoteAccess},
+};
+use crate::{
+    handlers::{
+        audit_handlers::audit_handlers::record_audit_event,
+        note_handlers::utils::{with_tags, NoteResponse},
+    },
+    models::{Note, NoteShare},
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::Claims,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize)]
+struct CollaboratorResponse {
+    user_id: i32,
+    username: String,
+    email: String,
+    permission: String,
+    created_on: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct SharedNoteResponse {
+    #[serde(flatten)]
+    note: NoteResponse,
+    owner: String,
+    permission: String,
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/shares",
+    responses(
+        (status = 200, description = "Successfully retrieved the users the note is shared with."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Only the owner can list a note's collaborators."),
+        (status = 404, description = "Note not found."),
+        (status = 500, description = "Internal server error: Unable to retrieve collaborators."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn fetch_note_shares(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    authorize_note(&db, claims.id, note_id, NoteAccess::Owner).await?;
+
+    let collaborators: Vec<CollaboratorResponse> = db
+        .send(FetchNoteShares { note_id })
+        .await??
+        .into_iter()
+        .map(|(share, username, email)| CollaboratorResponse {
+            user_id: share.user_id,
+            username,
+            email,
+            permission: share.permission,
+            created_on: share.created_on,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(collaborators))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ShareNoteRequest {
+    #[schema(example = "friend@example.com", required = true)]
+    pub email: String,
+    /// read or edit. Editors can change the note's title, content, image and
+    /// status but cannot delete, re-share, tag or file it.
+    #[schema(example = "edit", required = true)]
+    pub permission: String,
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/shares",
+    request_body = ShareNoteRequest,
+    responses(
+        (status = 200, description = "Note shared. Sharing again with the same user changes their permission."),
+        (status = 400, description = "Invalid permission, or the user is the note's owner."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Only the owner can share a note."),
+        (status = 404, description = "Note or user not found."),
+        (status = 500, description = "Internal server error: Unable to share the note."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("")]
+pub async fn share_note(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    body: Json<ShareNoteRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    if !matches!(body.permission.as_str(), "read" | "edit") {
+        return Err(ApiError::BadRequest(String::from(
+            "permission must be read or edit",
+        )));
+    }
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    authorize_note(&db, claims.id, note_id, NoteAccess::Owner).await?;
+
+    let share: NoteShare = db
+        .send(ShareNote {
+            note_id,
+            email: body.email.trim().to_string(),
+            permission: body.permission.clone(),
+        })
+        .await?
+        .not_found(|| format!("no user with email {}", body.email.trim()))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.shared",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({ "user_id": share.user_id, "permission": share.permission }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(share))
+}
+
+#[utoipa::path(
+    path = "/api/notes/{note_id}/shares/{user_id}",
+    responses(
+        (status = 200, description = "The user no longer has access to the note. Owners can remove anyone, collaborators can remove themselves."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 403, description = "Only the owner can remove other collaborators."),
+        (status = 404, description = "Note not found or not shared with the user."),
+        (status = 500, description = "Internal server error: Unable to unshare the note."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/{user_id}")]
+pub async fn unshare_note(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (note_id, user_id) = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let required: NoteAccess = if user_id == claims.id {
+        NoteAccess::Read
+    } else {
+        NoteAccess::Owner
+    };
+
+    authorize_note(&db, claims.id, note_id, required).await?;
+
+    if db.send(UnshareNote { note_id, user_id }).await?? == 0 {
+        return Err(ApiError::NotFound(format!(
+            "note {} is not shared with user {}",
+            note_id, user_id
+        )));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.unshared",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({ "user_id": user_id }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": format!("note {} is no longer shared with user {}", note_id, user_id)
+    })))
+}
+
+#[utoipa::path(
+    path = "/api/shared-with-me",
+    responses(
+        (status = 200, description = "Successfully retrieved the notes other users shared with the authenticated user, with the owner's username and the granted permission."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 500, description = "Internal server error: Unable to retrieve shared notes."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn fetch_shared_with_me(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let (notes, grants): (Vec<Note>, Vec<(String, String)>) = db
+        .send(FetchSharedNotes { user_id: claims.id })
+        .await??
+        .into_iter()
+        .map(|(note, permission, owner)| (note, (permission, owner)))
+        .unzip();
+
+    let shared_notes: Vec<SharedNoteResponse> = with_tags(&db, notes)
+        .await?
+        .into_iter()
+        .zip(grants)
+        .map(|(note, (permission, owner))| SharedNoteResponse {
+            note,
+            owner,
+            permission,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(shared_notes))
+}
@@ -0,0 +1,62 @@
+use super::messages::FetchNoteAccess;
+use crate::{
+    models::Note,
+    utils::{
+        db::DbActor,
+        errors::{ApiError, ApiResultExt},
+    },
+};
+use actix::Addr;
+use serde::Serialize;
+
+/// What a user may do with a note: owners may do anything, editors may change
+/// its text, image and status, readers may only read it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteAccess {
+    Owner,
+    Edit,
+    Read,
+}
+
+impl NoteAccess {
+    pub fn from_permission(permission: &str) -> NoteAccess {
+        match permission {
+            "edit" => NoteAccess::Edit,
+            _ => NoteAccess::Read,
+        }
+    }
+
+    pub fn can_edit(self) -> bool {
+        matches!(self, NoteAccess::Owner | NoteAccess::Edit)
+    }
+}
+
+/// Loads a note the user owns or that was shared with them, failing with 404
+/// when they have no access and with 403 when their access is below `required`.
+pub async fn authorize_note(
+    db: &Addr<DbActor>,
+    user_id: i32,
+    note_id: i32,
+    required: NoteAccess,
+) -> Result<(Note, NoteAccess), ApiError> {
+    let (note, access) = db
+        .send(FetchNoteAccess { user_id, note_id })
+        .await?
+        .not_found(|| format!("note {} not found", note_id))?;
+
+    let allowed: bool = match required {
+        NoteAccess::Owner => access == NoteAccess::Owner,
+        NoteAccess::Edit => access.can_edit(),
+        NoteAccess::Read => true,
+    };
+
+    if !allowed {
+        return Err(ApiError::Forbidden(match required {
+            NoteAccess::Owner => format!("only the owner of note {} can do that", note_id),
+            _ => format!("note {} is shared with you read-only", note_id),
+        }));
+    }
+
+    Ok((note, access))
+}
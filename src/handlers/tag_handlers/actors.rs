@@ -0,0 +1,209 @@
+use super::insertables::{NewNoteTag, NewTag};
+use super::messages::*;
+use crate::models::Tag;
+use crate::schema::{note_tags, notes, tags};
+use crate::utils::db::{DbActor, DbResult};
+use actix::Handler;
+use diesel::{dsl::count_star, pg::Pg, prelude::*, sql_types::Bool};
+use std::collections::HashMap;
+
+/// Replaces the tags of a note with the given names, creating any of the
+/// user's tags that do not exist yet.
+pub fn replace_note_tags(
+    connection: &mut PgConnection,
+    user_id: i32,
+    note_id: i32,
+    names: &[String],
+) -> QueryResult<usize> {
+    diesel::delete(note_tags::table.filter(note_tags::note_id.eq(note_id))).execute(connection)?;
+
+    if names.is_empty() {
+        return Ok(0);
+    }
+
+    let new_tags: Vec<NewTag> = names
+        .iter()
+        .map(|name| NewTag {
+            user_id,
+            name: name.clone(),
+        })
+        .collect();
+
+    diesel::insert_into(tags::table)
+        .values(new_tags)
+        .on_conflict((tags::user_id, tags::name))
+        .do_nothing()
+        .execute(connection)?;
+
+    let new_note_tags: Vec<NewNoteTag> = tags::table
+        .filter(tags::user_id.eq(user_id))
+        .filter(tags::name.eq_any(names))
+        .select(tags::id)
+        .get_results::<i32>(connection)?
+        .into_iter()
+        .map(|tag_id| NewNoteTag { note_id, tag_id })
+        .collect();
+
+    diesel::insert_into(note_tags::table)
+        .values(new_note_tags)
+        .execute(connection)
+}
+
+/// `id IN (...)` over the notes tagged with any, or with all, of the given
+/// tag names, for filtering boxed `notes` queries. `names` must not contain
+/// duplicates.
+pub fn tagged_with(
+    names: &[String],
+    match_all: bool,
+) -> Box<dyn BoxableExpression<notes::table, Pg, SqlType = Bool>> {
+    let tagged_note_ids = note_tags::table
+        .inner_join(tags::table)
+        .filter(tags::name.eq_any(names.to_vec()));
+
+    if match_all {
+        Box::new(
+            notes::id.eq_any(
+                tagged_note_ids
+                    .group_by(note_tags::note_id)
+                    .having(count_star().eq(names.len() as i64))
+                    .select(note_tags::note_id),
+            ),
+        )
+    } else {
+        Box::new(notes::id.eq_any(tagged_note_ids.select(note_tags::note_id)))
+    }
+}
+
+impl Handler<FetchTags> for DbActor {
+    type Result = DbResult<Vec<(Tag, i64)>>;
+
+    fn handle(&mut self, msg: FetchTags, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let user_tags: Vec<Tag> = tags::table
+            .filter(tags::user_id.eq(msg.user_id))
+            .order(tags::name.asc())
+            .get_results::<Tag>(&mut connection)?;
+
+        let note_counts: HashMap<i32, i64> = note_tags::table
+            .inner_join(tags::table)
+            .inner_join(notes::table)
+            .filter(tags::user_id.eq(msg.user_id))
+            .filter(notes::deleted_at.is_null())
+            .group_by(note_tags::tag_id)
+            .select((note_tags::tag_id, count_star()))
+            .get_results::<(i32, i64)>(&mut connection)?
+            .into_iter()
+            .collect();
+
+        Ok(user_tags
+            .into_iter()
+            .map(|tag| {
+                let note_count: i64 = note_counts.get(&tag.id).copied().unwrap_or_default();
+
+                (tag, note_count)
+            })
+            .collect())
+    }
+}
+
+impl Handler<CreateTag> for DbActor {
+    type Result = DbResult<Tag>;
+
+    fn handle(&mut self, msg: CreateTag, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::insert_into(tags::table)
+            .values(NewTag {
+                user_id: msg.user_id,
+                name: msg.name,
+            })
+            .get_result::<Tag>(&mut connection)?)
+    }
+}
+
+impl Handler<RenameTag> for DbActor {
+    type Result = DbResult<Tag>;
+
+    fn handle(&mut self, msg: RenameTag, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(
+            tags::table
+                .filter(tags::id.eq(msg.tag_id))
+                .filter(tags::user_id.eq(msg.user_id)),
+        )
+        .set(tags::name.eq(msg.name))
+        .get_result::<Tag>(&mut connection)?)
+    }
+}
+
+impl Handler<MergeTags> for DbActor {
+    type Result = DbResult<Tag>;
+
+    fn handle(&mut self, msg: MergeTags, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(connection.transaction(|connection| {
+            let source: Tag = tags::table
+                .filter(tags::id.eq(msg.source_id))
+                .filter(tags::user_id.eq(msg.user_id))
+                .first::<Tag>(connection)?;
+
+            let target: Tag = tags::table
+                .filter(tags::id.eq(msg.target_id))
+                .filter(tags::user_id.eq(msg.user_id))
+                .first::<Tag>(connection)?;
+
+            let new_note_tags: Vec<NewNoteTag> = note_tags::table
+                .filter(note_tags::tag_id.eq(source.id))
+                .select(note_tags::note_id)
+                .get_results::<i32>(connection)?
+                .into_iter()
+                .map(|note_id| NewNoteTag {
+                    note_id,
+                    tag_id: target.id,
+                })
+                .collect();
+
+            diesel::insert_into(note_tags::table)
+                .values(new_note_tags)
+                .on_conflict_do_nothing()
+                .execute(connection)?;
+
+            diesel::delete(tags::table.find(source.id)).execute(connection)?;
+
+            Ok::<Tag, diesel::result::Error>(target)
+        })?)
+    }
+}
+
+impl Handler<DeleteTag> for DbActor {
+    type Result = DbResult<usize>;
+
+    fn handle(&mut self, msg: DeleteTag, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::delete(
+            tags::table
+                .filter(tags::id.eq(msg.tag_id))
+                .filter(tags::user_id.eq(msg.user_id)),
+        )
+        .execute(&mut connection)?)
+    }
+}
+
+impl Handler<FetchNoteTags> for DbActor {
+    type Result = DbResult<Vec<(i32, String)>>;
+
+    fn handle(&mut self, msg: FetchNoteTags, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(note_tags::table
+            .inner_join(tags::table)
+            .filter(note_tags::note_id.eq_any(msg.note_ids))
+            .select((note_tags::note_id, tags::name))
+            .order(tags::name.asc())
+            .get_results::<(i32, String)>(&mut connection)?)
+    }
+}
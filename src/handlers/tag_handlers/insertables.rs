@@ -0,0 +1,17 @@
+use crate::schema::{note_tags, tags};
+use diesel::Insertable;
+use serde::Serialize;
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=tags)]
+pub struct NewTag {
+    pub user_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable, Serialize, Clone)]
+#[diesel(table_name=note_tags)]
+pub struct NewNoteTag {
+    pub note_id: i32,
+    pub tag_id: i32,
+}
@@ -0,0 +1,45 @@
+use crate::models::Tag;
+use crate::utils::db::DbResult;
+use actix::Message;
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<(Tag, i64)>>")]
+pub struct FetchTags {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Tag>")]
+pub struct CreateTag {
+    pub user_id: i32,
+    pub name: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Tag>")]
+pub struct RenameTag {
+    pub user_id: i32,
+    pub tag_id: i32,
+    pub name: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Tag>")]
+pub struct MergeTags {
+    pub user_id: i32,
+    pub source_id: i32,
+    pub target_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<usize>")]
+pub struct DeleteTag {
+    pub user_id: i32,
+    pub tag_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<(i32, String)>>")]
+pub struct FetchNoteTags {
+    pub note_ids: Vec<i32>,
+}
@@ -0,0 +1,357 @@
+use super::messages::*;
+use crate::{
+    handlers::audit_handlers::audit_handlers::record_audit_event,
+    models::Tag,
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::Claims,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get, patch, post,
+    web::{Data, Json, Path},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const MAX_NOTE_TAGS: usize = 20;
+
+#[derive(Serialize)]
+struct TagResponse {
+    id: i32,
+    name: String,
+    note_count: i64,
+    created_on: DateTime<Utc>,
+}
+
+impl From<(Tag, i64)> for TagResponse {
+    fn from((tag, note_count): (Tag, i64)) -> Self {
+        TagResponse {
+            id: tag.id,
+            name: tag.name,
+            note_count,
+            created_on: tag.created_on,
+        }
+    }
+}
+
+/// Tag names are trimmed and lowercased so `Rust` and `rust ` are one tag.
+pub fn normalize_tag_name(name: &str) -> Result<String, ApiError> {
+    let name: String = name.trim().to_lowercase();
+
+    if name.is_empty() || name.chars().count() > 50 || name.contains(',') {
+        return Err(ApiError::BadRequest(String::from(
+            "tag names must be between 1 and 50 characters and must not contain commas",
+        )));
+    }
+
+    Ok(name)
+}
+
+/// Parses a comma-separated list of tag names, dropping blanks and duplicates.
+pub fn parse_tag_names(raw: &str) -> Result<Vec<String>, ApiError> {
+    let mut names: Vec<String> = Vec::new();
+
+    for name in raw.split(',').filter(|name| !name.trim().is_empty()) {
+        let name: String = normalize_tag_name(name)?;
+
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    if names.len() > MAX_NOTE_TAGS {
+        return Err(ApiError::BadRequest(format!(
+            "a note can have at most {} tags",
+            MAX_NOTE_TAGS
+        )));
+    }
+
+    Ok(names)
+}
+
+async fn tag_note_count(db: &Addr<DbActor>, user_id: i32, tag_id: i32) -> Result<i64, ApiError> {
+    Ok(db
+        .send(FetchTags { user_id })
+        .await??
+        .into_iter()
+        .find(|(tag, _)| tag.id == tag_id)
+        .map(|(_, note_count)| note_count)
+        .unwrap_or_default())
+}
+
+#[utoipa::path(
+    path = "/api/tags",
+    responses(
+        (status = 200, description = "Successfully retrieved the authenticated user's tags with the number of notes using each."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 500, description = "Internal server error: Unable to retrieve tags."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn fetch_tags(state: Data<AppState>, req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let tags: Vec<TagResponse> = db
+        .send(FetchTags { user_id: claims.id })
+        .await??
+        .into_iter()
+        .map(TagResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TagRequest {
+    #[schema(example = "rust", required = true)]
+    pub name: String,
+}
+
+#[utoipa::path(
+    path = "/api/tags",
+    request_body = TagRequest,
+    responses(
+        (status = 201, description = "Tag created."),
+        (status = 400, description = "Invalid tag name."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 409, description = "A tag with that name already exists."),
+        (status = 500, description = "Internal server error: Unable to create the tag."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("")]
+pub async fn create_tag(
+    state: Data<AppState>,
+    req: HttpRequest,
+    body: Json<TagRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let name: String = normalize_tag_name(&body.name)?;
+
+    let tag: Tag = db
+        .send(CreateTag {
+            user_id: claims.id,
+            name,
+        })
+        .await?
+        .conflict(|| String::from("tag already exists"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "tag.created",
+        Some(("tag", tag.id.to_string())),
+        serde_json::json!({ "name": tag.name }),
+    )
+    .await;
+
+    Ok(HttpResponse::Created().json(TagResponse::from((tag, 0))))
+}
+
+#[utoipa::path(
+    path = "/api/tags/{tag_id}",
+    request_body = TagRequest,
+    responses(
+        (status = 200, description = "Tag renamed. Notes keep the tag under its new name."),
+        (status = 400, description = "Invalid tag name."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Tag not found."),
+        (status = 409, description = "Another tag already has that name. Merge the tags instead."),
+        (status = 500, description = "Internal server error: Unable to rename the tag."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[patch("/{tag_id}")]
+pub async fn rename_tag(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    body: Json<TagRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let tag_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let name: String = normalize_tag_name(&body.name)?;
+
+    let tag: Tag = db
+        .send(RenameTag {
+            user_id: claims.id,
+            tag_id,
+            name,
+        })
+        .await?
+        .not_found(|| format!("tag {} not found", tag_id))
+        .conflict(|| String::from("a tag with that name already exists, merge the tags instead"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "tag.renamed",
+        Some(("tag", tag.id.to_string())),
+        serde_json::json!({ "name": tag.name }),
+    )
+    .await;
+
+    let note_count: i64 = tag_note_count(&db, claims.id, tag.id).await?;
+
+    Ok(HttpResponse::Ok().json(TagResponse::from((tag, note_count))))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MergeTagRequest {
+    #[schema(example = 2, required = true)]
+    pub into_tag_id: i32,
+}
+
+#[utoipa::path(
+    path = "/api/tags/{tag_id}/merge",
+    request_body = MergeTagRequest,
+    responses(
+        (status = 200, description = "Every note tagged with the tag is now tagged with the target tag instead, and the tag is deleted. Returns the target tag."),
+        (status = 400, description = "A tag cannot be merged into itself."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Tag or target tag not found."),
+        (status = 500, description = "Internal server error: Unable to merge the tags."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/{tag_id}/merge")]
+pub async fn merge_tag(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+    body: Json<MergeTagRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let tag_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    if tag_id == body.into_tag_id {
+        return Err(ApiError::BadRequest(String::from(
+            "a tag cannot be merged into itself",
+        )));
+    }
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let tag: Tag = db
+        .send(MergeTags {
+            user_id: claims.id,
+            source_id: tag_id,
+            target_id: body.into_tag_id,
+        })
+        .await?
+        .not_found(|| String::from("tag not found"))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "tag.merged",
+        Some(("tag", tag_id.to_string())),
+        serde_json::json!({ "into_tag_id": tag.id }),
+    )
+    .await;
+
+    let note_count: i64 = tag_note_count(&db, claims.id, tag.id).await?;
+
+    Ok(HttpResponse::Ok().json(TagResponse::from((tag, note_count))))
+}
+
+#[utoipa::path(
+    path = "/api/tags/{tag_id}",
+    responses(
+        (status = 200, description = "Tag deleted and removed from every note."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Tag not found."),
+        (status = 500, description = "Internal server error: Unable to delete the tag."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/{tag_id}")]
+pub async fn delete_tag(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let tag_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    if db
+        .send(DeleteTag {
+            user_id: claims.id,
+            tag_id,
+        })
+        .await??
+        == 0
+    {
+        return Err(ApiError::NotFound(format!("tag {} not found", tag_id)));
+    }
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "tag.deleted",
+        Some(("tag", tag_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(
+        HttpResponse::Ok()
+            .json(serde_json::json!({ "message": format!("deleted tag {}", tag_id) })),
+    )
+}
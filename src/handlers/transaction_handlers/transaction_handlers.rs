@@ -1,8 +1,21 @@
-use crate::utils::{self, jwt::Claims};
-use actix_web::{get, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use crate::utils::{db::AppState, errors::ApiError, jwt::Claims};
+use actix_web::{
+    get,
+    web::{self, Data},
+    HttpMessage, HttpRequest, HttpResponse,
+};
 use reqwest::Client;
 use serde::Deserialize;
 
+fn moonpay_api_key(state: &AppState) -> Result<String, ApiError> {
+    state
+        .config
+        .moonpay
+        .api_key
+        .clone()
+        .ok_or_else(|| ApiError::ServiceUnavailable(String::from("moonpay is not configured")))
+}
+
 #[derive(Deserialize)]
 struct BuyListsQuery {
     moonpay_token: String,
@@ -16,21 +29,23 @@ struct BuyListsQuery {
     responses(
         (status = 200, description = "Handles buy lists queries and fetches the user's transactions."),
         (status = 401, description = "Unauthorized access, missing or invalid token."),
-        (status = 500, description = "Failed to fetch transactions from Moonpay."),
+        (status = 502, description = "Failed to fetch transactions from Moonpay."),
     ),
     security(
         ("bearer_auth" = [])
     )
 )]
 #[get("/buy/lists")]
-pub async fn get_buy_lists(req: HttpRequest, query: web::Query<BuyListsQuery>) -> impl Responder {
+pub async fn get_buy_lists(
+    req: HttpRequest,
+    query: web::Query<BuyListsQuery>,
+) -> Result<HttpResponse, ApiError> {
     let BuyListsQuery { moonpay_token } = query.into_inner();
 
     let claims: Claims = match req.extensions().get::<Claims>() {
         Some(claims) => claims.clone(),
         None => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({ "message": "unauthorized access" }));
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
         }
     };
 
@@ -41,28 +56,20 @@ pub async fn get_buy_lists(req: HttpRequest, query: web::Query<BuyListsQuery>) -
 
     let client: Client = Client::new();
 
-    match client.get(url).bearer_auth(moonpay_token).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<serde_json::Value>().await {
-                    Ok(json) => HttpResponse::Ok().json(json),
-                    Err(_) => HttpResponse::InternalServerError()
-                        .json(serde_json::json!({ "error": "failed to parse response" })),
-                }
-            } else {
-                let status_code: reqwest::StatusCode = response.status();
-
-                HttpResponse::BadRequest().json(serde_json::json!({
-                    "error": "failed to fetch transaction info",
-                    "status": status_code.as_u16(),
-                }))
-            }
-        }
-        Err(error) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "request error",
-            "details": error.to_string(),
-        })),
+    let response = client.get(url).bearer_auth(moonpay_token).send().await?;
+
+    if !response.status().is_success() {
+        let status_code: reqwest::StatusCode = response.status();
+
+        return Err(ApiError::BadRequest(format!(
+            "failed to fetch transaction info: moonpay responded with {}",
+            status_code.as_u16()
+        )));
     }
+
+    let json: serde_json::Value = response.json::<serde_json::Value>().await?;
+
+    Ok(HttpResponse::Ok().json(json))
 }
 
 #[derive(Deserialize)]
@@ -82,18 +89,22 @@ struct BuyQuoteInfo {
     responses(
         (status = 200, description = "Handles buy quote queries and returns the quote."),
         (status = 400, description = "Invalid input parameters."),
-        (status = 500, description = "Failed to fetch buy quote from Moonpay."),
+        (status = 502, description = "Failed to fetch buy quote from Moonpay."),
+        (status = 503, description = "No Moonpay API key is configured."),
     )
 )]
 #[get("/buy/quote")]
-pub async fn get_buy_quote(query: web::Query<BuyQuoteInfo>) -> impl Responder {
+pub async fn get_buy_quote(
+    state: Data<AppState>,
+    query: web::Query<BuyQuoteInfo>,
+) -> Result<HttpResponse, ApiError> {
     let BuyQuoteInfo {
         crypto_code,
         fiat_code,
         crypto_amount,
     } = query.into_inner();
 
-    let api_key: String = (*utils::constants::MOONPAY_API_KEY).clone();
+    let api_key: String = moonpay_api_key(state.as_ref())?;
 
     let url: String = format!(
         "https://api.moonpay.com/v3/currencies/{}/buy_quote?quoteCurrencyAmount={}&baseCurrencyCode={}&apiKey={}",
@@ -102,28 +113,20 @@ pub async fn get_buy_quote(query: web::Query<BuyQuoteInfo>) -> impl Responder {
 
     let client: Client = Client::new();
 
-    match client.get(url).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<serde_json::Value>().await {
-                    Ok(json) => HttpResponse::Ok().json(json),
-                    Err(_) => HttpResponse::InternalServerError()
-                        .json(serde_json::json!({ "error": "failed to parse response" })),
-                }
-            } else {
-                let status_code: reqwest::StatusCode = response.status();
-
-                HttpResponse::BadRequest().json(serde_json::json!({
-                    "error": "failed to fetch buy quote info",
-                    "status": status_code.as_u16(),
-                }))
-            }
-        }
-        Err(error) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "request error",
-            "details": error.to_string(),
-        })),
+    let response = client.get(url).send().await?;
+
+    if !response.status().is_success() {
+        let status_code: reqwest::StatusCode = response.status();
+
+        return Err(ApiError::BadRequest(format!(
+            "failed to fetch buy quote info: moonpay responded with {}",
+            status_code.as_u16()
+        )));
     }
+
+    let json: serde_json::Value = response.json::<serde_json::Value>().await?;
+
+    Ok(HttpResponse::Ok().json(json))
 }
 
 #[derive(Deserialize)]
@@ -139,14 +142,18 @@ struct BuyInfoQuery {
     responses(
         (status = 200, description = "Returns the details of a specific buy transaction."),
         (status = 404, description = "Transaction not found."),
-        (status = 500, description = "Failed to fetch transaction details from Moonpay."),
+        (status = 502, description = "Failed to fetch transaction details from Moonpay."),
+        (status = 503, description = "No Moonpay API key is configured."),
     )
 )]
 #[get("/buy/info")]
-pub async fn get_buy_information(query: web::Query<BuyInfoQuery>) -> impl Responder {
+pub async fn get_buy_information(
+    state: Data<AppState>,
+    query: web::Query<BuyInfoQuery>,
+) -> Result<HttpResponse, ApiError> {
     let BuyInfoQuery { transaction_id } = query.into_inner();
 
-    let api_key: String = (*utils::constants::MOONPAY_API_KEY).clone();
+    let api_key: String = moonpay_api_key(state.as_ref())?;
 
     let url: String = format!(
         "https://api.moonpay.com/v1/transactions/{}?apiKey={}",
@@ -155,33 +162,25 @@ pub async fn get_buy_information(query: web::Query<BuyInfoQuery>) -> impl Respon
 
     let client: Client = Client::new();
 
-    match client.get(url).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<serde_json::Value>().await {
-                    Ok(json) => HttpResponse::Ok().json(json),
-                    Err(_) => HttpResponse::InternalServerError()
-                        .json(serde_json::json!({ "error": "failed to parse response" })),
-                }
-            } else {
-                let status_code: reqwest::StatusCode = response.status();
-                let error_body = match response.text().await {
-                    Ok(body) => body,
-                    Err(_) => "failed to read error response body".to_string(),
-                };
-
-                HttpResponse::BadRequest().json(serde_json::json!({
-                    "error": "failed to fetch buy info",
-                    "status": status_code.as_u16(),
-                    "details": error_body,
-                }))
-            }
-        }
-        Err(error) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "Request error",
-            "details": error.to_string(),
-        })),
+    let response = client.get(url).send().await?;
+
+    if !response.status().is_success() {
+        let status_code: reqwest::StatusCode = response.status();
+        let error_body: String = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "failed to read error response body".to_string());
+
+        return Err(ApiError::BadRequest(format!(
+            "failed to fetch buy info: moonpay responded with {}: {}",
+            status_code.as_u16(),
+            error_body
+        )));
     }
+
+    let json: serde_json::Value = response.json::<serde_json::Value>().await?;
+
+    Ok(HttpResponse::Ok().json(json))
 }
 
 #[derive(Deserialize)]
@@ -199,17 +198,21 @@ struct SwapInfo {
     responses(
         (status = 200, description = "Returns details of a swap transaction"),
         (status = 404, description = "Swap transaction not found"),
-        (status = 500, description = "Failed to fetch swap transaction details from Moonpay"),
+        (status = 502, description = "Failed to fetch swap transaction details from Moonpay"),
+        (status = 503, description = "No Moonpay API key is configured"),
     )
 )]
 #[get("/swap/info")]
-pub async fn get_swap_transaction(query: web::Query<SwapInfo>) -> impl Responder {
+pub async fn get_swap_transaction(
+    state: Data<AppState>,
+    query: web::Query<SwapInfo>,
+) -> Result<HttpResponse, ApiError> {
     let SwapInfo {
         moonpay_token,
         transaction_id,
     } = query.into_inner();
 
-    let api_key: String = (*utils::constants::MOONPAY_API_KEY).clone();
+    let api_key: String = moonpay_api_key(state.as_ref())?;
 
     let url: String = format!(
         "https://api.moonpay.com/v4/swap/transaction/{}?apiKey={}",
@@ -218,31 +221,23 @@ pub async fn get_swap_transaction(query: web::Query<SwapInfo>) -> impl Responder
 
     let client: Client = Client::new();
 
-    match client.get(url).bearer_auth(moonpay_token).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<serde_json::Value>().await {
-                    Ok(json) => HttpResponse::Ok().json(json),
-                    Err(_) => HttpResponse::InternalServerError()
-                        .json(serde_json::json!({ "error": "failed to parse response" })),
-                }
-            } else {
-                let status_code: reqwest::StatusCode = response.status();
-                let error_body = match response.text().await {
-                    Ok(body) => body,
-                    Err(_) => "failed to read error response body".to_string(),
-                };
-
-                HttpResponse::BadRequest().json(serde_json::json!({
-                    "error": "failed to fetch buy quote info",
-                    "status": status_code.as_u16(),
-                    "details": error_body,
-                }))
-            }
-        }
-        Err(error) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "error": "request error",
-            "details": error.to_string(),
-        })),
+    let response = client.get(url).bearer_auth(moonpay_token).send().await?;
+
+    if !response.status().is_success() {
+        let status_code: reqwest::StatusCode = response.status();
+        let error_body: String = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "failed to read error response body".to_string());
+
+        return Err(ApiError::BadRequest(format!(
+            "failed to fetch buy quote info: moonpay responded with {}: {}",
+            status_code.as_u16(),
+            error_body
+        )));
     }
+
+    let json: serde_json::Value = response.json::<serde_json::Value>().await?;
+
+    Ok(HttpResponse::Ok().json(json))
 }
@@ -0,0 +1,70 @@
+use super::messages::*;
+use crate::models::Note;
+use crate::schema::notes;
+use crate::utils::db::{DbActor, DbResult};
+use actix::Handler;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+impl Handler<FetchTrashedNotes> for DbActor {
+    type Result = DbResult<Vec<Note>>;
+
+    fn handle(&mut self, msg: FetchTrashedNotes, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(notes::table
+            .filter(notes::created_by.eq(msg.user_id))
+            .filter(notes::deleted_at.is_not_null())
+            .order(notes::deleted_at.desc())
+            .get_results::<Note>(&mut connection)?)
+    }
+}
+
+impl Handler<RestoreNote> for DbActor {
+    type Result = DbResult<Note>;
+
+    fn handle(&mut self, msg: RestoreNote, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(diesel::update(
+            notes::table
+                .filter(notes::id.eq(msg.note_id))
+                .filter(notes::created_by.eq(msg.user_id))
+                .filter(notes::deleted_at.is_not_null()),
+        )
+        .set(notes::deleted_at.eq(None::<DateTime<Utc>>))
+        .get_result::<Note>(&mut connection)?)
+    }
+}
+
+impl Handler<PurgeUserNotes> for DbActor {
+    type Result = DbResult<Vec<Note>>;
+
+    fn handle(&mut self, msg: PurgeUserNotes, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        let mut query = diesel::delete(notes::table)
+            .filter(notes::created_by.eq(msg.user_id))
+            .filter(notes::deleted_at.is_not_null())
+            .into_boxed();
+
+        if let Some(note_id) = msg.note_id {
+            query = query.filter(notes::id.eq(note_id));
+        }
+
+        Ok(query.get_results::<Note>(&mut connection)?)
+    }
+}
+
+impl Handler<PurgeTrash> for DbActor {
+    type Result = DbResult<Vec<Note>>;
+
+    fn handle(&mut self, msg: PurgeTrash, _ctx: &mut Self::Context) -> Self::Result {
+        let mut connection = self.0.get()?;
+
+        Ok(
+            diesel::delete(notes::table.filter(notes::deleted_at.lt(msg.trashed_before)))
+                .get_results::<Note>(&mut connection)?,
+        )
+    }
+}
@@ -0,0 +1,32 @@
+use crate::models::Note;
+use crate::utils::db::DbResult;
+use actix::Message;
+use chrono::{DateTime, Utc};
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<Note>>")]
+pub struct FetchTrashedNotes {
+    pub user_id: i32,
+}
+
+#[derive(Message)]
+#[rtype(result = "DbResult<Note>")]
+pub struct RestoreNote {
+    pub user_id: i32,
+    pub note_id: i32,
+}
+
+/// Permanently deletes trashed notes of a user, one note or all of them.
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<Note>>")]
+pub struct PurgeUserNotes {
+    pub user_id: i32,
+    pub note_id: Option<i32>,
+}
+
+/// Permanently deletes every note trashed before the cutoff.
+#[derive(Message)]
+#[rtype(result = "DbResult<Vec<Note>>")]
+pub struct PurgeTrash {
+    pub trashed_before: DateTime<Utc>,
+}
@@ -0,0 +1,4 @@
+pub mod actors;
+pub mod messages;
+pub mod trash_handlers;
+pub mod utils;
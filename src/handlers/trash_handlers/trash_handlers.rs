@@ -0,0 +1,229 @@
+use super::{messages::*, utils::delete_note_images};
+use crate::{
+    handlers::{
+        audit_handlers::audit_handlers::record_audit_event,
+        note_handlers::utils::{with_tags, NoteResponse},
+    },
+    models::Note,
+    utils::{
+        db::{AppState, DbActor},
+        errors::{ApiError, ApiResultExt},
+        jwt::Claims,
+    },
+};
+use actix::Addr;
+use actix_web::{
+    delete, get, post,
+    web::{Data, Path},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct TrashedNoteResponse {
+    #[serde(flatten)]
+    note: NoteResponse,
+    purge_on: Option<DateTime<Utc>>,
+}
+
+#[utoipa::path(
+    path = "/api/trash",
+    responses(
+        (status = 200, description = "Successfully retrieved the authenticated user's trashed notes, most recently deleted first, with the time each will be purged."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 500, description = "Internal server error: Unable to retrieve the trash."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[get("")]
+pub async fn fetch_trash(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+    let retention: Duration = Duration::days(state.config.trash.retention_days as i64);
+
+    let notes: Vec<Note> = db.send(FetchTrashedNotes { user_id: claims.id }).await??;
+
+    let trash: Vec<TrashedNoteResponse> = with_tags(&db, notes)
+        .await?
+        .into_iter()
+        .map(|note| TrashedNoteResponse {
+            purge_on: note
+                .note
+                .deleted_at
+                .map(|deleted_at| deleted_at + retention),
+            note,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(trash))
+}
+
+#[utoipa::path(
+    path = "/api/trash/{note_id}/restore",
+    responses(
+        (status = 200, description = "Note restored from the trash. A note whose notebook was deleted is restored outside any notebook."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Note not found in the trash."),
+        (status = 500, description = "Internal server error: Unable to restore the note."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[post("/{note_id}/restore")]
+pub async fn restore_note(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let note: Note = db
+        .send(RestoreNote {
+            user_id: claims.id,
+            note_id,
+        })
+        .await?
+        .not_found(|| format!("note {} not found in the trash", note_id))?;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.restored",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(with_tags(&db, vec![note]).await?.pop()))
+}
+
+#[utoipa::path(
+    path = "/api/trash/{note_id}",
+    responses(
+        (status = 200, description = "Note and its image permanently deleted."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 404, description = "Note not found in the trash."),
+        (status = 500, description = "Internal server error: Unable to delete the note."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("/{note_id}")]
+pub async fn purge_note(
+    state: Data<AppState>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, ApiError> {
+    let note_id: i32 = path.into_inner();
+
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let purged_notes: Vec<Note> = db
+        .send(PurgeUserNotes {
+            user_id: claims.id,
+            note_id: Some(note_id),
+        })
+        .await??;
+
+    if purged_notes.is_empty() {
+        return Err(ApiError::NotFound(format!(
+            "note {} not found in the trash",
+            note_id
+        )));
+    }
+
+    delete_note_images(&state.config.cloudinary, &purged_notes).await;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "note.purged",
+        Some(("note", note_id.to_string())),
+        serde_json::json!({}),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok()
+        .json(serde_json::json!({ "message": format!("permanently deleted note {}", note_id) })))
+}
+
+#[utoipa::path(
+    path = "/api/trash",
+    responses(
+        (status = 200, description = "Every note in the trash and its image permanently deleted."),
+        (status = 401, description = "Unauthorized: Bearer authentication required."),
+        (status = 500, description = "Internal server error: Unable to empty the trash."),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+#[delete("")]
+pub async fn empty_trash(
+    state: Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let claims: Claims = match req.extensions().get::<Claims>() {
+        Some(claims) => claims.clone(),
+        None => {
+            return Err(ApiError::Unauthorized(String::from("unauthorized access")));
+        }
+    };
+
+    let db: Addr<DbActor> = state.as_ref().db.clone();
+
+    let purged_notes: Vec<Note> = db
+        .send(PurgeUserNotes {
+            user_id: claims.id,
+            note_id: None,
+        })
+        .await??;
+
+    delete_note_images(&state.config.cloudinary, &purged_notes).await;
+
+    record_audit_event(
+        &db,
+        &req,
+        Some(claims.id),
+        "trash.emptied",
+        None,
+        serde_json::json!({ "purged_notes": purged_notes.len() }),
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(
+        serde_json::json!({ "message": format!("permanently deleted {} notes", purged_notes.len()) }),
+    ))
+}
@@ -0,0 +1,57 @@
+use super::messages::PurgeTrash;
+use crate::{
+    handlers::note_handlers::utils::delete_image_from_cloudinary,
+    models::Note,
+    utils::{
+        config::{CloudinaryConfig, Config},
+        db::DbActor,
+    },
+};
+use actix::Addr;
+use actix_web::rt::time::interval;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+/// Deletes the uploaded images of purged notes. Images are left in place when
+/// no Cloudinary API credentials are configured.
+pub async fn delete_note_images(cloudinary: &CloudinaryConfig, purged_notes: &[Note]) {
+    let Some((cloud_name, api_key, api_secret)) = cloudinary.admin_credentials() else {
+        return;
+    };
+
+    for note in purged_notes {
+        if let Some(image_url) = note.image_url.as_deref() {
+            if let Err(err) =
+                delete_image_from_cloudinary(image_url, cloud_name, api_key, api_secret).await
+            {
+                eprintln!("Trash: unable to delete image of note {}: {}", note.id, err);
+            }
+        }
+    }
+}
+
+/// Periodically purges notes that have been in the trash longer than the
+/// configured retention period.
+pub async fn purge_trash_periodically(db: Addr<DbActor>, config: Arc<Config>) {
+    let mut ticker = interval(std::time::Duration::from_secs(
+        config.trash.purge_interval_minutes * 60,
+    ));
+
+    loop {
+        ticker.tick().await;
+
+        let trashed_before = Utc::now() - Duration::days(config.trash.retention_days as i64);
+
+        match db.send(PurgeTrash { trashed_before }).await {
+            Ok(Ok(purged_notes)) => {
+                if !purged_notes.is_empty() {
+                    println!("Trash: purged {} notes", purged_notes.len());
+                }
+
+                delete_note_images(&config.cloudinary, &purged_notes).await;
+            }
+            Ok(Err(err)) => eprintln!("Trash: unable to purge notes: {}", err),
+            Err(err) => eprintln!("Trash: unable to purge notes: {}", err),
+        }
+    }
+}
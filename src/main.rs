@@ -1,12 +1,20 @@
 use actix::{Addr, SyncArbiter};
-use actix_web::{web::Data, App, HttpServer};
+use actix_web::{
+    web::{Data, JsonConfig, PathConfig, QueryConfig},
+    App, HttpServer,
+};
 use diesel::{
     r2d2::{ConnectionManager, Pool},
     PgConnection,
 };
+use std::sync::Arc;
 use utils::{
+    config::{Config, ConfigError},
     db::{get_pool, AppState, DbActor},
-    jwt::Claims,
+    errors::ApiError,
+    jwt::{load_jwt_keys, Claims, JwtKeys},
+    mailer::{build_mailer, Mailer},
+    password_policy::PasswordViolation,
 };
 mod handlers;
 mod middlewares;
@@ -14,20 +22,35 @@ mod models;
 mod routes;
 use actix_governor::{Governor, GovernorConfigBuilder};
 use handlers::{
-    auth_handlers::{auth_handlers::*, messages::*, two_fa_handlers::*, user_handlers::*},
+    audit_handlers::audit_handlers::*,
+    auth_handlers::{
+        admin_user_handlers::*, api_key_handlers::*, auth_handlers::*, lockout_handlers::*,
+        messages::*, oidc_handlers::*, password_handlers::*, session_handlers::*,
+        token_handlers::*, two_fa_handlers::*, user_handlers::*, verification_handlers::*,
+    },
+    link_handlers::{link_handlers::*, public_handlers::*},
     note_handlers::note_handlers::*,
+    notebook_handlers::notebook_handlers::*,
+    revision_handlers::revision_handlers::*,
+    role_handlers::role_handlers::*,
+    search_handlers::search_handlers::*,
+    share_handlers::share_handlers::*,
+    tag_handlers::tag_handlers::*,
     test_handlers::test_handlers::*,
     transaction_handlers::transaction_handlers::*,
+    trash_handlers::{trash_handlers::*, utils::purge_trash_periodically},
 };
 use routes::{
-    admin_routes::admin_routes, auth_routes::auth_routes, note_routes::note_routes,
-    test_routes::test_routes, transaction_routes::transaction_routes,
+    admin_routes::admin_routes, auth_routes::auth_routes, link_routes::link_routes,
+    note_routes::note_routes, notebook_routes::notebook_routes, revision_routes::revision_routes,
+    search_routes::search_routes, share_routes::share_routes, tag_routes::tag_routes,
+    test_routes::test_routes, transaction_routes::transaction_routes, trash_routes::trash_routes,
 };
 mod schema;
 mod utils;
 use utoipa::{
     openapi::{
-        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
         Components, OpenApi as OpenApiType,
     },
     Modify, OpenApi as OpenApiDerive,
@@ -36,12 +59,36 @@ use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let address: String = (*utils::constants::ADDRESS).clone();
-    let port: u16 = (*utils::constants::PORT).clone();
+    let config: Arc<Config> = match Config::load() {
+        Ok(config) => Arc::new(config),
+        Err(err) => {
+            eprint!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let address: String = config.server.address.clone();
+    let port: u16 = config.server.port;
 
-    let db_url: String = (*utils::constants::DATABASE_URL).clone();
-    let pool: Pool<ConnectionManager<PgConnection>> = get_pool(&db_url);
+    let jwt_keys: Arc<JwtKeys> = match load_jwt_keys(&config.jwt, &config.auth.secret) {
+        Ok(jwt_keys) => Arc::new(jwt_keys),
+        Err(err) => {
+            eprint!("{}", ConfigError(vec![err]));
+            std::process::exit(1);
+        }
+    };
+
+    let pool: Pool<ConnectionManager<PgConnection>> = get_pool(&config.database.url);
     let db_addr: Addr<DbActor> = SyncArbiter::start(5, move || DbActor(pool.clone()));
+    let mailer: Arc<dyn Mailer> = match build_mailer(&config.mail) {
+        Ok(mailer) => mailer,
+        Err(err) => {
+            eprint!("{}", ConfigError(vec![err]));
+            std::process::exit(1);
+        }
+    };
+
+    actix_web::rt::spawn(purge_trash_periodically(db_addr.clone(), config.clone()));
 
     println!("Server running at http://{}:{}", address, port);
 
@@ -50,6 +97,9 @@ async fn main() -> std::io::Result<()> {
     paths(
         login_user,
         register_user,
+        refresh_token_handler,
+        forgot_password,
+        reset_password,
         hello,
         home,
         index,
@@ -59,11 +109,41 @@ async fn main() -> std::io::Result<()> {
         fetch_user_notes,
         update_user_note,
         delete_user_note,
+        fetch_tags,
+        create_tag,
+        rename_tag,
+        merge_tag,
+        delete_tag,
+        fetch_notebooks,
+        create_notebook,
+        rename_notebook,
+        move_notebook,
+        delete_notebook,
+        fetch_revisions,
+        diff_revisions,
+        fetch_note_revision,
+        restore_note_revision,
+        fetch_trash,
+        restore_note,
+        purge_note,
+        empty_trash,
+        fetch_note_shares,
+        share_note,
+        unshare_note,
+        fetch_shared_with_me,
+        fetch_note_links,
+        create_note_link,
+        revoke_note_link,
+        view_public_note,
+        unlock_public_note,
+        search_notes,
         generate_otp_handler,
         verify_otp_handler,
         logout_user,
         token_validate_handler,
         disable_otp_handler,
+        recovery_codes_handler,
+        regenerate_recovery_codes_handler,
         get_user,
         get_buy_lists,
         get_buy_quote,
@@ -71,17 +151,63 @@ async fn main() -> std::io::Result<()> {
         get_swap_transaction,
         update_password,
         delete_user,
+        fetch_sessions,
+        revoke_session,
+        revoke_all_sessions,
+        verify_email,
+        resend_verification_email,
+        resend_verification_email_by_address,
+        create_api_key,
+        fetch_api_keys,
+        revoke_api_key,
+        jwks_handler,
+        oidc_login,
+        oidc_callback,
+        start_identity_link,
+        fetch_identities,
+        unlink_identity,
+        fetch_roles,
+        create_role,
+        update_role_permissions,
+        fetch_permissions,
+        assign_user_role,
+        suspend_user,
+        reactivate_user,
+        force_password_reset,
+        reset_user_otp,
+        admin_delete_user,
+        fetch_login_attempts,
+        unlock_user,
+        fetch_audit_events,
     ),
     components(
         schemas(
             Claims,
             RegisterUserRequest,
             LoginUserRequest,
+            RefreshTokenRequest,
+            ForgotPasswordRequest,
+            ResendVerificationRequest,
+            ResetPasswordRequest,
+            CreateApiKeyRequest,
             CreateNoteRequest,
             UpdateNoteRequest,
+            TagRequest,
+            MergeTagRequest,
+            CreateNotebookRequest,
+            RenameNotebookRequest,
+            MoveNotebookRequest,
+            ShareNoteRequest,
+            CreateNoteLinkRequest,
+            PublicNotePasswordForm,
             VerifyOTPRequest,
             ValidateOTPRequest,
+            DisableOTPRequest,
             UpdatePasswordRequest,
+            CreateRoleRequest,
+            UpdateRolePermissionsRequest,
+            AssignRoleRequest,
+            PasswordViolation,
         )
     ),
     modifiers(&SecurityAddon)
@@ -103,6 +229,11 @@ async fn main() -> std::io::Result<()> {
                 ),
             );
 
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+            );
+
             components.add_security_scheme(
                 "basic_auth",
                 SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Basic).build()),
@@ -122,13 +253,35 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(Data::new(AppState {
                 db: db_addr.clone(),
+                mailer: mailer.clone(),
+                config: config.clone(),
+                jwt_keys: jwt_keys.clone(),
             }))
+            .app_data(
+                JsonConfig::default()
+                    .error_handler(|err, _| ApiError::BadRequest(err.to_string()).into()),
+            )
+            .app_data(
+                QueryConfig::default()
+                    .error_handler(|err, _| ApiError::BadRequest(err.to_string()).into()),
+            )
+            .app_data(
+                PathConfig::default()
+                    .error_handler(|err, _| ApiError::BadRequest(err.to_string()).into()),
+            )
             .wrap(Governor::new(&governor_conf))
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}")
                     .url("/api-docs/openapi.json", open_api.clone()),
             )
             .configure(test_routes::configuration)
+            .configure(tag_routes::configuration)
+            .configure(notebook_routes::configuration)
+            .configure(revision_routes::configuration)
+            .configure(trash_routes::configuration)
+            .configure(share_routes::configuration)
+            .configure(link_routes::configuration)
+            .configure(search_routes::configuration)
             .configure(note_routes::configuration)
             .configure(auth_routes::configuration)
             .configure(transaction_routes::configuration)
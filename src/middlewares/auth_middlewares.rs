@@ -1,136 +1,311 @@
-use crate::utils::jwt::{decode_jwt, Claims};
+use crate::{
+    handlers::{
+        auth_handlers::{
+            api_key_handlers::API_KEY_PREFIX,
+            messages::{AuthenticateApiKey, TouchSession},
+        },
+        role_handlers::messages::FetchUserPermissions,
+    },
+    utils::{
+        config::AuthConfig,
+        db::{AppState, DbActor},
+        errors::ApiError,
+        jwt::Claims,
+        tokens::hash_token,
+    },
+};
+use actix::Addr;
 use actix_web::{
     body::MessageBody,
     dev::{ServiceRequest, ServiceResponse},
-    error::{ErrorForbidden, ErrorInternalServerError, ErrorUnauthorized},
-    http::header::{HeaderValue, AUTHORIZATION},
-    Error, HttpMessage,
+    http::{header::AUTHORIZATION, Method},
+    web::Data,
+    Error, HttpMessage, HttpRequest,
 };
-use actix_web_lab::middleware::Next;
-use jsonwebtoken::{errors::ErrorKind, TokenData};
+use actix_web_lab::middleware::{from_fn, MiddlewareFn, Next};
+use chrono::Utc;
+use jsonwebtoken::TokenData;
+use std::{future::Future, pin::Pin};
 
-pub async fn check_auth_middleware(
-    req: ServiceRequest,
-    next: Next<impl MessageBody>,
-) -> Result<ServiceResponse<impl MessageBody>, Error> {
-    if !req.cookie("token").is_some() {
-        return Err(ErrorUnauthorized(
-            serde_json::json!({ "message": "token is not available in the cookie" }),
-        ));
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+#[derive(PartialEq)]
+pub enum TokenTransport {
+    Header,
+    Cookie,
+}
+
+/// Access token sent with the request, from the `Authorization` header or the
+/// `token` cookie. When both are present `config.auth.token_precedence`
+/// decides which wins.
+pub fn token_from_request(
+    req: &HttpRequest,
+    config: &AuthConfig,
+) -> Option<(String, TokenTransport)> {
+    let header: Option<(String, TokenTransport)> = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| (token.trim().to_string(), TokenTransport::Header));
+
+    let cookie: Option<(String, TokenTransport)> = req
+        .cookie("token")
+        .map(|cookie| (cookie.value().to_string(), TokenTransport::Cookie));
+
+    if config.token_precedence == "cookie" {
+        cookie.or(header)
+    } else {
+        header.or(cookie)
     }
+}
+
+/// Double-submit check for cookie authenticated requests: state-changing
+/// requests must repeat the `csrf_token` cookie in the `X-CSRF-Token` header,
+/// which a cross-site page cannot read.
+pub fn check_csrf(req: &HttpRequest) -> Result<(), ApiError> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(());
+    }
+
+    let cookie: Option<String> = req
+        .cookie(CSRF_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .filter(|value| !value.is_empty());
 
-    let auth: Option<&HeaderValue> = req.headers().get(AUTHORIZATION);
+    let header: Option<&str> = req
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok());
 
-    if auth.is_none() {
-        return Err(ErrorUnauthorized(
-            serde_json::json!({ "message": "provide an authentication token in the request" }),
-        ));
+    match (cookie, header) {
+        (Some(cookie), Some(header)) if hash_token(&cookie) == hash_token(header) => Ok(()),
+        _ => Err(ApiError::Forbidden(String::from(
+            "missing or invalid csrf token",
+        ))),
+    }
+}
+
+fn app_state(req: &ServiceRequest) -> Result<&Data<AppState>, ApiError> {
+    req.app_data::<Data<AppState>>()
+        .ok_or_else(|| ApiError::Internal(String::from("application state missing")))
+}
+
+fn api_key_from_request(req: &ServiceRequest) -> Option<String> {
+    if let Some(api_key) = req.headers().get("X-API-Key") {
+        return api_key.to_str().ok().map(String::from);
+    }
+
+    req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.replace("Bearer ", ""))
+        .filter(|token| token.starts_with(API_KEY_PREFIX))
+}
+
+async fn authenticate_api_key(req: &ServiceRequest, api_key: String) -> Result<Claims, ApiError> {
+    let state: &Data<AppState> = app_state(req)?;
+
+    match state
+        .db
+        .send(AuthenticateApiKey {
+            key_hash: hash_token(&api_key),
+        })
+        .await??
+    {
+        Some((_, user)) if user.suspended_at.is_some() => {
+            Err(ApiError::Forbidden(String::from("account is suspended")))
+        }
+        Some((_, user)) if user.password_reset_required => Err(ApiError::Forbidden(String::from(
+            "password reset required, use the link sent to your email address",
+        ))),
+        Some((api_key, user)) => Ok(Claims {
+            exp: api_key
+                .expires_on
+                .map(|expires_on| expires_on.timestamp() as usize)
+                .unwrap_or(usize::MAX),
+            iat: Utc::now().timestamp() as usize,
+            email: user.email,
+            id: user.id,
+            role: user.role,
+            jti: String::new(),
+            mfa_pending: false,
+            scopes: Some(
+                api_key
+                    .scopes
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect(),
+            ),
+        }),
+        None => Err(ApiError::Unauthorized(String::from(
+            "invalid, expired or revoked api key",
+        ))),
+    }
+}
+
+fn check_scope(req: &ServiceRequest, claims: &Claims) -> Result<(), ApiError> {
+    let Some(scopes) = &claims.scopes else {
+        return Ok(());
     };
 
-    let token: String = auth
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .replace("Bearer ", "")
-        .to_owned();
-
-    let claim: TokenData<Claims> = match decode_jwt(token) {
-        Ok(claim_data) => claim_data,
-        Err(err) => match err.kind() {
-            ErrorKind::ExpiredSignature => {
-                return Err(ErrorUnauthorized(
-                    serde_json::json!({ "message": "token has expired" }),
-                ));
-            }
-            ErrorKind::InvalidSignature => {
-                return Err(ErrorUnauthorized(
-                    serde_json::json!({ "message": "invalid token signature" }),
-                ));
-            }
-            _ => {
-                return Err(ErrorUnauthorized(
-                    serde_json::json!({ "message": "invalid token", "details": err.to_string() }),
-                ));
-            }
-        },
+    let resource: &str = match req.path().trim_start_matches('/').split('/').next() {
+        Some("api") => "notes",
+        Some("crypto") => "transactions",
+        Some("admin") => "admin",
+        _ => "account",
+    };
+
+    let access: &str = match *req.method() {
+        Method::GET | Method::HEAD => "read",
+        _ => "write",
+    };
+
+    let required_scope: String = format!("{}:{}", resource, access);
+
+    if !scopes.contains(&required_scope) {
+        return Err(ApiError::Forbidden(format!(
+            "api key is missing the {} scope",
+            required_scope
+        )));
+    }
+
+    Ok(())
+}
+
+fn extract_claims(req: &ServiceRequest) -> Result<Claims, ApiError> {
+    let state: &Data<AppState> = app_state(req)?;
+
+    let (token, transport) = match token_from_request(req.request(), &state.config.auth) {
+        Some(token) => token,
+        None => {
+            return Err(ApiError::Unauthorized(String::from(
+                "provide an authentication token in the request",
+            )));
+        }
     };
 
-    req.extensions_mut().insert(claim.claims);
+    if transport == TokenTransport::Cookie {
+        check_csrf(req.request())?;
+    }
+
+    let claim: TokenData<Claims> = state.jwt_keys.decode_jwt(token)?;
+
+    Ok(claim.claims)
+}
+
+async fn check_session(req: &ServiceRequest, claims: &Claims) -> Result<(), ApiError> {
+    let state: &Data<AppState> = app_state(req)?;
 
-    next.call(req).await.map_err(|err| {
-        ErrorInternalServerError(
-            serde_json::json!({ "message": "internal server error", "details": err.to_string() })
-                .to_string(),
-        )
-    })
+    match state
+        .db
+        .send(TouchSession {
+            jti: claims.jti.clone(),
+        })
+        .await??
+    {
+        Some((_, user)) if user.suspended_at.is_some() => {
+            Err(ApiError::Forbidden(String::from("account is suspended")))
+        }
+        Some(_) => Ok(()),
+        None => Err(ApiError::Unauthorized(String::from(
+            "session has been revoked",
+        ))),
+    }
 }
 
-async fn check_auth_permissions(
+async fn authenticate(req: &ServiceRequest) -> Result<Claims, ApiError> {
+    if let Some(api_key) = api_key_from_request(req) {
+        let claims: Claims = authenticate_api_key(req, api_key).await?;
+        check_scope(req, &claims)?;
+        return Ok(claims);
+    }
+
+    let claims: Claims = extract_claims(req)?;
+
+    if claims.mfa_pending {
+        return Err(ApiError::Unauthorized(String::from(
+            "two-factor authentication is required",
+        )));
+    }
+
+    check_session(req, &claims).await?;
+
+    Ok(claims)
+}
+
+pub async fn check_auth_middleware(
     req: ServiceRequest,
     next: Next<impl MessageBody>,
-    allowed_roles: Vec<String>,
 ) -> Result<ServiceResponse<impl MessageBody>, Error> {
-    if !req.cookie("token").is_some() {
-        return Err(ErrorUnauthorized(
-            serde_json::json!({ "message": "token is not available in the cookie" }),
-        ));
-    }
+    let claims: Claims = authenticate(&req).await?;
 
-    let auth: Option<&HeaderValue> = req.headers().get(AUTHORIZATION);
+    req.extensions_mut().insert(claims);
 
-    if auth.is_none() {
-        return Err(ErrorUnauthorized(
-            serde_json::json!({ "message": "provide an authentication token" }),
-        ));
-    };
+    next.call(req).await
+}
 
-    let token: String = auth
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .replace("Bearer ", "")
-        .to_owned();
-
-    let claim: TokenData<Claims> = match decode_jwt(token) {
-        Ok(claim_data) => claim_data,
-        Err(e) => match e.kind() {
-            ErrorKind::ExpiredSignature => {
-                return Err(ErrorUnauthorized(
-                    serde_json::json!({ "message": "token has expired" }),
-                ));
-            }
-            ErrorKind::InvalidSignature => {
-                return Err(ErrorUnauthorized(
-                    serde_json::json!({ "message": "invalid token signature" }),
-                ));
-            }
-            _ => {
-                return Err(ErrorUnauthorized(
-                    serde_json::json!({ "message": "invalid token", "details": e.to_string() }),
-                ));
-            }
-        },
+pub async fn check_mfa_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let claims: Claims = extract_claims(&req)?;
+
+    req.extensions_mut().insert(claims);
+
+    next.call(req).await
+}
+
+async fn authorize(req: &ServiceRequest, permission: &str) -> Result<Claims, ApiError> {
+    let existing_claims: Option<Claims> = req.extensions().get::<Claims>().cloned();
+
+    let claims: Claims = match existing_claims {
+        Some(claims) => claims,
+        None => authenticate(req).await?,
     };
 
-    let user_role: String = claim.claims.role;
+    let db: Addr<DbActor> = app_state(req)?.db.clone();
+
+    let permissions: Vec<String> = db
+        .send(FetchUserPermissions { user_id: claims.id })
+        .await??;
 
-    if !allowed_roles.contains(&user_role) {
-        return Err(ErrorForbidden(
-            serde_json::json!({ "message": "unauthorized to access this route" }),
-        ));
+    if !permissions.iter().any(|granted| granted == permission) {
+        return Err(ApiError::Forbidden(format!(
+            "missing the {} permission",
+            permission
+        )));
     }
 
-    next.call(req).await.map_err(|err| {
-        ErrorInternalServerError(
-            serde_json::json!({ "message": "internal server error", "details": err.to_string() })
-                .to_string(),
-        )
-    })
+    Ok(claims)
 }
 
-pub async fn auth_stats_middleware(
+async fn check_permission<B: MessageBody>(
     req: ServiceRequest,
-    next: Next<impl MessageBody>,
-) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
-    check_auth_permissions(req, next, vec![String::from("admin")]).await
+    next: Next<B>,
+    permission: &'static str,
+) -> Result<ServiceResponse<B>, Error> {
+    let claims: Claims = authorize(&req, permission).await?;
+
+    req.extensions_mut().insert(claims);
+
+    next.call(req).await
+}
+
+type PermissionFuture<B> = Pin<Box<dyn Future<Output = Result<ServiceResponse<B>, Error>>>>;
+
+/// Builds a middleware that only lets through users whose role grants
+/// `permission`, e.g. `.wrap(require_permission("notes:read_all"))`.
+///
+/// Permissions are looked up from the user's current role on every request, so
+/// role changes take effect without issuing new tokens.
+pub fn require_permission<B: MessageBody + 'static>(
+    permission: &'static str,
+) -> MiddlewareFn<impl Fn(ServiceRequest, Next<B>) -> PermissionFuture<B>, ()> {
+    from_fn(
+        move |req: ServiceRequest, next: Next<B>| -> PermissionFuture<B> {
+            Box::pin(check_permission(req, next, permission))
+        },
+    )
 }
@@ -17,6 +17,8 @@ pub struct Note {
     pub created_by: i32,
     pub created_on: Option<DateTime<Utc>>,
     pub updated_on: Option<DateTime<Utc>>,
+    pub notebook_id: Option<i32>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Queryable, Debug, Serialize)]
@@ -25,10 +27,185 @@ pub struct User {
     pub username: String,
     pub email: String,
     #[serde(skip_serializing)]
-    pub password: String,
+    pub password: Option<String>,
     pub otp_verified: Option<bool>,
     pub otp_enabled: Option<bool>,
     pub otp_base32: Option<String>,
     pub otp_auth_url: Option<String>,
     pub role: String,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub password_reset_required: bool,
+    pub failed_login_count: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    #[serde(skip_serializing)]
+    pub verification_email_sent_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct RefreshToken {
+    pub id: i32,
+    pub user_id: i32,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub family_id: String,
+    pub expires_on: DateTime<Utc>,
+    pub used_on: Option<DateTime<Utc>>,
+    pub revoked_on: Option<DateTime<Utc>>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct Session {
+    pub id: i32,
+    #[serde(skip_serializing)]
+    pub jti: String,
+    pub user_id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_on: DateTime<Utc>,
+    pub last_seen_on: DateTime<Utc>,
+    pub revoked_on: Option<DateTime<Utc>>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct OTPRecoveryCode {
+    pub id: i32,
+    pub user_id: i32,
+    #[serde(skip_serializing)]
+    pub code_hash: String,
+    pub used_on: Option<DateTime<Utc>>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: String,
+    pub expires_on: Option<DateTime<Utc>>,
+    pub last_used_on: Option<DateTime<Utc>>,
+    pub revoked_on: Option<DateTime<Utc>>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct AuditEvent {
+    pub id: i32,
+    pub actor_id: Option<i32>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: serde_json::Value,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct LoginAttempt {
+    pub id: i32,
+    pub user_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub succeeded: bool,
+    pub attempted_on: DateTime<Utc>,
+    pub email: Option<String>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct UserIdentity {
+    pub id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_on: Option<DateTime<Utc>>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct OidcLoginState {
+    pub id: i32,
+    #[serde(skip_serializing)]
+    pub state_hash: String,
+    #[serde(skip_serializing)]
+    pub nonce: String,
+    #[serde(skip_serializing)]
+    pub code_verifier: String,
+    pub user_id: Option<i32>,
+    pub expires_on: DateTime<Utc>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct Role {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct Permission {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct Tag {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct Notebook {
+    pub id: i32,
+    pub user_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct NoteRevision {
+    pub id: i32,
+    pub note_id: i32,
+    pub revision: i32,
+    pub title: String,
+    pub content: String,
+    pub edited_by: Option<i32>,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct NoteShare {
+    pub note_id: i32,
+    pub user_id: i32,
+    pub permission: String,
+    pub created_on: DateTime<Utc>,
+}
+
+#[derive(Queryable, Debug, Serialize)]
+pub struct NoteLink {
+    pub id: i32,
+    pub note_id: i32,
+    pub slug: String,
+    #[serde(skip_serializing)]
+    pub password_hash: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub view_count: i64,
+    pub created_by: i32,
+    pub created_on: DateTime<Utc>,
+    #[serde(skip_serializing)]
+    pub failed_password_count: i32,
+    #[serde(skip_serializing)]
+    pub last_failed_password_at: Option<DateTime<Utc>>,
 }
@@ -1,12 +1,29 @@
-use crate::{fetch_notes, fetch_users, middlewares::auth_middlewares::auth_stats_middleware};
+use crate::{
+    admin_delete_user, assign_user_role, create_role, fetch_audit_events, fetch_login_attempts,
+    fetch_notes, fetch_permissions, fetch_roles, fetch_users, force_password_reset,
+    middlewares::auth_middlewares::require_permission, reactivate_user, reset_user_otp,
+    suspend_user, unlock_user, update_role_permissions,
+};
 use actix_web::web;
-use actix_web_lab::middleware::from_fn;
 
 pub fn configuration(configure: &mut web::ServiceConfig) {
     configure.service(
         web::scope("/admin/dashboard")
-            .wrap(from_fn(auth_stats_middleware))
+            .wrap(require_permission("admin:access"))
             .service(fetch_notes)
-            .service(fetch_users),
+            .service(fetch_users)
+            .service(fetch_roles)
+            .service(create_role)
+            .service(update_role_permissions)
+            .service(fetch_permissions)
+            .service(assign_user_role)
+            .service(suspend_user)
+            .service(reactivate_user)
+            .service(force_password_reset)
+            .service(reset_user_otp)
+            .service(admin_delete_user)
+            .service(fetch_login_attempts)
+            .service(unlock_user)
+            .service(fetch_audit_events),
     );
 }
@@ -1,4 +1,7 @@
-use crate::handlers::auth_handlers::{two_fa_handlers::*, user_handlers::*};
+use crate::handlers::auth_handlers::{
+    api_key_handlers::*, oidc_handlers::*, password_handlers::*, session_handlers::*,
+    token_handlers::*, two_fa_handlers::*, user_handlers::*, verification_handlers::*,
+};
 use crate::{handlers::auth_handlers::*, middlewares::auth_middlewares::*};
 use actix_web::web;
 use actix_web_lab::middleware::from_fn;
@@ -6,21 +9,45 @@ use auth_handlers::*;
 
 pub fn configuration(configure: &mut web::ServiceConfig) {
     configure
+        .service(jwks_handler)
         .service(
             web::scope("/user")
                 .service(register_user)
                 .service(login_user)
+                .service(refresh_token_handler)
+                .service(forgot_password)
+                .service(reset_password)
+                .service(verify_email)
+                .service(resend_verification_email_by_address)
+                .service(oidc_login)
+                .service(oidc_callback)
                 .service(logout_user),
         )
+        .service(
+            web::scope("/auth/otp/validate")
+                .wrap(from_fn(check_mfa_middleware))
+                .service(token_validate_handler),
+        )
         .service(
             web::scope("/auth")
                 .wrap(from_fn(check_auth_middleware))
                 .service(generate_otp_handler)
                 .service(verify_otp_handler)
-                .service(token_validate_handler)
                 .service(disable_otp_handler)
+                .service(recovery_codes_handler)
+                .service(regenerate_recovery_codes_handler)
                 .service(get_user)
                 .service(delete_user)
-                .service(update_password),
+                .service(update_password)
+                .service(fetch_sessions)
+                .service(revoke_session)
+                .service(revoke_all_sessions)
+                .service(resend_verification_email)
+                .service(create_api_key)
+                .service(fetch_api_keys)
+                .service(revoke_api_key)
+                .service(start_identity_link)
+                .service(fetch_identities)
+                .service(unlink_identity),
         );
 }
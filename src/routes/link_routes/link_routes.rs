@@ -0,0 +1,22 @@
+use crate::{
+    handlers::link_handlers::{link_handlers::*, public_handlers::*},
+    middlewares::auth_middlewares::*,
+};
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn configuration(configure: &mut web::ServiceConfig) {
+    configure
+        .service(
+            web::scope("/api/notes/{note_id}/links")
+                .wrap(from_fn(check_auth_middleware))
+                .service(fetch_note_links)
+                .service(create_note_link)
+                .service(revoke_note_link),
+        )
+        .service(
+            web::scope("/p")
+                .service(view_public_note)
+                .service(unlock_public_note),
+        );
+}
@@ -1,5 +1,12 @@
 pub mod admin_routes;
 pub mod auth_routes;
+pub mod link_routes;
 pub mod note_routes;
+pub mod notebook_routes;
+pub mod revision_routes;
+pub mod search_routes;
+pub mod share_routes;
+pub mod tag_routes;
 pub mod test_routes;
 pub mod transaction_routes;
+pub mod trash_routes;
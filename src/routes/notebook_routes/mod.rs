@@ -0,0 +1 @@
+pub mod notebook_routes;
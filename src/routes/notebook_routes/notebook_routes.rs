@@ -0,0 +1,15 @@
+use crate::{handlers::notebook_handlers::notebook_handlers::*, middlewares::auth_middlewares::*};
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn configuration(configure: &mut web::ServiceConfig) {
+    configure.service(
+        web::scope("/api/notebooks")
+            .wrap(from_fn(check_auth_middleware))
+            .service(fetch_notebooks)
+            .service(create_notebook)
+            .service(rename_notebook)
+            .service(move_notebook)
+            .service(delete_notebook),
+    );
+}
@@ -0,0 +1 @@
+pub mod revision_routes;
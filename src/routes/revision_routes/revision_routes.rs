@@ -0,0 +1,14 @@
+use crate::{handlers::revision_handlers::revision_handlers::*, middlewares::auth_middlewares::*};
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn configuration(configure: &mut web::ServiceConfig) {
+    configure.service(
+        web::scope("/api/notes/{note_id}/revisions")
+            .wrap(from_fn(check_auth_middleware))
+            .service(fetch_revisions)
+            .service(diff_revisions)
+            .service(fetch_note_revision)
+            .service(restore_note_revision),
+    );
+}
@@ -0,0 +1,11 @@
+use crate::{handlers::search_handlers::search_handlers::*, middlewares::auth_middlewares::*};
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn configuration(configure: &mut web::ServiceConfig) {
+    configure.service(
+        web::scope("/api/search")
+            .wrap(from_fn(check_auth_middleware))
+            .service(search_notes),
+    );
+}
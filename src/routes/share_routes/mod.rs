@@ -0,0 +1 @@
+pub mod share_routes;
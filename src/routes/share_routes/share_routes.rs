@@ -0,0 +1,19 @@
+use crate::{handlers::share_handlers::share_handlers::*, middlewares::auth_middlewares::*};
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn configuration(configure: &mut web::ServiceConfig) {
+    configure
+        .service(
+            web::scope("/api/notes/{note_id}/shares")
+                .wrap(from_fn(check_auth_middleware))
+                .service(fetch_note_shares)
+                .service(share_note)
+                .service(unshare_note),
+        )
+        .service(
+            web::scope("/api/shared-with-me")
+                .wrap(from_fn(check_auth_middleware))
+                .service(fetch_shared_with_me),
+        );
+}
@@ -0,0 +1 @@
+pub mod tag_routes;
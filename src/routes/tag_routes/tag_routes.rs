@@ -0,0 +1,15 @@
+use crate::{handlers::tag_handlers::tag_handlers::*, middlewares::auth_middlewares::*};
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn configuration(configure: &mut web::ServiceConfig) {
+    configure.service(
+        web::scope("/api/tags")
+            .wrap(from_fn(check_auth_middleware))
+            .service(fetch_tags)
+            .service(create_tag)
+            .service(rename_tag)
+            .service(merge_tag)
+            .service(delete_tag),
+    );
+}
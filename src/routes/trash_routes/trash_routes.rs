@@ -0,0 +1,14 @@
+use crate::{handlers::trash_handlers::trash_handlers::*, middlewares::auth_middlewares::*};
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn configuration(configure: &mut web::ServiceConfig) {
+    configure.service(
+        web::scope("/api/trash")
+            .wrap(from_fn(check_auth_middleware))
+            .service(fetch_trash)
+            .service(empty_trash)
+            .service(restore_note)
+            .service(purge_note),
+    );
+}
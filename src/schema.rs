@@ -1,5 +1,115 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 100]
+        name -> Varchar,
+        #[max_length = 16]
+        prefix -> Varchar,
+        #[max_length = 64]
+        key_hash -> Varchar,
+        #[max_length = 255]
+        scopes -> Varchar,
+        expires_on -> Nullable<Timestamptz>,
+        last_used_on -> Nullable<Timestamptz>,
+        revoked_on -> Nullable<Timestamptz>,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    audit_events (id) {
+        id -> Int4,
+        actor_id -> Nullable<Int4>,
+        #[max_length = 100]
+        action -> Varchar,
+        #[max_length = 50]
+        target_type -> Nullable<Varchar>,
+        #[max_length = 100]
+        target_id -> Nullable<Varchar>,
+        #[max_length = 45]
+        ip_address -> Nullable<Varchar>,
+        #[max_length = 255]
+        user_agent -> Nullable<Varchar>,
+        details -> Jsonb,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    login_attempts (id) {
+        id -> Int4,
+        user_id -> Nullable<Int4>,
+        #[max_length = 45]
+        ip_address -> Nullable<Varchar>,
+        succeeded -> Bool,
+        attempted_on -> Timestamptz,
+        #[max_length = 255]
+        email -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    note_links (id) {
+        id -> Int4,
+        note_id -> Int4,
+        #[max_length = 64]
+        slug -> Varchar,
+        #[max_length = 255]
+        password_hash -> Nullable<Varchar>,
+        expires_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+        view_count -> Int8,
+        created_by -> Int4,
+        created_on -> Timestamptz,
+        failed_password_count -> Int4,
+        last_failed_password_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    note_revisions (id) {
+        id -> Int4,
+        note_id -> Int4,
+        revision -> Int4,
+        #[max_length = 255]
+        title -> Varchar,
+        content -> Text,
+        edited_by -> Nullable<Int4>,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    note_shares (note_id, user_id) {
+        note_id -> Int4,
+        user_id -> Int4,
+        #[max_length = 10]
+        permission -> Varchar,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    note_tags (note_id, tag_id) {
+        note_id -> Int4,
+        tag_id -> Int4,
+    }
+}
+
+diesel::table! {
+    notebooks (id) {
+        id -> Int4,
+        user_id -> Int4,
+        parent_id -> Nullable<Int4>,
+        #[max_length = 100]
+        name -> Varchar,
+        created_on -> Timestamptz,
+    }
+}
+
 diesel::table! {
     notes (id) {
         id -> Int4,
@@ -12,6 +122,130 @@ diesel::table! {
         created_by -> Int4,
         created_on -> Nullable<Timestamptz>,
         updated_on -> Nullable<Timestamptz>,
+        notebook_id -> Nullable<Int4>,
+        deleted_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    oidc_login_states (id) {
+        id -> Int4,
+        #[max_length = 64]
+        state_hash -> Varchar,
+        #[max_length = 64]
+        nonce -> Varchar,
+        #[max_length = 128]
+        code_verifier -> Varchar,
+        user_id -> Nullable<Int4>,
+        expires_on -> Timestamptz,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    otp_recovery_codes (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 255]
+        code_hash -> Varchar,
+        used_on -> Nullable<Timestamptz>,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    password_reset_tokens (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        expires_on -> Timestamptz,
+        used_on -> Nullable<Timestamptz>,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    permissions (id) {
+        id -> Int4,
+        #[max_length = 100]
+        name -> Varchar,
+        #[max_length = 255]
+        description -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    refresh_tokens (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        #[max_length = 64]
+        family_id -> Varchar,
+        expires_on -> Timestamptz,
+        used_on -> Nullable<Timestamptz>,
+        revoked_on -> Nullable<Timestamptz>,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    role_permissions (role_id, permission_id) {
+        role_id -> Int4,
+        permission_id -> Int4,
+    }
+}
+
+diesel::table! {
+    roles (id) {
+        id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+        #[max_length = 255]
+        description -> Nullable<Varchar>,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    sessions (id) {
+        id -> Int4,
+        #[max_length = 64]
+        jti -> Varchar,
+        user_id -> Int4,
+        #[max_length = 255]
+        user_agent -> Nullable<Varchar>,
+        #[max_length = 45]
+        ip_address -> Nullable<Varchar>,
+        created_on -> Timestamptz,
+        last_seen_on -> Timestamptz,
+        revoked_on -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+        created_on -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    user_identities (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 50]
+        provider -> Varchar,
+        #[max_length = 255]
+        subject -> Varchar,
+        #[max_length = 100]
+        email -> Nullable<Varchar>,
+        last_login_on -> Nullable<Timestamptz>,
+        created_on -> Timestamptz,
     }
 }
 
@@ -22,19 +256,68 @@ diesel::table! {
         username -> Varchar,
         #[max_length = 100]
         email -> Varchar,
-        #[max_length = 80]
-        password -> Varchar,
+        #[max_length = 255]
+        password -> Nullable<Varchar>,
         otp_verified -> Nullable<Bool>,
         otp_enabled -> Nullable<Bool>,
         #[max_length = 100]
         otp_base32 -> Nullable<Varchar>,
         #[max_length = 255]
         otp_auth_url -> Nullable<Varchar>,
-        #[max_length = 10]
+        #[max_length = 50]
         role -> Varchar,
+        email_verified_at -> Nullable<Timestamptz>,
+        suspended_at -> Nullable<Timestamptz>,
+        password_reset_required -> Bool,
+        failed_login_count -> Int4,
+        locked_until -> Nullable<Timestamptz>,
+        verification_email_sent_at -> Nullable<Timestamptz>,
     }
 }
 
+diesel::joinable!(api_keys -> users (user_id));
+diesel::joinable!(audit_events -> users (actor_id));
+diesel::joinable!(login_attempts -> users (user_id));
+diesel::joinable!(note_links -> notes (note_id));
+diesel::joinable!(note_links -> users (created_by));
+diesel::joinable!(note_revisions -> notes (note_id));
+diesel::joinable!(note_revisions -> users (edited_by));
+diesel::joinable!(note_shares -> notes (note_id));
+diesel::joinable!(note_shares -> users (user_id));
+diesel::joinable!(note_tags -> notes (note_id));
+diesel::joinable!(note_tags -> tags (tag_id));
+diesel::joinable!(notebooks -> users (user_id));
+diesel::joinable!(notes -> notebooks (notebook_id));
 diesel::joinable!(notes -> users (created_by));
+diesel::joinable!(oidc_login_states -> users (user_id));
+diesel::joinable!(otp_recovery_codes -> users (user_id));
+diesel::joinable!(password_reset_tokens -> users (user_id));
+diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(role_permissions -> permissions (permission_id));
+diesel::joinable!(role_permissions -> roles (role_id));
+diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(tags -> users (user_id));
+diesel::joinable!(user_identities -> users (user_id));
 
-diesel::allow_tables_to_appear_in_same_query!(notes, users,);
+diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
+    audit_events,
+    login_attempts,
+    note_links,
+    note_revisions,
+    note_shares,
+    note_tags,
+    notebooks,
+    notes,
+    oidc_login_states,
+    otp_recovery_codes,
+    password_reset_tokens,
+    permissions,
+    refresh_tokens,
+    role_permissions,
+    roles,
+    sessions,
+    tags,
+    user_identities,
+    users,
+);
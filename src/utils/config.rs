@@ -0,0 +1,734 @@
+use super::{jwt::load_jwt_keys, mailer::build_mailer};
+use argon2::Params;
+use serde::Deserialize;
+use std::{collections::HashMap, env, fmt, fs, str::FromStr};
+
+/// Default location of the configuration file, used when `CONFIG_FILE` is not
+/// set. A missing default file is not an error.
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Application settings, layered from the defaults below, an optional TOML
+/// file and environment variables, in increasing order of precedence.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub jwt: JwtConfig,
+    pub cookies: CookieConfig,
+    pub mail: MailConfig,
+    pub passwords: PasswordConfig,
+    pub oidc: OidcConfig,
+    pub cloudinary: CloudinaryConfig,
+    pub moonpay: MoonpayConfig,
+    pub revisions: RevisionConfig,
+    pub trash: TrashConfig,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub address: String,
+    pub port: u16,
+    /// Defaults to `http://<address>:<port>`.
+    pub public_url: Option<String>,
+    /// Take the client IP from `Forwarded` / `X-Forwarded-For`. Only enable
+    /// behind a reverse proxy that overwrites those headers.
+    pub trust_proxy_headers: bool,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub secret: String,
+    pub token_precedence: String,
+    pub email_verification: String,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtConfig {
+    pub algorithm: String,
+    pub signing_key: Option<String>,
+    pub signing_kid: Option<String>,
+    pub verification_keys_dir: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookieConfig {
+    pub http_only: bool,
+    /// Defaults to true when the public URL uses https.
+    pub secure: Option<bool>,
+    pub same_site: String,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    pub mailer: String,
+    pub from: String,
+    pub outbox_dir: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordConfig {
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub min_length: usize,
+    pub min_score: u8,
+    pub breached_passwords_dir: Option<String>,
+}
+
+/// OpenID Connect login is enabled when both `issuer` and `client_id` are set.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcConfig {
+    pub provider_name: String,
+    pub issuer: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Defaults to `<public_url>/user/oidc/callback`.
+    pub redirect_url: Option<String>,
+    pub scopes: String,
+}
+
+/// Image uploads are enabled when both `cloud_name` and `upload_preset` are
+/// present. Images of purged notes are only deleted from Cloudinary when the
+/// API key and secret are present too.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CloudinaryConfig {
+    pub cloud_name: Option<String>,
+    pub upload_preset: Option<String>,
+    pub api_key: Option<String>,
+    pub api_secret: Option<String>,
+}
+
+/// The Moonpay routes are enabled when an API key is present.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MoonpayConfig {
+    pub api_key: Option<String>,
+}
+
+/// How many note revisions are kept. Zero disables a limit. The newest
+/// revision of a note is never pruned.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RevisionConfig {
+    pub keep_per_note: u32,
+    pub keep_days: u32,
+}
+
+/// Trashed notes are purged `retention_days` after they were deleted, checked
+/// every `purge_interval_minutes`.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrashConfig {
+    pub retention_days: u32,
+    pub purge_interval_minutes: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: String::from("127.0.0.1"),
+            port: 8080,
+            public_url: None,
+            trust_proxy_headers: false,
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            secret: String::new(),
+            token_precedence: String::from("header"),
+            email_verification: String::from("off"),
+        }
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        JwtConfig {
+            algorithm: String::from("HS256"),
+            signing_key: None,
+            signing_kid: None,
+            verification_keys_dir: None,
+        }
+    }
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        CookieConfig {
+            http_only: true,
+            secure: None,
+            same_site: String::from("lax"),
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig {
+            mailer: String::from("outbox"),
+            from: String::from("RustNoteAPI <no-reply@localhost>"),
+            outbox_dir: String::from("outbox"),
+            smtp_host: None,
+            smtp_port: None,
+            smtp_username: None,
+            smtp_password: None,
+        }
+    }
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        PasswordConfig {
+            argon2_memory_kib: 19456,
+            argon2_iterations: 2,
+            argon2_parallelism: 1,
+            min_length: 8,
+            min_score: 2,
+            breached_passwords_dir: None,
+        }
+    }
+}
+
+impl Default for RevisionConfig {
+    fn default() -> Self {
+        RevisionConfig {
+            keep_per_note: 50,
+            keep_days: 0,
+        }
+    }
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        TrashConfig {
+            retention_days: 30,
+            purge_interval_minutes: 60,
+        }
+    }
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        OidcConfig {
+            provider_name: String::from("oidc"),
+            issuer: None,
+            client_id: None,
+            client_secret: None,
+            redirect_url: None,
+            scopes: String::from("openid email profile"),
+        }
+    }
+}
+
+/// Every problem found while loading the configuration, so they can all be
+/// fixed in one go.
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration:")?;
+
+        for error in &self.0 {
+            writeln!(f, "  - {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Applies environment variable overrides, collecting parse errors instead of
+/// stopping at the first one.
+struct EnvOverrides<'a> {
+    vars: &'a HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl EnvOverrides<'_> {
+    fn string(&self, name: &str, target: &mut String) {
+        if let Some(value) = self.vars.get(name) {
+            *target = value.clone();
+        }
+    }
+
+    fn optional(&self, name: &str, target: &mut Option<String>) {
+        if let Some(value) = self.vars.get(name) {
+            *target = Some(value.clone()).filter(|value| !value.is_empty());
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, target: &mut T) {
+        if let Some(value) = self.vars.get(name) {
+            match value.parse::<T>() {
+                Ok(value) => *target = value,
+                Err(_) => self
+                    .errors
+                    .push(format!("{} has an invalid value: {}", name, value)),
+            }
+        }
+    }
+
+    fn parse_optional<T: FromStr>(&mut self, name: &str, target: &mut Option<T>) {
+        if let Some(value) = self.vars.get(name) {
+            match value.parse::<T>() {
+                Ok(value) => *target = Some(value),
+                Err(_) => self
+                    .errors
+                    .push(format!("{} has an invalid value: {}", name, value)),
+            }
+        }
+    }
+}
+
+impl Config {
+    /// Loads `.env`, then the file named by `CONFIG_FILE` (or `config.toml`
+    /// when it exists), then the process environment.
+    pub fn load() -> Result<Config, ConfigError> {
+        dotenv::dotenv().ok();
+
+        let vars: HashMap<String, String> = env::vars().collect();
+
+        let file: Option<String> = match vars.get("CONFIG_FILE") {
+            Some(path) => Some(fs::read_to_string(path).map_err(|err| {
+                ConfigError(vec![format!(
+                    "unable to read CONFIG_FILE {}: {}",
+                    path, err
+                )])
+            })?),
+            None => fs::read_to_string(DEFAULT_CONFIG_FILE).ok(),
+        };
+
+        Config::from_sources(file.as_deref(), &vars)
+    }
+
+    /// Builds and validates a configuration from TOML contents and a set of
+    /// environment variables, without touching the process environment.
+    pub fn from_sources(
+        toml: Option<&str>,
+        vars: &HashMap<String, String>,
+    ) -> Result<Config, ConfigError> {
+        let mut config: Config = match toml {
+            Some(contents) => toml::from_str(contents)
+                .map_err(|err| ConfigError(vec![format!("invalid configuration file: {}", err)]))?,
+            None => Config::default(),
+        };
+
+        let mut errors: Vec<String> = config.apply_env(vars);
+        errors.extend(config.validate());
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(errors))
+        }
+    }
+
+    fn apply_env(&mut self, vars: &HashMap<String, String>) -> Vec<String> {
+        let mut env: EnvOverrides = EnvOverrides {
+            vars,
+            errors: Vec::new(),
+        };
+
+        env.string("ADDRESS", &mut self.server.address);
+        env.parse("PORT", &mut self.server.port);
+        env.optional("PUBLIC_URL", &mut self.server.public_url);
+        env.parse("TRUST_PROXY_HEADERS", &mut self.server.trust_proxy_headers);
+
+        env.string("DATABASE_URL", &mut self.database.url);
+
+        env.string("SECRET", &mut self.auth.secret);
+        env.string("TOKEN_PRECEDENCE", &mut self.auth.token_precedence);
+        env.string("EMAIL_VERIFICATION", &mut self.auth.email_verification);
+
+        env.string("JWT_ALGORITHM", &mut self.jwt.algorithm);
+        env.optional("JWT_SIGNING_KEY", &mut self.jwt.signing_key);
+        env.optional("JWT_SIGNING_KID", &mut self.jwt.signing_kid);
+        env.optional(
+            "JWT_VERIFICATION_KEYS_DIR",
+            &mut self.jwt.verification_keys_dir,
+        );
+
+        env.parse("COOKIE_HTTP_ONLY", &mut self.cookies.http_only);
+        env.parse_optional("COOKIE_SECURE", &mut self.cookies.secure);
+        env.string("COOKIE_SAME_SITE", &mut self.cookies.same_site);
+
+        env.string("MAILER", &mut self.mail.mailer);
+        env.string("MAIL_FROM", &mut self.mail.from);
+        env.string("MAIL_OUTBOX_DIR", &mut self.mail.outbox_dir);
+        env.optional("SMTP_HOST", &mut self.mail.smtp_host);
+        env.parse_optional("SMTP_PORT", &mut self.mail.smtp_port);
+        env.optional("SMTP_USERNAME", &mut self.mail.smtp_username);
+        env.optional("SMTP_PASSWORD", &mut self.mail.smtp_password);
+
+        env.parse("ARGON2_MEMORY_KIB", &mut self.passwords.argon2_memory_kib);
+        env.parse("ARGON2_ITERATIONS", &mut self.passwords.argon2_iterations);
+        env.parse("ARGON2_PARALLELISM", &mut self.passwords.argon2_parallelism);
+        env.parse("PASSWORD_MIN_LENGTH", &mut self.passwords.min_length);
+        env.parse("PASSWORD_MIN_SCORE", &mut self.passwords.min_score);
+        env.optional(
+            "BREACHED_PASSWORDS_DIR",
+            &mut self.passwords.breached_passwords_dir,
+        );
+
+        env.string("OIDC_PROVIDER_NAME", &mut self.oidc.provider_name);
+        env.optional("OIDC_ISSUER", &mut self.oidc.issuer);
+        env.optional("OIDC_CLIENT_ID", &mut self.oidc.client_id);
+        env.optional("OIDC_CLIENT_SECRET", &mut self.oidc.client_secret);
+        env.optional("OIDC_REDIRECT_URL", &mut self.oidc.redirect_url);
+        env.string("OIDC_SCOPES", &mut self.oidc.scopes);
+
+        env.optional("CLOUDINARY_CLOUD_NAME", &mut self.cloudinary.cloud_name);
+        env.optional(
+            "CLOUDINARY_UPLOAD_PRESET",
+            &mut self.cloudinary.upload_preset,
+        );
+        env.optional("CLOUDINARY_API_KEY", &mut self.cloudinary.api_key);
+        env.optional("CLOUDINARY_API_SECRET", &mut self.cloudinary.api_secret);
+
+        env.optional("MOONPAY_API_KEY", &mut self.moonpay.api_key);
+
+        env.parse("NOTE_REVISIONS_KEEP", &mut self.revisions.keep_per_note);
+        env.parse("NOTE_REVISIONS_KEEP_DAYS", &mut self.revisions.keep_days);
+
+        env.parse("TRASH_RETENTION_DAYS", &mut self.trash.retention_days);
+        env.parse(
+            "TRASH_PURGE_INTERVAL_MINUTES",
+            &mut self.trash.purge_interval_minutes,
+        );
+
+        env.errors
+    }
+
+    /// Returns every invalid or missing setting.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors: Vec<String> = Vec::new();
+
+        if self.database.url.is_empty() {
+            errors.push(String::from("DATABASE_URL must be set"));
+        }
+
+        if self.auth.secret.is_empty() {
+            errors.push(String::from("SECRET must be set"));
+        }
+
+        if !matches!(self.auth.token_precedence.as_str(), "header" | "cookie") {
+            errors.push(String::from("TOKEN_PRECEDENCE must be header or cookie"));
+        }
+
+        if !matches!(
+            self.auth.email_verification.as_str(),
+            "off" | "notes" | "login"
+        ) {
+            errors.push(String::from(
+                "EMAIL_VERIFICATION must be off, notes or login",
+            ));
+        }
+
+        match self.jwt.algorithm.as_str() {
+            "HS256" => {}
+            "RS256" | "EdDSA" => {
+                for (name, value) in [
+                    ("JWT_SIGNING_KEY", &self.jwt.signing_key),
+                    ("JWT_SIGNING_KID", &self.jwt.signing_kid),
+                    ("JWT_VERIFICATION_KEYS_DIR", &self.jwt.verification_keys_dir),
+                ] {
+                    if value.is_none() {
+                        errors.push(format!(
+                            "{} must be set when JWT_ALGORITHM is RS256 or EdDSA",
+                            name
+                        ));
+                    }
+                }
+
+                if self.jwt.signing_key.is_some()
+                    && self.jwt.signing_kid.is_some()
+                    && self.jwt.verification_keys_dir.is_some()
+                {
+                    if let Err(err) = load_jwt_keys(&self.jwt, &self.auth.secret) {
+                        errors.push(err);
+                    }
+                }
+            }
+            _ => errors.push(String::from("JWT_ALGORITHM must be HS256, RS256 or EdDSA")),
+        }
+
+        match self.cookies.same_site.as_str() {
+            "strict" | "lax" => {}
+            "none" if self.cookie_secure() => {}
+            "none" => errors.push(String::from(
+                "COOKIE_SAME_SITE=none requires COOKIE_SECURE=true",
+            )),
+            _ => errors.push(String::from("COOKIE_SAME_SITE must be strict, lax or none")),
+        }
+
+        match self.mail.mailer.as_str() {
+            "outbox" => {}
+            "smtp" if self.mail.smtp_host.is_some() => {
+                if let Err(err) = build_mailer(&self.mail) {
+                    errors.push(err);
+                }
+            }
+            "smtp" => errors.push(String::from("SMTP_HOST must be set when MAILER is smtp")),
+            _ => errors.push(String::from("MAILER must be smtp or outbox")),
+        }
+
+        if let Err(err) = self.passwords.argon2_params() {
+            errors.push(format!("invalid Argon2 parameters: {}", err));
+        }
+
+        if self.passwords.min_length == 0 {
+            errors.push(String::from("PASSWORD_MIN_LENGTH must be at least 1"));
+        }
+
+        if self.passwords.min_score > 4 {
+            errors.push(String::from("PASSWORD_MIN_SCORE must be between 0 and 4"));
+        }
+
+        if self.oidc.issuer.is_some() != self.oidc.client_id.is_some() {
+            errors.push(String::from(
+                "OIDC_ISSUER and OIDC_CLIENT_ID must be set together",
+            ));
+        }
+
+        if self.cloudinary.cloud_name.is_some() != self.cloudinary.upload_preset.is_some() {
+            errors.push(String::from(
+                "CLOUDINARY_CLOUD_NAME and CLOUDINARY_UPLOAD_PRESET must be set together",
+            ));
+        }
+
+        if self.cloudinary.api_key.is_some() != self.cloudinary.api_secret.is_some() {
+            errors.push(String::from(
+                "CLOUDINARY_API_KEY and CLOUDINARY_API_SECRET must be set together",
+            ));
+        }
+
+        if self.trash.purge_interval_minutes == 0 {
+            errors.push(String::from(
+                "TRASH_PURGE_INTERVAL_MINUTES must be greater than 0",
+            ));
+        }
+
+        errors
+    }
+
+    pub fn public_url(&self) -> String {
+        self.server
+            .public_url
+            .clone()
+            .unwrap_or_else(|| format!("http://{}:{}", self.server.address, self.server.port))
+    }
+
+    pub fn cookie_secure(&self) -> bool {
+        self.cookies
+            .secure
+            .unwrap_or_else(|| self.public_url().starts_with("https://"))
+    }
+}
+
+impl PasswordConfig {
+    pub fn argon2_params(&self) -> Result<Params, argon2::Error> {
+        Params::new(
+            self.argon2_memory_kib,
+            self.argon2_iterations,
+            self.argon2_parallelism,
+            None,
+        )
+    }
+}
+
+impl CloudinaryConfig {
+    /// The cloud name and upload preset, or `None` when uploads are disabled.
+    pub fn credentials(&self) -> Option<(&str, &str)> {
+        Some((self.cloud_name.as_deref()?, self.upload_preset.as_deref()?))
+    }
+
+    /// The cloud name, API key and API secret, or `None` when images cannot
+    /// be deleted.
+    pub fn admin_credentials(&self) -> Option<(&str, &str, &str)> {
+        Some((
+            self.cloud_name.as_deref()?,
+            self.api_key.as_deref()?,
+            self.api_secret.as_deref()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn required_vars() -> HashMap<String, String> {
+        vars(&[
+            ("DATABASE_URL", "postgres://localhost/notes"),
+            ("SECRET", "test-secret"),
+        ])
+    }
+
+    #[test]
+    fn uses_defaults_for_unset_settings() {
+        let config: Config = Config::from_sources(None, &required_vars()).unwrap();
+
+        assert_eq!(config.server.address, "127.0.0.1");
+        assert_eq!(config.server.port, 8080);
+        assert!(!config.server.trust_proxy_headers);
+        assert_eq!(config.public_url(), "http://127.0.0.1:8080");
+        assert!(!config.cookie_secure());
+        assert_eq!(config.auth.token_precedence, "header");
+        assert_eq!(config.jwt.algorithm, "HS256");
+        assert_eq!(config.mail.mailer, "outbox");
+        assert_eq!(config.passwords.min_length, 8);
+    }
+
+    #[test]
+    fn file_settings_override_defaults() {
+        let toml: &str = r#"
+            [server]
+            port = 9000
+            public_url = "https://notes.example.com"
+
+            [database]
+            url = "postgres://db.example.com/notes"
+
+            [auth]
+            secret = "file-secret"
+
+            [passwords]
+            min_length = 12
+        "#;
+
+        let config: Config = Config::from_sources(Some(toml), &HashMap::new()).unwrap();
+
+        assert_eq!(config.server.address, "127.0.0.1");
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.database.url, "postgres://db.example.com/notes");
+        assert_eq!(config.auth.secret, "file-secret");
+        assert_eq!(config.passwords.min_length, 12);
+        assert!(config.cookie_secure());
+    }
+
+    #[test]
+    fn environment_overrides_file_settings() {
+        let toml: &str = r#"
+            [server]
+            port = 9000
+
+            [database]
+            url = "postgres://db.example.com/notes"
+
+            [auth]
+            secret = "file-secret"
+            token_precedence = "header"
+        "#;
+
+        let config: Config = Config::from_sources(
+            Some(toml),
+            &vars(&[
+                ("PORT", "9100"),
+                ("SECRET", "env-secret"),
+                ("TOKEN_PRECEDENCE", "cookie"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.server.port, 9100);
+        assert_eq!(config.database.url, "postgres://db.example.com/notes");
+        assert_eq!(config.auth.secret, "env-secret");
+        assert_eq!(config.auth.token_precedence, "cookie");
+    }
+
+    #[test]
+    fn reports_every_invalid_setting_at_once() {
+        let ConfigError(errors) = Config::from_sources(
+            None,
+            &vars(&[
+                ("DATABASE_URL", "postgres://localhost/notes"),
+                ("PORT", "not-a-port"),
+                ("TOKEN_PRECEDENCE", "both"),
+                ("JWT_ALGORITHM", "none"),
+                ("PASSWORD_MIN_LENGTH", "0"),
+                ("MAILER", "pigeon"),
+            ]),
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            errors,
+            vec![
+                "PORT has an invalid value: not-a-port",
+                "SECRET must be set",
+                "TOKEN_PRECEDENCE must be header or cookie",
+                "JWT_ALGORITHM must be HS256, RS256 or EdDSA",
+                "MAILER must be smtp or outbox",
+                "PASSWORD_MIN_LENGTH must be at least 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unreadable_jwt_keys() {
+        let mut env: HashMap<String, String> = required_vars();
+        env.extend(vars(&[
+            ("JWT_ALGORITHM", "EdDSA"),
+            ("JWT_SIGNING_KEY", "/nonexistent/signing.pem"),
+            ("JWT_SIGNING_KID", "2024-12"),
+            ("JWT_VERIFICATION_KEYS_DIR", "/nonexistent"),
+        ]));
+
+        let ConfigError(errors) = Config::from_sources(None, &env).err().unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("JWT_SIGNING_KEY must be a readable file"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_file_settings() {
+        let ConfigError(errors) =
+            Config::from_sources(Some("[server]\nprot = 9000\n"), &required_vars())
+                .err()
+                .unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("invalid configuration file"),
+            "{}",
+            errors[0]
+        );
+    }
+}
@@ -1,13 +1,18 @@
+use super::{config::Config, errors::ApiError, jwt::JwtKeys, mailer::Mailer};
 use actix::{Actor, Addr, SyncContext};
 use diesel::{
     r2d2::{ConnectionManager, Pool},
     PgConnection,
 };
+use std::sync::Arc;
 
 type PgConnectionManagerType = ConnectionManager<PgConnection>;
 
 pub struct AppState {
     pub db: Addr<DbActor>,
+    pub mailer: Arc<dyn Mailer>,
+    pub config: Arc<Config>,
+    pub jwt_keys: Arc<JwtKeys>,
 }
 
 pub struct DbActor(pub Pool<PgConnectionManagerType>);
@@ -25,3 +30,25 @@ pub fn get_pool(db_url: &str) -> Pool<PgConnectionManagerType> {
 
     pool
 }
+
+/// Result of a message handled by `DbActor`.
+pub type DbResult<T> = Result<T, ApiError>;
+
+/// Application state for handler tests. The database is only connected to
+/// when a handler first uses it, so tests that never reach it need no server.
+#[cfg(test)]
+pub fn test_app_state(config: Config) -> AppState {
+    use super::{jwt::load_jwt_keys, mailer::build_mailer};
+    use actix::SyncArbiter;
+
+    let pool: Pool<PgConnectionManagerType> =
+        Pool::builder()
+            .build_unchecked(ConnectionManager::<PgConnection>::new(&config.database.url));
+
+    AppState {
+        db: SyncArbiter::start(1, move || DbActor(pool.clone())),
+        mailer: build_mailer(&config.mail).unwrap(),
+        jwt_keys: Arc::new(load_jwt_keys(&config.jwt, &config.auth.secret).unwrap()),
+        config: Arc::new(config),
+    }
+}
@@ -0,0 +1,193 @@
+use actix::MailboxError;
+use actix_web::{
+    http::{header::RETRY_AFTER, StatusCode},
+    HttpResponse, ResponseError,
+};
+use diesel::{
+    r2d2::PoolError,
+    result::{DatabaseErrorKind, Error as DieselError},
+};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind as JwtErrorKind};
+use std::fmt;
+
+/// Error returned by handlers and actors. It is rendered as an RFC 7807
+/// `application/problem+json` body whose `code` member is stable and meant
+/// for clients to match on, while `detail` is for humans.
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    /// Request body failed validation; `errors` lists every violation.
+    Validation(String, serde_json::Value),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    /// Too many attempts; the client may retry after the given seconds.
+    RateLimited(String, i64),
+    BadGateway(String),
+    ServiceUnavailable(String),
+    Internal(String),
+    Database(DieselError),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Validation(_, _) => "validation_failed",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::RateLimited(_, _) => "rate_limited",
+            ApiError::BadGateway(_) => "upstream_unavailable",
+            ApiError::ServiceUnavailable(_) => "service_unavailable",
+            ApiError::Internal(_) => "internal_error",
+            ApiError::Database(_) => "database_error",
+        }
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            ApiError::BadRequest(detail)
+            | ApiError::Validation(detail, _)
+            | ApiError::Unauthorized(detail)
+            | ApiError::Forbidden(detail)
+            | ApiError::NotFound(detail)
+            | ApiError::Conflict(detail)
+            | ApiError::RateLimited(detail, _)
+            | ApiError::BadGateway(detail)
+            | ApiError::ServiceUnavailable(detail)
+            | ApiError::Internal(detail) => detail.clone(),
+            ApiError::Database(_) => String::from("database error"),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Database(err) => write!(f, "database error: {}", err),
+            _ => write!(f, "{}: {}", self.code(), self.detail()),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) | ApiError::Validation(_, _) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::RateLimited(_, _) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) | ApiError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status: StatusCode = self.status_code();
+
+        if status.is_server_error() {
+            eprintln!("{}", self);
+        }
+
+        let mut problem: serde_json::Value = serde_json::json!({
+            "type": "about:blank",
+            "title": status.canonical_reason().unwrap_or_default(),
+            "status": status.as_u16(),
+            "detail": self.detail(),
+            "code": self.code(),
+        });
+
+        let mut response = HttpResponse::build(status);
+
+        match self {
+            ApiError::Validation(_, errors) => {
+                problem["errors"] = errors.clone();
+            }
+            ApiError::RateLimited(_, retry_after) => {
+                problem["retry_after"] = serde_json::json!(retry_after);
+                response.insert_header((RETRY_AFTER, retry_after.to_string()));
+            }
+            _ => {}
+        }
+
+        response
+            .content_type("application/problem+json")
+            .body(problem.to_string())
+    }
+}
+
+impl From<DieselError> for ApiError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::NotFound => ApiError::NotFound(String::from("resource not found")),
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                ApiError::Conflict(String::from("resource already exists"))
+            }
+            err => ApiError::Database(err),
+        }
+    }
+}
+
+impl From<PoolError> for ApiError {
+    fn from(err: PoolError) -> Self {
+        eprintln!("Database pool: {}", err);
+        ApiError::ServiceUnavailable(String::from("database is unavailable"))
+    }
+}
+
+impl From<MailboxError> for ApiError {
+    fn from(err: MailboxError) -> Self {
+        eprintln!("Database actor: {}", err);
+        ApiError::ServiceUnavailable(String::from("database is unavailable"))
+    }
+}
+
+impl From<JwtError> for ApiError {
+    fn from(err: JwtError) -> Self {
+        match err.kind() {
+            JwtErrorKind::ExpiredSignature => {
+                ApiError::Unauthorized(String::from("token has expired"))
+            }
+            JwtErrorKind::InvalidSignature => {
+                ApiError::Unauthorized(String::from("invalid token signature"))
+            }
+            _ => ApiError::Unauthorized(String::from("invalid token")),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(err: reqwest::Error) -> Self {
+        eprintln!("Upstream request: {}", err);
+        ApiError::BadGateway(String::from("upstream service is unavailable"))
+    }
+}
+
+pub trait ApiResultExt<T> {
+    /// Replaces a generic "resource not found" with a more specific detail.
+    fn not_found(self, detail: impl FnOnce() -> String) -> Result<T, ApiError>;
+    /// Replaces a generic "resource already exists" with a more specific detail.
+    fn conflict(self, detail: impl FnOnce() -> String) -> Result<T, ApiError>;
+}
+
+impl<T> ApiResultExt<T> for Result<T, ApiError> {
+    fn not_found(self, detail: impl FnOnce() -> String) -> Result<T, ApiError> {
+        self.map_err(|err| match err {
+            ApiError::NotFound(_) => ApiError::NotFound(detail()),
+            err => err,
+        })
+    }
+
+    fn conflict(self, detail: impl FnOnce() -> String) -> Result<T, ApiError> {
+        self.map_err(|err| match err {
+            ApiError::Conflict(_) => ApiError::Conflict(detail()),
+            err => err,
+        })
+    }
+}
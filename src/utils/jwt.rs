@@ -1,12 +1,268 @@
-use super::constants;
+use super::{config::JwtConfig, errors::ApiError};
 use actix_web::{FromRequest, HttpMessage};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
 use chrono::{DateTime, Duration, TimeDelta, Utc};
-use jsonwebtoken::errors::Error;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData,
+    Validation,
+};
+use rsa::{
+    pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey,
+};
 use serde::{Deserialize, Serialize};
-use std::future;
+use std::{collections::HashMap, fs, future, path::PathBuf};
 use utoipa::ToSchema;
 
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+pub const REFRESH_TOKEN_DAYS: i64 = 30;
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the 32 byte key.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+pub struct JwtKeys {
+    algorithm: Algorithm,
+    signing_kid: Option<String>,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, DecodingKey>,
+    jwks: Vec<serde_json::Value>,
+}
+
+impl JwtKeys {
+    /// Public verification keys in JWK format. Empty when tokens are signed
+    /// with the shared HS256 secret.
+    pub fn jwks(&self) -> serde_json::Value {
+        serde_json::json!({ "keys": self.jwks })
+    }
+
+    pub fn encode_jwt(
+        &self,
+        email: String,
+        id: i32,
+        role: String,
+        jti: String,
+    ) -> Result<String, Error> {
+        self.encode_claims(
+            email,
+            id,
+            role,
+            jti,
+            Duration::minutes(ACCESS_TOKEN_MINUTES),
+            false,
+        )
+    }
+
+    pub fn encode_mfa_challenge_jwt(
+        &self,
+        email: String,
+        id: i32,
+        role: String,
+    ) -> Result<String, Error> {
+        self.encode_claims(email, id, role, String::new(), Duration::minutes(5), true)
+    }
+
+    fn encode_claims(
+        &self,
+        email: String,
+        id: i32,
+        role: String,
+        jti: String,
+        expire: TimeDelta,
+        mfa_pending: bool,
+    ) -> Result<String, Error> {
+        let now: DateTime<Utc> = Utc::now();
+
+        let claims: Claims = Claims {
+            exp: (now + expire).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            email,
+            id,
+            role,
+            jti,
+            mfa_pending,
+            scopes: None,
+        };
+
+        let mut header: Header = Header::new(self.algorithm);
+        header.kid = self.signing_kid.clone();
+
+        encode(&header, &claims, &self.encoding_key)
+    }
+
+    pub fn decode_jwt(&self, jwt: String) -> Result<TokenData<Claims>, Error> {
+        let kid: String = match self.signing_kid {
+            Some(_) => decode_header(&jwt)?
+                .kid
+                .ok_or_else(|| Error::from(ErrorKind::InvalidToken))?,
+            None => String::new(),
+        };
+
+        let decoding_key: &DecodingKey = self
+            .decoding_keys
+            .get(&kid)
+            .ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+
+        decode(&jwt, decoding_key, &Validation::new(self.algorithm))
+    }
+}
+
+fn pem_body(pem: &str) -> Option<Vec<u8>> {
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+
+    STANDARD.decode(body.trim()).ok()
+}
+
+fn load_verification_key(
+    algorithm: Algorithm,
+    kid: &str,
+    pem: &str,
+) -> Result<(DecodingKey, serde_json::Value), String> {
+    match algorithm {
+        Algorithm::RS256 => {
+            let public_key: RsaPublicKey = RsaPublicKey::from_public_key_pem(pem)
+                .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+                .map_err(|_| format!("JWT verification key {} is not an RSA public key", kid))?;
+
+            let n: String = URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be());
+            let e: String = URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be());
+
+            let decoding_key: DecodingKey = DecodingKey::from_rsa_components(&n, &e)
+                .map_err(|_| format!("JWT verification key {} is invalid", kid))?;
+
+            Ok((
+                decoding_key,
+                serde_json::json!({
+                    "kty": "RSA",
+                    "use": "sig",
+                    "alg": "RS256",
+                    "kid": kid,
+                    "n": n,
+                    "e": e,
+                }),
+            ))
+        }
+        _ => {
+            let der: Vec<u8> = pem_body(pem)
+                .filter(|der| der.len() == 44 && der.starts_with(&ED25519_SPKI_PREFIX))
+                .ok_or_else(|| {
+                    format!("JWT verification key {} is not an Ed25519 public key", kid)
+                })?;
+
+            let x: String = URL_SAFE_NO_PAD.encode(&der[12..]);
+
+            let decoding_key: DecodingKey = DecodingKey::from_ed_components(&x)
+                .map_err(|_| format!("JWT verification key {} is invalid", kid))?;
+
+            Ok((
+                decoding_key,
+                serde_json::json!({
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "use": "sig",
+                    "alg": "EdDSA",
+                    "kid": kid,
+                    "x": x,
+                }),
+            ))
+        }
+    }
+}
+
+/// Loads the signing and verification keys, or describes the first key file
+/// that is missing, unreadable or of the wrong type. `Config::validate` runs
+/// this too, so it only fails at startup for an unvalidated configuration.
+pub fn load_jwt_keys(config: &JwtConfig, secret: &str) -> Result<JwtKeys, String> {
+    let algorithm: Algorithm = match config.algorithm.as_str() {
+        "RS256" => Algorithm::RS256,
+        "EdDSA" => Algorithm::EdDSA,
+        _ => {
+            return Ok(JwtKeys {
+                algorithm: Algorithm::HS256,
+                signing_kid: None,
+                encoding_key: EncodingKey::from_secret(secret.as_ref()),
+                decoding_keys: HashMap::from([(
+                    String::new(),
+                    DecodingKey::from_secret(secret.as_ref()),
+                )]),
+                jwks: Vec::new(),
+            });
+        }
+    };
+
+    let (Some(signing_key_path), Some(signing_kid), Some(verification_keys_dir)) = (
+        config.signing_key.as_deref(),
+        config.signing_kid.clone(),
+        config.verification_keys_dir.as_deref(),
+    ) else {
+        return Err(String::from(
+            "JWT_SIGNING_KEY, JWT_SIGNING_KID and JWT_VERIFICATION_KEYS_DIR must be set when JWT_ALGORITHM is RS256 or EdDSA",
+        ));
+    };
+
+    let signing_key: Vec<u8> = fs::read(signing_key_path)
+        .map_err(|err| format!("JWT_SIGNING_KEY must be a readable file: {}", err))?;
+
+    let encoding_key: EncodingKey = match algorithm {
+        Algorithm::RS256 => EncodingKey::from_rsa_pem(&signing_key),
+        _ => EncodingKey::from_ed_pem(&signing_key),
+    }
+    .map_err(|_| {
+        String::from("JWT_SIGNING_KEY must be a PEM private key matching JWT_ALGORITHM")
+    })?;
+
+    let mut decoding_keys: HashMap<String, DecodingKey> = HashMap::new();
+    let mut jwks: Vec<serde_json::Value> = Vec::new();
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(verification_keys_dir)
+        .map_err(|err| {
+            format!(
+                "JWT_VERIFICATION_KEYS_DIR must be a readable directory: {}",
+                err
+            )
+        })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "pem"))
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        let kid: String = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| String::from("JWT verification key file names must be valid UTF-8"))?
+            .to_string();
+        let pem: String = fs::read_to_string(&path)
+            .map_err(|err| format!("unable to read JWT verification key {}: {}", kid, err))?;
+
+        let (decoding_key, jwk) = load_verification_key(algorithm, &kid, &pem)?;
+        decoding_keys.insert(kid, decoding_key);
+        jwks.push(jwk);
+    }
+
+    if !decoding_keys.contains_key(&signing_kid) {
+        return Err(format!(
+            "JWT_VERIFICATION_KEYS_DIR must contain {}.pem, the public key of JWT_SIGNING_KEY",
+            signing_kid
+        ));
+    }
+
+    Ok(JwtKeys {
+        algorithm,
+        signing_kid: Some(signing_kid),
+        encoding_key,
+        decoding_keys,
+        jwks,
+    })
+}
+
 #[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct Claims {
     pub exp: usize,
@@ -14,50 +270,26 @@ pub struct Claims {
     pub email: String,
     pub id: i32,
     pub role: String,
+    pub jti: String,
+    #[serde(default)]
+    pub mfa_pending: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
 }
 
 impl FromRequest for Claims {
-    type Error = actix_web::Error;
+    type Error = ApiError;
     type Future = future::Ready<Result<Self, Self::Error>>;
 
     fn from_request(
         req: &actix_web::HttpRequest,
         _payload: &mut actix_web::dev::Payload,
-    ) -> future::Ready<Result<Claims, actix_web::Error>> {
+    ) -> future::Ready<Result<Claims, ApiError>> {
         match req.extensions().get::<Claims>() {
             Some(claim) => future::ready(Ok(claim.clone())),
-            None => future::ready(Err(actix_web::error::ErrorBadRequest("Bad Claims"))),
+            None => future::ready(Err(ApiError::Unauthorized(String::from(
+                "unauthorized access",
+            )))),
         }
     }
 }
-
-pub fn encode_jwt(email: String, id: i32, role: String) -> Result<String, Error> {
-    let now: DateTime<Utc> = Utc::now();
-    let expire: TimeDelta = Duration::hours(24);
-
-    let claims: Claims = Claims {
-        exp: (now + expire).timestamp() as usize,
-        iat: now.timestamp() as usize,
-        email,
-        id,
-        role,
-    };
-
-    let secret: String = (*constants::SECRET).clone();
-
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_ref()),
-    )
-}
-
-pub fn decode_jwt(jwt: String) -> Result<TokenData<Claims>, Error> {
-    let secret = (*constants::SECRET).clone();
-    let claim_data = decode(
-        &jwt,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &Validation::default(),
-    );
-    claim_data
-}
@@ -0,0 +1,136 @@
+use super::config::MailConfig;
+use chrono::Utc;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, Message, SmtpTransport,
+    Transport,
+};
+use std::{error::Error, fs, path::PathBuf, sync::Arc};
+
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+pub struct SmtpMailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>,
+        from: &str,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut builder = SmtpTransport::starttls_relay(host)?;
+
+        if let Some(port) = port {
+            builder = builder.port(port);
+        }
+
+        if let (Some(username), Some(password)) = (username, password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpMailer {
+            transport: builder.build(),
+            from: from.parse()?,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &Email) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let message: Message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse()?)
+            .subject(email.subject.clone())
+            .body(email.body.clone())?;
+
+        self.transport.send(&message)?;
+
+        Ok(())
+    }
+}
+
+/// Writes every email to a directory instead of sending it, so that local
+/// development and tests can read the messages without network access.
+pub struct OutboxMailer {
+    dir: PathBuf,
+    from: String,
+}
+
+impl OutboxMailer {
+    pub fn new(dir: impl Into<PathBuf>, from: &str) -> Self {
+        OutboxMailer {
+            dir: dir.into(),
+            from: from.to_string(),
+        }
+    }
+}
+
+impl Mailer for OutboxMailer {
+    fn send(&self, email: &Email) -> Result<(), Box<dyn Error + Send + Sync>> {
+        fs::create_dir_all(&self.dir)?;
+
+        let file_name: String = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S%f"),
+            rand::random::<u32>()
+        );
+
+        let contents: String = format!(
+            "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            self.from, email.to, email.subject, email.body
+        );
+
+        fs::write(self.dir.join(file_name), contents)?;
+
+        Ok(())
+    }
+}
+
+/// Builds the configured mailer, or describes why the mail settings cannot
+/// work. `Config::validate` runs this too.
+pub fn build_mailer(config: &MailConfig) -> Result<Arc<dyn Mailer>, String> {
+    let from: String = config.from.clone();
+
+    match config.mailer.as_str() {
+        "smtp" => {
+            let host: String = config
+                .smtp_host
+                .clone()
+                .ok_or_else(|| String::from("SMTP_HOST must be set when MAILER is smtp"))?;
+
+            let mailer: SmtpMailer = SmtpMailer::new(
+                &host,
+                config.smtp_port,
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+                &from,
+            )
+            .map_err(|err| format!("unable to build the SMTP mailer: {}", err))?;
+
+            Ok(Arc::new(mailer))
+        }
+        "outbox" => Ok(Arc::new(OutboxMailer::new(
+            config.outbox_dir.clone(),
+            &from,
+        ))),
+        other => Err(format!("MAILER must be smtp or outbox, got {}", other)),
+    }
+}
+
+pub async fn deliver(mailer: Arc<dyn Mailer>, email: Email) -> Result<(), String> {
+    match actix_web::web::block(move || mailer.send(&email).map_err(|err| err.to_string())).await {
+        Ok(result) => result,
+        Err(err) => Err(err.to_string()),
+    }
+}
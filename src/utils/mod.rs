@@ -1,3 +1,10 @@
-pub mod constants;
+pub mod config;
 pub mod db;
+pub mod errors;
+pub mod html;
 pub mod jwt;
+pub mod mailer;
+pub mod oidc;
+pub mod password_policy;
+pub mod passwords;
+pub mod tokens;
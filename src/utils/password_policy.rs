@@ -0,0 +1,204 @@
+use super::config::PasswordConfig;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::{fs, io, path::Path};
+use utoipa::ToSchema;
+use zxcvbn::{zxcvbn, Entropy};
+
+/// Usernames and email local parts shorter than this are too common as
+/// substrings to forbid them inside passwords.
+const MIN_IDENTIFIER_LENGTH: usize = 4;
+
+#[derive(Serialize, ToSchema)]
+pub struct PasswordViolation {
+    #[schema(example = "too_short")]
+    pub code: &'static str,
+    #[schema(example = "password must be at least 8 characters long")]
+    pub message: String,
+}
+
+/// Looks the password up in a local copy of a breached-password corpus laid
+/// out like the Pwned Passwords range API: one `<PREFIX>.txt` file per
+/// five-character SHA-1 prefix, each line holding `<SUFFIX>:<COUNT>`.
+fn is_breached(password: &str, dir: &str) -> io::Result<bool> {
+    let hash: String = format!("{:X}", Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = hash.split_at(5);
+
+    let contents: String = match fs::read_to_string(Path::new(dir).join(format!("{}.txt", prefix)))
+    {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    Ok(contents.lines().any(|line| {
+        line.split(':')
+            .next()
+            .is_some_and(|candidate| candidate.trim().eq_ignore_ascii_case(suffix))
+    }))
+}
+
+/// Checks a password against the configured policy and returns every rule it
+/// breaks, or an empty list when it is acceptable.
+pub fn validate_password(
+    config: &PasswordConfig,
+    password: &str,
+    username: &str,
+    email: &str,
+) -> Vec<PasswordViolation> {
+    let mut violations: Vec<PasswordViolation> = Vec::new();
+    let min_length: usize = config.min_length;
+
+    if password.chars().count() < min_length {
+        violations.push(PasswordViolation {
+            code: "too_short",
+            message: format!("password must be at least {} characters long", min_length),
+        });
+    }
+
+    let lowercase_password: String = password.to_lowercase();
+    let email_local_part: &str = email.split('@').next().unwrap_or_default();
+
+    if username.chars().count() >= MIN_IDENTIFIER_LENGTH
+        && lowercase_password.contains(&username.to_lowercase())
+    {
+        violations.push(PasswordViolation {
+            code: "contains_username",
+            message: String::from("password must not contain the username"),
+        });
+    }
+
+    if email_local_part.chars().count() >= MIN_IDENTIFIER_LENGTH
+        && lowercase_password.contains(&email_local_part.to_lowercase())
+    {
+        violations.push(PasswordViolation {
+            code: "contains_email",
+            message: String::from("password must not contain the email address"),
+        });
+    }
+
+    if !password.is_empty() {
+        let entropy: Entropy = zxcvbn(password, &[username, email]);
+
+        if u8::from(entropy.score()) < config.min_score {
+            let feedback: String = entropy
+                .feedback()
+                .and_then(|feedback| feedback.warning())
+                .map(|warning| format!(": {}", warning))
+                .unwrap_or_default();
+
+            violations.push(PasswordViolation {
+                code: "too_weak",
+                message: format!("password is too easy to guess{}", feedback),
+            });
+        }
+    }
+
+    if let Some(dir) = config.breached_passwords_dir.as_deref() {
+        match is_breached(password, dir) {
+            Ok(true) => violations.push(PasswordViolation {
+                code: "breached",
+                message: String::from("password has appeared in a data breach"),
+            }),
+            Ok(false) => {}
+            Err(err) => eprintln!(
+                "Password Policy: unable to read breached passwords: {}",
+                err
+            ),
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRONG_PASSWORD: &str = "Tangerine-Orbit-Glacier-5521";
+
+    fn codes(
+        config: &PasswordConfig,
+        password: &str,
+        username: &str,
+        email: &str,
+    ) -> Vec<&'static str> {
+        validate_password(config, password, username, email)
+            .into_iter()
+            .map(|violation| violation.code)
+            .collect()
+    }
+
+    #[test]
+    fn strong_passwords_pass_the_default_policy() {
+        let config: PasswordConfig = PasswordConfig::default();
+
+        assert!(codes(&config, STRONG_PASSWORD, "alice", "alice@example.com").is_empty());
+    }
+
+    #[test]
+    fn short_usernames_and_email_local_parts_are_not_matched() {
+        let config: PasswordConfig = PasswordConfig::default();
+
+        assert!(codes(&config, STRONG_PASSWORD, "bit", "or@example.com").is_empty());
+        assert_eq!(
+            codes(&config, STRONG_PASSWORD, "orbit", "or@example.com"),
+            ["contains_username"]
+        );
+        assert_eq!(
+            codes(&config, STRONG_PASSWORD, "bit", "GLACIER@example.com"),
+            ["contains_email"]
+        );
+    }
+
+    #[test]
+    fn minimum_length_of_one_rejects_only_the_empty_password() {
+        let config: PasswordConfig = PasswordConfig {
+            min_length: 1,
+            min_score: 0,
+            ..PasswordConfig::default()
+        };
+
+        assert_eq!(
+            codes(&config, "", "alice", "alice@example.com"),
+            ["too_short"]
+        );
+        assert!(codes(&config, "x", "alice", "alice@example.com").is_empty());
+    }
+
+    #[test]
+    fn passwords_in_the_breached_corpus_are_rejected() {
+        let dir = std::env::temp_dir().join(format!("breached-{}", rand::random::<u32>()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let hash: String = format!("{:X}", Sha1::digest(STRONG_PASSWORD.as_bytes()));
+        let (prefix, suffix) = hash.split_at(5);
+        fs::write(
+            dir.join(format!("{}.txt", prefix)),
+            format!(
+                "0000000000000000000000000000000000A:3\r\n{}:12\r\n",
+                suffix.to_lowercase()
+            ),
+        )
+        .unwrap();
+
+        let config: PasswordConfig = PasswordConfig {
+            breached_passwords_dir: Some(dir.to_str().unwrap().to_string()),
+            ..PasswordConfig::default()
+        };
+
+        assert_eq!(
+            codes(&config, STRONG_PASSWORD, "alice", "alice@example.com"),
+            ["breached"]
+        );
+        assert!(codes(
+            &config,
+            "Quartz-Meadow-Lantern-8830",
+            "alice",
+            "alice@example.com"
+        )
+        .is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
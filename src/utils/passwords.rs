@@ -0,0 +1,58 @@
+use super::config::PasswordConfig;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+
+fn argon2(config: &PasswordConfig) -> Result<Argon2<'static>, String> {
+    let params: Params = config.argon2_params().map_err(|err| err.to_string())?;
+
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// Hashes a password with Argon2id using the configured parameters and
+/// returns it in PHC string format.
+pub fn hash_password(config: &PasswordConfig, password: &str) -> Result<String, String> {
+    let salt: SaltString = SaltString::generate(&mut OsRng);
+
+    argon2(config)?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| err.to_string())
+}
+
+/// Verifies a password against either a PHC-format Argon2 hash or a legacy
+/// bcrypt hash.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    if password_hash.starts_with("$argon2") {
+        return match PasswordHash::new(password_hash) {
+            Ok(parsed_hash) => Argon2::default()
+                .verify_password(password.as_bytes(), &parsed_hash)
+                .is_ok(),
+            Err(_) => false,
+        };
+    }
+
+    bcrypt::verify(password, password_hash).unwrap_or(false)
+}
+
+/// Whether a hash that just verified successfully should be replaced, because
+/// it is a legacy bcrypt hash or was made with different Argon2 parameters.
+pub fn needs_rehash(config: &PasswordConfig, password_hash: &str) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(password_hash) else {
+        return true;
+    };
+
+    if parsed_hash.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    match Params::try_from(&parsed_hash) {
+        Ok(params) => {
+            params.m_cost() != config.argon2_memory_kib
+                || params.t_cost() != config.argon2_iterations
+                || params.p_cost() != config.argon2_parallelism
+        }
+        Err(_) => true,
+    }
+}
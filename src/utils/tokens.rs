@@ -0,0 +1,57 @@
+use hmac::{Hmac, Mac};
+use rand::{rngs::ThreadRng, Rng};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn generate_opaque_token() -> String {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let data_byte: [u8; 32] = rng.gen();
+
+    base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &data_byte).to_lowercase()
+}
+
+pub fn generate_recovery_code() -> String {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let data_byte: [u8; 7] = rng.gen();
+    let code: String =
+        base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &data_byte).to_lowercase();
+
+    format!("{}-{}", &code[..5], &code[5..10])
+}
+
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|character| character.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn signature_mac(secret: &str, payload: &str) -> HmacSha256 {
+    let mut mac: HmacSha256 =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+pub fn sign_payload(secret: &str, payload: &str) -> String {
+    let signature = signature_mac(secret, payload).finalize().into_bytes();
+
+    base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &signature).to_lowercase()
+}
+
+pub fn verify_payload_signature(secret: &str, payload: &str, signature: &str) -> bool {
+    match base32::decode(
+        base32::Alphabet::Rfc4648 { padding: false },
+        &signature.to_uppercase(),
+    ) {
+        Some(signature) => signature_mac(secret, payload)
+            .verify_slice(&signature)
+            .is_ok(),
+        None => false,
+    }
+}